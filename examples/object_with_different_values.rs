@@ -13,10 +13,11 @@ fn main() {
         {"invalid expression": 1+2}
     "#;
 
-    let mut json_parser = Parser::new(json2).expect("failed to initialize the parser");
-    let parsed = match json_parser.parse_root() {
-        Ok(d) => d,
-        Err(e) => panic!("{e:?}"),
-    };
-    println!("{parsed:?}");
+    for input in [json, json2] {
+        let mut json_parser = Parser::new(input).expect("failed to initialize the parser");
+        match json_parser.parse_root() {
+            Ok(parsed) => println!("{parsed:?}"),
+            Err(e) => println!("{e}"),
+        }
+    }
 }
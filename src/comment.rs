@@ -0,0 +1,132 @@
+use crate::position::Span;
+
+/// One step on the way from the root to a value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// A member of an object.
+    Key(String),
+    /// An element of an array.
+    Index(usize),
+}
+
+/// Where a comment sits relative to the value it is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// On the lines before the value, or before the key of an object member.
+    Before,
+    /// On the same line as the end of the value, after it.
+    After,
+    /// Inside an array or object, after its last element and on a line of
+    /// its own, or in an empty array or object.
+    Inside,
+}
+
+/// A comment attached to the nearest value, collected when
+/// [`ParserOptions::attach_comments`](crate::options::ParserOptions::attach_comments)
+/// is on.
+///
+/// A comment that follows a value on the same line belongs after that
+/// value. Any other comment belongs before the next value, or inside the
+/// enclosing array or object if no value follows. Comments after the root
+/// value belong after it.
+///
+/// ```
+/// use jimson::comment::{PathSegment, Placement};
+/// use jimson::options::{Dialect, ParserOptions};
+/// use jimson::parser::Parser;
+///
+/// let input = "{\n  // the port\n  \"port\": 80 // not 8080\n}";
+/// let options = ParserOptions::new()
+///     .dialect(Dialect::Jsonc)
+///     .attach_comments(true);
+/// let mut parser = Parser::with_options(input, options).unwrap();
+/// parser.parse_root().unwrap();
+///
+/// let comments = parser.comments();
+/// assert_eq!(comments[0].text, "// the port");
+/// assert_eq!(comments[0].path, [PathSegment::Key("port".into())]);
+/// assert_eq!(comments[0].placement, Placement::Before);
+/// assert_eq!(comments[1].placement, Placement::After);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// The comment exactly as written, including the `//` or the `/*` and
+    /// `*/`.
+    pub text: String,
+    /// Where the comment appears in the input.
+    pub span: Span,
+    /// The keys and indices leading from the root to the value the comment
+    /// is attached to. Empty for the root itself.
+    pub path: Vec<PathSegment>,
+    pub placement: Placement,
+}
+
+/// Attaches comments to values as the parser walks the document.
+#[derive(Debug, Default)]
+pub(crate) struct Attacher {
+    pub(crate) comments: Vec<Comment>,
+    /// Comments seen since the last value that have not been attached yet.
+    pending: Vec<(String, Span)>,
+    /// The path of the value being parsed.
+    path: Vec<PathSegment>,
+    /// The value that ended last and the line it ended on, while a comment
+    /// on that line can still trail it.
+    trailing: Option<(Vec<PathSegment>, usize)>,
+}
+
+impl Attacher {
+    /// Takes in a comment that the lexer has just produced.
+    pub(crate) fn comment(&mut self, text: &str, span: Span) {
+        match &self.trailing {
+            Some((path, line)) if *line == span.start.line => {
+                let path = path.clone();
+                self.attach(text.to_owned(), span, path, Placement::After);
+            }
+            _ => self.pending.push((text.to_owned(), span)),
+        }
+    }
+
+    /// Steps into a member or element.
+    pub(crate) fn enter(&mut self, segment: PathSegment) {
+        self.path.push(segment);
+    }
+
+    /// Steps back out of a member or element.
+    pub(crate) fn leave(&mut self) {
+        self.path.pop();
+    }
+
+    /// Marks the start of the value, or object member, at the current path.
+    pub(crate) fn begin_value(&mut self) {
+        self.trailing = None;
+        self.flush(self.path.clone(), Placement::Before);
+    }
+
+    /// Marks the end of the value at the current path on line `line`.
+    pub(crate) fn end_value(&mut self, line: usize, container: bool) {
+        if container {
+            self.flush(self.path.clone(), Placement::Inside);
+        }
+        self.trailing = Some((self.path.clone(), line));
+    }
+
+    /// Attaches whatever follows the root value, once the input is done.
+    pub(crate) fn finish(&mut self) {
+        self.flush(Vec::new(), Placement::After);
+    }
+
+    fn flush(&mut self, path: Vec<PathSegment>, placement: Placement) {
+        for (text, span) in std::mem::take(&mut self.pending) {
+            self.attach(text, span, path.clone(), placement);
+        }
+    }
+
+    fn attach(&mut self, text: String, span: Span, path: Vec<PathSegment>, placement: Placement) {
+        self.comments.push(Comment {
+            text,
+            span,
+            path,
+            placement,
+        });
+    }
+}
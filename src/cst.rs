@@ -0,0 +1,511 @@
+use crate::comment::PathSegment;
+use crate::errors::{EditError, JsonError};
+use crate::lexer::Lexer;
+use crate::options::ParserOptions;
+use crate::parser::{JsonValue, Parser};
+use crate::position::Span;
+use crate::serializer;
+use crate::token::TokenType;
+
+use std::ops::Range;
+
+/// What a [`Node`] of the syntax tree stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// The whole input: the root value with the whitespace and comments
+    /// around it.
+    Document,
+    Object,
+    /// A key, the colon and the value of an object member.
+    Member,
+    Array,
+    /// A string, number, boolean or null.
+    Scalar,
+}
+
+/// A token of the syntax tree. Its text is the part of
+/// [`Document::text`] covered by its span.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CstToken {
+    pub token_type: TokenType,
+    pub span: Span,
+}
+
+/// A child of a [`Node`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Element {
+    Node(Node),
+    Token(CstToken),
+}
+
+impl Element {
+    pub fn span(&self) -> Span {
+        match self {
+            Element::Node(node) => node.span,
+            Element::Token(tok) => tok.span,
+        }
+    }
+
+    fn is_token(&self, token_type: TokenType) -> bool {
+        matches!(self, Element::Token(tok) if tok.token_type == token_type)
+    }
+}
+
+/// A node of the syntax tree.
+///
+/// The children of a node cover its span without gaps, whitespace and
+/// comments included, so the tree accounts for every byte of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    kind: NodeKind,
+    span: Span,
+    children: Vec<Element>,
+    /// The decoded key of a member.
+    key: Option<String>,
+}
+
+impl Node {
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn children(&self) -> &[Element] {
+        &self.children
+    }
+
+    /// The decoded key, for a `Member` node.
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// The value of a `Member` node, or the root value of a `Document`.
+    pub fn value(&self) -> Option<&Node> {
+        match self.kind {
+            NodeKind::Member | NodeKind::Document => self.nodes().next(),
+            _ => None,
+        }
+    }
+
+    /// The members of an object or the elements of an array, in order.
+    pub fn items(&self) -> impl Iterator<Item = &Node> {
+        let container = matches!(self.kind, NodeKind::Object | NodeKind::Array);
+        self.nodes().filter(move |_| container)
+    }
+
+    /// The member of an object with the given key. If the key appears more
+    /// than once, the last member wins, as it does in the parser by default.
+    pub fn member(&self, key: &str) -> Option<&Node> {
+        match self.kind {
+            NodeKind::Object => self.items().filter(|m| m.key() == Some(key)).last(),
+            _ => None,
+        }
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|child| match child {
+            Element::Node(node) => Some(node),
+            Element::Token(_) => None,
+        })
+    }
+
+    /// The position in `children` of the `index`th child node.
+    fn child_position(&self, index: usize) -> Option<usize> {
+        self.children
+            .iter()
+            .enumerate()
+            .filter(|(_, child)| matches!(child, Element::Node(_)))
+            .nth(index)
+            .map(|(i, _)| i)
+    }
+}
+
+/// A lossless concrete syntax tree of a JSON document.
+///
+/// The document keeps the input text as it is, and the tree keeps every
+/// token of it, whitespace and comments included. The editing methods
+/// rewrite only the bytes of the value they change, so the formatting of
+/// the rest of the document survives.
+///
+/// ```
+/// use jimson::{comment::PathSegment, cst::Document, parser::JsonValue};
+///
+/// let mut doc = Document::parse("{\n  \"name\": \"jimson\",\n  \"version\": \"0.1.0\"\n}\n").unwrap();
+/// let version = [PathSegment::Key("version".into())];
+/// doc.set(&version, &JsonValue::String("0.2.0".into())).unwrap();
+/// assert_eq!(doc.text(), "{\n  \"name\": \"jimson\",\n  \"version\": \"0.2.0\"\n}\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Document {
+    text: String,
+    root: Node,
+    options: ParserOptions,
+}
+
+impl Document {
+    /// Parses `text` as strict JSON.
+    pub fn parse(text: impl Into<String>) -> Result<Self, JsonError> {
+        Self::parse_with_options(text, ParserOptions::default())
+    }
+
+    /// Parses `text` with the given dialect and limits.
+    pub fn parse_with_options(
+        text: impl Into<String>,
+        options: ParserOptions,
+    ) -> Result<Self, JsonError> {
+        let text = text.into();
+        let root = build(&text, &options)?;
+        Ok(Document {
+            text,
+            root,
+            options,
+        })
+    }
+
+    /// The text of the document, including all edits so far.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The `Document` node at the root of the tree.
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    /// The text covered by `node`.
+    pub fn node_text(&self, node: &Node) -> &str {
+        &self.text[node.span.range()]
+    }
+
+    /// Parses the document into a value.
+    pub fn to_value(&self) -> Result<JsonValue, JsonError> {
+        Parser::with_options(&self.text, self.options.clone())?.parse_root()
+    }
+
+    /// The value at `path`, or `None` if there is none.
+    pub fn get(&self, path: &[PathSegment]) -> Option<&Node> {
+        let mut node = self.root.value()?;
+        for segment in path {
+            node = match segment {
+                PathSegment::Key(key) => node.member(key)?.value()?,
+                PathSegment::Index(index) => match node.kind {
+                    NodeKind::Array => node.items().nth(*index)?,
+                    _ => return None,
+                },
+            };
+        }
+        Some(node)
+    }
+
+    /// Replaces the value at `path` with `value`, written as compact JSON.
+    pub fn set(&mut self, path: &[PathSegment], value: &JsonValue) -> Result<(), EditError> {
+        let range = self.get(path).ok_or(EditError::PathNotFound)?.span.range();
+        let text = serializer::to_string(value)?;
+        self.splice(range, &text)
+    }
+
+    /// Adds a member to the end of the object at `path`. The new member
+    /// copies the indentation and the spacing around the colon of the
+    /// member before it.
+    pub fn insert_member(
+        &mut self,
+        path: &[PathSegment],
+        key: &str,
+        value: &JsonValue,
+    ) -> Result<(), EditError> {
+        let object = self.get(path).ok_or(EditError::PathNotFound)?;
+        if object.kind != NodeKind::Object {
+            return Err(EditError::NotAnObject);
+        }
+        if object.member(key).is_some() {
+            return Err(EditError::KeyExists);
+        }
+        let value = serializer::to_string(value)?;
+        let key = serializer::to_string(&JsonValue::String(key.to_owned()))?;
+
+        let count = object.items().count();
+        let Some(last) = count.checked_sub(1).and_then(|i| object.child_position(i)) else {
+            // An empty object: put the member right after the `{`.
+            let at = object.span.start.offset + 1;
+            return self.splice(at..at, &format!("{}: {}", key, value));
+        };
+        let Element::Node(member) = &object.children[last] else {
+            unreachable!("child_position only finds nodes");
+        };
+        let colon = match member.value() {
+            Some(value) => {
+                let key_end = member.children[0].span().end.offset;
+                let colon = &self.text[key_end..value.span.start.offset];
+                if colon.trim() == ":" {
+                    colon.to_owned()
+                } else {
+                    ": ".to_owned()
+                }
+            }
+            None => ": ".to_owned(),
+        };
+        let indent = self.indent_before(object, last);
+        let member_text = format!("{}{}{}", key, colon, value);
+        let (at, text) = match trailing_comma(object, last) {
+            Some(comma) => (comma.end.offset, format!("{}{},", indent, member_text)),
+            None => (
+                member.span.end.offset,
+                format!(",{}{}", indent, member_text),
+            ),
+        };
+        self.splice(at..at, &text)
+    }
+
+    /// Removes the member with the given key from the object at `path`,
+    /// along with its comma and any comment on the same line after it.
+    pub fn remove_member(&mut self, path: &[PathSegment], key: &str) -> Result<(), EditError> {
+        let object = self.get(path).ok_or(EditError::PathNotFound)?;
+        if object.kind != NodeKind::Object {
+            return Err(EditError::NotAnObject);
+        }
+        let index = object
+            .items()
+            .position(|member| member.key() == Some(key))
+            .ok_or(EditError::PathNotFound)?;
+        let range = removal_range(object, index);
+        self.splice(range, "")
+    }
+
+    /// Removes the element at `index` from the array at `path`, along with
+    /// its comma and any comment on the same line after it.
+    pub fn remove_element(&mut self, path: &[PathSegment], index: usize) -> Result<(), EditError> {
+        let array = self.get(path).ok_or(EditError::PathNotFound)?;
+        if array.kind != NodeKind::Array {
+            return Err(EditError::NotAnArray);
+        }
+        if index >= array.items().count() {
+            return Err(EditError::PathNotFound);
+        }
+        let range = removal_range(array, index);
+        self.splice(range, "")
+    }
+
+    /// The line break and indentation to put before a new item after the
+    /// child at `position` of `container`, copied from the space before
+    /// that child.
+    fn indent_before(&self, container: &Node, position: usize) -> String {
+        let previous = container.children[..position]
+            .iter()
+            .rev()
+            .find(|child| !is_trivia(child))
+            .expect("containers start with a delimiter");
+        let gap = &self.text
+            [previous.span().end.offset..container.children[position].span().start.offset];
+        match gap.rfind('\n') {
+            Some(i) => {
+                let newline = if gap[..i].ends_with('\r') {
+                    "\r\n"
+                } else {
+                    "\n"
+                };
+                let indent: String = gap[i + 1..]
+                    .chars()
+                    .take_while(|ch| *ch == ' ' || *ch == '\t')
+                    .collect();
+                format!("{}{}", newline, indent)
+            }
+            // Copy the space after a comma as is; the space after the
+            // opening delimiter says little about it.
+            None if previous.is_token(TokenType::Comma) && !gap.contains('/') => gap.to_owned(),
+            None => " ".to_owned(),
+        }
+    }
+
+    /// Replaces `range` of the text and rebuilds the tree. If the new text
+    /// breaks one of the parser's limits the document is left as it was.
+    fn splice(&mut self, range: Range<usize>, text: &str) -> Result<(), EditError> {
+        let mut edited = self.text.clone();
+        edited.replace_range(range, text);
+        self.root = build(&edited, &self.options).map_err(EditError::Limit)?;
+        self.text = edited;
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+fn is_trivia(child: &Element) -> bool {
+    child.is_token(TokenType::Whitespace) || child.is_token(TokenType::Comment)
+}
+
+/// The comma after the child at `position` of `container`, if there is one.
+fn trailing_comma(container: &Node, position: usize) -> Option<Span> {
+    container.children[position + 1..]
+        .iter()
+        .find(|child| !is_trivia(child))
+        .filter(|child| child.is_token(TokenType::Comma))
+        .map(Element::span)
+}
+
+/// The bytes to delete to remove the `index`th item of `container`.
+///
+/// An item is removed together with the comma after it and any comments on
+/// the same line, up to the next item, so the next item takes over its
+/// indentation. The last item is instead removed from the end of the one
+/// before it, taking the comma in between.
+fn removal_range(container: &Node, index: usize) -> Range<usize> {
+    let count = container.items().count();
+    let position = container.child_position(index).expect("index is in range");
+    let item = container.children[position].span();
+
+    // The end of the item, its comma and any comments on its line.
+    let mut end = item.end.offset;
+    let mut next = position + 1;
+    for child in &container.children[position + 1..] {
+        match child {
+            Element::Token(tok) if tok.token_type == TokenType::Comma => end = tok.span.end.offset,
+            Element::Token(tok)
+                if tok.token_type == TokenType::Comment && tok.span.start.line == item.end.line =>
+            {
+                end = tok.span.end.offset
+            }
+            Element::Token(tok)
+                if tok.token_type == TokenType::Whitespace
+                    && tok.span.end.line == item.end.line => {}
+            _ => break,
+        }
+        next += 1;
+    }
+
+    if index + 1 < count {
+        // Up to where the next item, or a comment on a line before it,
+        // starts.
+        let resume = container.children[next..]
+            .iter()
+            .find(|child| !child.is_token(TokenType::Whitespace))
+            .expect("another item follows");
+        item.start.offset..resume.span().start.offset
+    } else if index > 0 {
+        let previous = container
+            .child_position(index - 1)
+            .expect("index is in range");
+        container.children[previous].span().end.offset..end
+    } else {
+        item.start.offset..end
+    }
+}
+
+/// Validates `text` with the parser, then builds its syntax tree.
+fn build(text: &str, options: &ParserOptions) -> Result<Node, JsonError> {
+    Parser::with_options(text, options.clone())?.parse_root()?;
+    let lexer = Lexer::new(text)?
+        .with_dialect(options.dialect)
+        .with_trivia(true);
+    let mut builder = Builder {
+        tokens: lexer.peekable(),
+    };
+
+    let mut children = Vec::new();
+    builder.trivia(&mut children)?;
+    children.push(Element::Node(builder.value()?));
+    builder.trivia(&mut children)?;
+    // With `allow_trailing_content` there may be more; keep it as tokens.
+    while let Some(tok) = builder.next()? {
+        children.push(Element::Token(tok));
+    }
+    Ok(node(NodeKind::Document, children, None))
+}
+
+/// Builds the tree from the tokens of input that the parser has already
+/// accepted.
+struct Builder<'a> {
+    tokens: std::iter::Peekable<Lexer<'a>>,
+}
+
+impl<'a> Builder<'a> {
+    fn next(&mut self) -> Result<Option<CstToken>, JsonError> {
+        Ok(self.tokens.next().transpose()?.map(|tok| CstToken {
+            token_type: tok.token_type,
+            span: tok.span,
+        }))
+    }
+
+    fn peek_type(&mut self) -> Option<TokenType> {
+        match self.tokens.peek() {
+            Some(Ok(tok)) => Some(tok.token_type.clone()),
+            _ => None,
+        }
+    }
+
+    /// Moves whitespace and comments into `children`.
+    fn trivia(&mut self, children: &mut Vec<Element>) -> Result<(), JsonError> {
+        while let Some(TokenType::Whitespace | TokenType::Comment) = self.peek_type() {
+            children.extend(self.next()?.map(Element::Token));
+        }
+        Ok(())
+    }
+
+    fn token(&mut self, children: &mut Vec<Element>) -> Result<(), JsonError> {
+        children.extend(self.next()?.map(Element::Token));
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Node, JsonError> {
+        let (kind, closer) = match self.peek_type() {
+            Some(TokenType::Lbrace) => (NodeKind::Object, TokenType::Rbrace),
+            Some(TokenType::LSqBracket) => (NodeKind::Array, TokenType::RSqbracket),
+            _ => {
+                let mut children = Vec::new();
+                self.token(&mut children)?;
+                return Ok(node(NodeKind::Scalar, children, None));
+            }
+        };
+        let mut children = Vec::new();
+        self.token(&mut children)?;
+        loop {
+            self.trivia(&mut children)?;
+            match self.peek_type() {
+                Some(tok) if tok == closer => break,
+                Some(TokenType::Comma) => self.token(&mut children)?,
+                Some(_) if kind == NodeKind::Object => children.push(Element::Node(self.member()?)),
+                Some(_) => children.push(Element::Node(self.value()?)),
+                None => break,
+            }
+        }
+        self.token(&mut children)?;
+        Ok(node(kind, children, None))
+    }
+
+    fn member(&mut self) -> Result<Node, JsonError> {
+        let key = match self.tokens.peek() {
+            Some(Ok(tok)) => tok.value.clone().unwrap_or_else(|| tok.lexeme.to_owned()),
+            _ => String::new(),
+        };
+        let mut children = Vec::new();
+        self.token(&mut children)?;
+        self.trivia(&mut children)?;
+        self.token(&mut children)?;
+        self.trivia(&mut children)?;
+        children.push(Element::Node(self.value()?));
+        Ok(node(NodeKind::Member, children, Some(key)))
+    }
+}
+
+fn node(kind: NodeKind, children: Vec<Element>, key: Option<String>) -> Node {
+    let start = children
+        .first()
+        .map(Element::span)
+        .expect("nodes are never empty");
+    let end = children
+        .last()
+        .map(Element::span)
+        .expect("nodes are never empty");
+    Node {
+        kind,
+        span: Span::new(start.start, end.end),
+        children,
+        key,
+    }
+}
@@ -0,0 +1,104 @@
+use crate::errors::JsonError;
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders a [`JsonError`] together with the line of input it points at.
+///
+/// ```text
+/// error: trailing comma
+///  --> line 3, column 15
+///   |
+/// 3 |     "b": [1, 2,],
+///   |               ^ remove this trailing comma
+/// ```
+///
+/// The span of the error is underlined; a span that runs past the end of
+/// its first line is underlined up to the end of that line.
+#[derive(Debug, Clone, Copy)]
+pub struct Diagnostic<'a> {
+    error: &'a JsonError,
+    source: &'a str,
+    color: bool,
+}
+
+impl<'a> Diagnostic<'a> {
+    /// Creates a diagnostic for `error`, which was raised while parsing
+    /// `source`.
+    pub fn new(error: &'a JsonError, source: &'a str) -> Self {
+        Diagnostic {
+            error,
+            source,
+            color: false,
+        }
+    }
+
+    /// Whether to highlight the output with ANSI escape codes.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    fn paint(&self, style: &'static str) -> &'static str {
+        if self.color {
+            style
+        } else {
+            ""
+        }
+    }
+
+    /// Returns the line containing byte `offset`, without its line break,
+    /// and the byte offset at which that line starts.
+    fn source_line(&self, offset: usize) -> (&'a str, usize) {
+        let offset = offset.min(self.source.len());
+        let start = self.source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = self.source[start..]
+            .find('\n')
+            .map_or(self.source.len(), |i| start + i);
+        (self.source[start..end].trim_end_matches('\r'), start)
+    }
+}
+
+impl std::fmt::Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (red, blue, bold, reset) = (
+            self.paint(RED),
+            self.paint(BLUE),
+            self.paint(BOLD),
+            self.paint(RESET),
+        );
+        let span = self.error.span;
+        let start = span.start;
+
+        writeln!(f, "{red}error{reset}{bold}: {}{reset}", self.error.kind)?;
+        let gutter = " ".repeat(start.line.to_string().len());
+        writeln!(
+            f,
+            "{gutter}{blue}-->{reset} line {}, column {}",
+            start.line, start.column
+        )?;
+
+        let (line, line_start) = self.source_line(start.offset);
+        let before = &line[..(start.offset - line_start).min(line.len())];
+        let underlined = if span.end.line == start.line {
+            let end = (span.end.offset - line_start).min(line.len());
+            line[before.len()..end.max(before.len())].chars().count()
+        } else {
+            line[before.len()..].chars().count()
+        };
+
+        // Keep tabs in the padding so the carets line up under the text.
+        let padding: String = before
+            .chars()
+            .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+            .collect();
+        let carets = "^".repeat(underlined.max(1));
+
+        writeln!(f, "{gutter} {blue}|{reset}")?;
+        writeln!(f, "{blue}{} |{reset} {}", start.line, line)?;
+        write!(f, "{gutter} {blue}|{reset} {padding}{red}{carets}{reset}")?;
+        writeln!(f, " {red}{}{reset}", self.error.kind.hint())
+    }
+}
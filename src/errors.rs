@@ -1,9 +1,18 @@
+use crate::parser::JsonValue;
+use crate::position::{Position, Span};
 use crate::token::TokenType;
 
+use std::sync::Arc;
+
 #[derive(Debug, Clone)]
 pub struct JsonError {
     pub kind: ErrorKind,
     pub line: Option<usize>,
+    /// Where the offending input starts.
+    pub position: Position,
+    /// The offending input, from the start of the token being read to where
+    /// the error was noticed.
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -21,21 +30,118 @@ pub enum ErrorKind {
     EmptyInput,
     Eof,
     NumberWithLeadingZero,
+    InvalidNumber,
+    NumberOutOfRange,
     InvalidBackslashEscape,
+    InvalidUnicodeEscape,
+    LoneSurrogate(u32),
+    ControlCharacterInString,
+    UnclosedComment,
     NestingTooDeep,
+    StringTooLong,
+    TooManyObjectMembers,
+    TooManyArrayElements,
+    InputTooLarge,
+    /// An object has the same key twice. The error's span points at the
+    /// second occurrence, the payload at the first. Boxed to keep
+    /// `JsonError` small.
+    DuplicateKey(Box<Span>),
     ParseNumberError(std::num::ParseFloatError),
+    /// Reading the input failed. Input that is not valid UTF-8 is reported
+    /// as `std::io::ErrorKind::InvalidData`.
+    Io(IoError),
+    /// A record of an RFC 7464 JSON text sequence does not end with a line
+    /// feed, so it may have been cut short.
+    TruncatedRecord,
 }
 
 impl JsonError {
     /// Compose an error
-    pub(crate) fn compose(kind: ErrorKind, line: Option<usize>) -> Self {
-        JsonError { kind, line }
+    pub(crate) fn compose(kind: ErrorKind, span: Span) -> Self {
+        JsonError {
+            kind,
+            line: Some(span.start.line),
+            position: span.start,
+            span,
+        }
+    }
+
+    /// Moves the error from a piece of the input that starts at `base` to
+    /// the same place in the whole input.
+    pub(crate) fn rebase(self, base: Position) -> Self {
+        JsonError::compose(self.kind, self.span.rebase(base))
     }
 }
 
-impl std::fmt::Display for JsonError {
+impl ErrorKind {
+    /// Whether the error reports an exceeded
+    /// [`ParserOptions`](crate::options::ParserOptions) limit.
+    pub fn is_limit(&self) -> bool {
+        matches!(
+            self,
+            ErrorKind::NestingTooDeep
+                | ErrorKind::StringTooLong
+                | ErrorKind::TooManyObjectMembers
+                | ErrorKind::TooManyArrayElements
+                | ErrorKind::InputTooLarge
+        )
+    }
+
+    /// A suggestion for fixing the input, shown by
+    /// [`Diagnostic`](crate::diagnostic::Diagnostic).
+    pub fn hint(&self) -> &'static str {
+        match self {
+            ErrorKind::InvalidSyntax => {
+                "check for a missing comma or a misspelled `true`, `false` or `null`"
+            }
+            ErrorKind::NotObjectOrArray => "wrap the value in `[` and `]`",
+            ErrorKind::SingleQuote => "use double quotes",
+            ErrorKind::MissingColon => "add a `:` between the key and its value",
+            ErrorKind::ObjectKeyNotString => "put the key in double quotes",
+            ErrorKind::InvalidObjectValueType => {
+                "a member value must be a string, number, object, array, `true`, `false` or `null`"
+            }
+            ErrorKind::TrailingComma => "remove this trailing comma",
+            ErrorKind::UnexpectedEof | ErrorKind::Eof => {
+                "the input ended early; finish the value or close what is open"
+            }
+            ErrorKind::UnclosedDelimiter(TokenType::Rbrace) => "add the missing `}`",
+            ErrorKind::UnclosedDelimiter(TokenType::RSqbracket) => "add the missing `]`",
+            ErrorKind::UnclosedDelimiter(TokenType::Str) => "add the missing `\"`",
+            ErrorKind::UnclosedDelimiter(_) => "close every `{` with `}` and every `[` with `]`",
+            ErrorKind::MissingValue => "add a value or remove the extra comma",
+            ErrorKind::EmptyInput => "a JSON document needs at least one value",
+            ErrorKind::NumberWithLeadingZero => "remove the leading zero",
+            ErrorKind::InvalidNumber => "numbers look like `12`, `-0.5` or `1e10`",
+            ErrorKind::NumberOutOfRange => "store very large numbers as strings",
+            ErrorKind::ParseNumberError(_) => "numbers look like `12`, `-0.5` or `1e10`",
+            ErrorKind::InvalidBackslashEscape => {
+                "valid escapes are \\\" \\\\ \\/ \\b \\f \\n \\r \\t and \\uXXXX"
+            }
+            ErrorKind::InvalidUnicodeEscape => "write exactly four hex digits, as in `\\u00e9`",
+            ErrorKind::LoneSurrogate(_) => {
+                "surrogates must come in high-low pairs, as in `\\ud83e\\udd80`"
+            }
+            ErrorKind::ControlCharacterInString => "escape it, for example `\\n` for a newline",
+            ErrorKind::UnclosedComment => "add the missing `*/`",
+            ErrorKind::NestingTooDeep => "flatten the arrays and objects",
+            ErrorKind::StringTooLong => "raise `max_string_length` or shorten the string",
+            ErrorKind::TooManyObjectMembers => "raise `max_object_members` or split the object up",
+            ErrorKind::TooManyArrayElements => "raise `max_array_elements` or split the array up",
+            ErrorKind::InputTooLarge => "raise `max_input_size` or send less input",
+            ErrorKind::Io(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                "the input must be UTF-8"
+            }
+            ErrorKind::Io(_) => "check that the input can be read",
+            ErrorKind::DuplicateKey(_) => "remove or rename one of the members",
+            ErrorKind::TruncatedRecord => "end each record with a line feed",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let msg = match &self.kind {
+        let msg = match self {
             ErrorKind::UnexpectedEof => "expected curly brace or comma".into(),
             ErrorKind::InvalidSyntax => "invalid syntax".into(),
             ErrorKind::MissingColon => "expected colon".into(),
@@ -52,15 +158,393 @@ impl std::fmt::Display for JsonError {
             ErrorKind::MissingValue => "expected value".into(),
             ErrorKind::InvalidObjectValueType => "invalid object value type".into(),
             ErrorKind::NumberWithLeadingZero => "number cannot begin with zero".into(),
+            ErrorKind::InvalidNumber => "invalid number".into(),
+            ErrorKind::NumberOutOfRange => "number is too large to represent".into(),
             ErrorKind::InvalidBackslashEscape => "invalid backslash escape".into(),
+            ErrorKind::InvalidUnicodeEscape => "expected four hexadecimal digits after \\u".into(),
+            ErrorKind::LoneSurrogate(code) => {
+                format!("unpaired UTF-16 surrogate \\u{:04X}", code)
+            }
+            ErrorKind::ControlCharacterInString => {
+                "control characters in strings must be escaped".into()
+            }
+            ErrorKind::UnclosedComment => "unclosed block comment".into(),
             ErrorKind::NestingTooDeep => "too many nested arrays or objects".into(),
+            ErrorKind::StringTooLong => "string is longer than the limit".into(),
+            ErrorKind::TooManyObjectMembers => "object has more members than the limit".into(),
+            ErrorKind::TooManyArrayElements => "array has more elements than the limit".into(),
+            ErrorKind::InputTooLarge => "input is larger than the limit".into(),
+            ErrorKind::Io(e) => format!("failed to read input, {}", e),
+            ErrorKind::TruncatedRecord => "truncated record in JSON text sequence".into(),
+            ErrorKind::DuplicateKey(first) => format!(
+                "duplicate key, first defined at line {}, column {}",
+                first.start.line, first.start.column
+            ),
         };
-        if let Some(line) = self.line {
-            write!(f, "error at line {}: {}", line, msg)
-        } else {
-            write!(f, "error: {}", msg)
+        f.write_str(&msg)
+    }
+}
+
+impl std::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(
+                f,
+                "error at line {}, column {}: {}",
+                line, self.position.column, self.kind
+            ),
+            None => write!(f, "error: {}", self.kind),
         }
     }
 }
 
 impl std::error::Error for JsonError {}
+
+/// The `std::io::Error` behind [`ErrorKind::Io`]. It is shared so that
+/// errors stay cheap to clone, and two compare equal when their
+/// `std::io::ErrorKind`s do.
+#[derive(Debug, Clone)]
+pub struct IoError(Arc<std::io::Error>);
+
+impl IoError {
+    /// The error for input that is not valid UTF-8.
+    pub(crate) fn invalid_utf8() -> Self {
+        let kind = std::io::ErrorKind::InvalidData;
+        std::io::Error::new(kind, "stream did not contain valid UTF-8").into()
+    }
+
+    /// The kind of the underlying error.
+    pub fn kind(&self) -> std::io::ErrorKind {
+        self.0.kind()
+    }
+
+    /// The underlying error.
+    pub fn get_ref(&self) -> &std::io::Error {
+        &self.0
+    }
+}
+
+impl From<std::io::Error> for IoError {
+    fn from(e: std::io::Error) -> Self {
+        IoError(Arc::new(e))
+    }
+}
+
+impl From<std::io::ErrorKind> for IoError {
+    fn from(kind: std::io::ErrorKind) -> Self {
+        std::io::Error::from(kind).into()
+    }
+}
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind()
+    }
+}
+
+impl std::fmt::Display for IoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for IoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.0)
+    }
+}
+
+/// An error raised while writing a `JsonValue` out as JSON text.
+#[derive(Debug)]
+pub enum SerializeError {
+    /// JSON has no representation for infinities and NaN.
+    NonFiniteFloat(f64),
+    /// The underlying `std::fmt::Write` failed.
+    Fmt(std::fmt::Error),
+    /// The underlying `std::io::Write` failed.
+    Io(std::io::Error),
+}
+
+impl From<std::fmt::Error> for SerializeError {
+    fn from(e: std::fmt::Error) -> Self {
+        SerializeError::Fmt(e)
+    }
+}
+
+impl std::fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SerializeError::NonFiniteFloat(x) => {
+                write!(f, "cannot serialize non-finite number {}", x)
+            }
+            SerializeError::Fmt(e) => write!(f, "failed to write JSON, {}", e),
+            SerializeError::Io(e) => write!(f, "failed to write JSON, {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SerializeError {}
+
+/// An error raised while editing a [`Document`](crate::cst::Document).
+#[derive(Debug)]
+pub enum EditError {
+    /// There is no value at the path, or no member or element to remove.
+    PathNotFound,
+    /// The path leads to something other than an object.
+    NotAnObject,
+    /// The path leads to something other than an array.
+    NotAnArray,
+    /// The object already has a member with the key.
+    KeyExists,
+    /// The new value cannot be written as JSON.
+    Serialize(SerializeError),
+    /// The edited document breaks one of the parser's limits, such as
+    /// `max_depth` or `max_string_length`.
+    Limit(JsonError),
+}
+
+impl From<SerializeError> for EditError {
+    fn from(e: SerializeError) -> Self {
+        EditError::Serialize(e)
+    }
+}
+
+impl std::fmt::Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EditError::PathNotFound => f.write_str("no value at the path"),
+            EditError::NotAnObject => f.write_str("the path does not lead to an object"),
+            EditError::NotAnArray => f.write_str("the path does not lead to an array"),
+            EditError::KeyExists => f.write_str("the object already has a member with the key"),
+            EditError::Serialize(e) => e.fmt(f),
+            EditError::Limit(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// An error raised by a [`JsonPointer`](crate::pointer::JsonPointer) that
+/// is malformed, or that does not lead to a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PointerError {
+    /// The pointer is neither empty nor starts with `/`.
+    MissingSlash,
+    /// A `~` is not followed by `0` or `1`. Carries the byte offset of the
+    /// `~` in the pointer.
+    InvalidEscape(usize),
+    /// A reference token applied to an array is not an index: `0`, a
+    /// number without leading zeros, or `-`.
+    InvalidIndex,
+    /// There is no value at the pointer: a member is missing, an index is
+    /// out of bounds, or the way leads through a scalar.
+    NotFound,
+    /// The root value cannot be removed.
+    RemoveRoot,
+}
+
+impl std::fmt::Display for PointerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PointerError::MissingSlash => f.write_str("JSON pointer must start with `/`"),
+            PointerError::InvalidEscape(offset) => {
+                write!(f, "invalid escape at byte {} of JSON pointer", offset)
+            }
+            PointerError::InvalidIndex => {
+                f.write_str("array index must be a number without leading zeros, or `-`")
+            }
+            PointerError::NotFound => f.write_str("no value at the JSON pointer"),
+            PointerError::RemoveRoot => f.write_str("cannot remove the root value"),
+        }
+    }
+}
+
+impl std::error::Error for PointerError {}
+
+/// An error in a [`Patch`](crate::patch::Patch), or raised while applying
+/// one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchError {
+    /// The index of the operation in the patch.
+    pub index: usize,
+    pub kind: PatchErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchErrorKind {
+    /// The patch is not an array.
+    NotAnArray,
+    /// The operation is not an object.
+    NotAnObject,
+    /// The operation lacks a member its `op` needs. Carries its name.
+    MissingMember(&'static str),
+    /// `op`, `path` or `from` is not a string. Carries its name.
+    InvalidMember(&'static str),
+    /// An `op` other than `add`, `remove`, `replace`, `move`, `copy` and
+    /// `test`.
+    UnknownOp(String),
+    /// A `path` or `from` is malformed, or does not lead to where the
+    /// operation needs it to.
+    Pointer(PointerError),
+    /// A `test` found a different value.
+    TestFailed,
+    /// A `move` from a value into one of its own members or elements.
+    MoveIntoChild,
+}
+
+impl From<PointerError> for PatchErrorKind {
+    fn from(e: PointerError) -> Self {
+        PatchErrorKind::Pointer(e)
+    }
+}
+
+impl std::fmt::Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            PatchErrorKind::NotAnArray => return f.write_str("JSON patch must be an array"),
+            PatchErrorKind::NotAnObject => f.write_str("operation is not an object")?,
+            PatchErrorKind::MissingMember(name) => write!(f, "missing `{}` member", name)?,
+            PatchErrorKind::InvalidMember(name) => write!(f, "`{}` must be a string", name)?,
+            PatchErrorKind::UnknownOp(op) => write!(f, "unknown op `{}`", op)?,
+            PatchErrorKind::Pointer(e) => write!(f, "{}", e)?,
+            PatchErrorKind::TestFailed => f.write_str("test failed")?,
+            PatchErrorKind::MoveIntoChild => {
+                f.write_str("cannot move a value into one of its children")?
+            }
+        }
+        write!(f, " in operation {} of the JSON patch", self.index)
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// An error in the text of a [`JsonPath`](crate::jsonpath::JsonPath).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPathError {
+    pub kind: JsonPathErrorKind,
+    /// The byte offset in the expression where the problem was found.
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonPathErrorKind {
+    /// The expression ends in the middle of a segment or filter.
+    UnexpectedEnd,
+    /// A character that cannot appear at this point.
+    UnexpectedChar(char),
+    /// A string literal with a bad escape or an unescaped control
+    /// character.
+    InvalidString,
+    /// A malformed number, or an index outside the range -(2^53-1) to
+    /// 2^53-1.
+    InvalidNumber,
+    /// A function other than `length`, `count`, `match`, `search` and
+    /// `value`.
+    UnknownFunction(String),
+    /// An expression used where its type does not fit: a query that can
+    /// select more than one node in a comparison, a function with the
+    /// wrong arguments, or one whose result cannot be used there.
+    NotWellTyped,
+    /// Filters, parentheses and function calls are nested more than 64
+    /// deep.
+    TooDeep,
+    /// A literal `match` or `search` pattern that is a valid I-Regexp but
+    /// cannot be compiled: it uses a category other than `L`, `Lu`, `Ll`,
+    /// `N` and `Cc`, is too large, or nests groups more than 64 deep.
+    UnsupportedPattern,
+}
+
+impl std::fmt::Display for JsonPathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            JsonPathErrorKind::UnexpectedEnd => f.write_str("unexpected end of JSONPath")?,
+            JsonPathErrorKind::UnexpectedChar(ch) => write!(f, "unexpected {:?} in JSONPath", ch)?,
+            JsonPathErrorKind::InvalidString => {
+                f.write_str("invalid string literal in JSONPath")?
+            }
+            JsonPathErrorKind::InvalidNumber => f.write_str("invalid number in JSONPath")?,
+            JsonPathErrorKind::UnknownFunction(name) => write!(f, "unknown function `{}`", name)?,
+            JsonPathErrorKind::NotWellTyped => f.write_str("expression is not well-typed")?,
+            JsonPathErrorKind::TooDeep => f.write_str("expression nested too deeply")?,
+            JsonPathErrorKind::UnsupportedPattern => {
+                f.write_str("unsupported regular expression in JSONPath")?
+            }
+        }
+        write!(f, " at byte {}", self.offset)
+    }
+}
+
+impl std::error::Error for JsonPathError {}
+
+/// An error in the text of a [`Filter`](crate::jq::Filter).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterError {
+    pub kind: FilterErrorKind,
+    /// The byte offset in the filter where the problem was found.
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterErrorKind {
+    /// The filter ends in the middle of an expression.
+    UnexpectedEnd,
+    /// A character or token that cannot appear at this point. Carries its
+    /// text.
+    Unexpected(String),
+    /// A string literal with a bad escape or an unescaped control
+    /// character. String interpolation is not supported.
+    InvalidString,
+    /// A malformed number.
+    InvalidNumber,
+    /// A call to a function that is not defined with that many arguments,
+    /// as `name/arity`, or a variable that is not bound, as `$name`.
+    Undefined(String),
+    /// Terms are nested more than 64 deep.
+    TooDeep,
+}
+
+impl std::fmt::Display for FilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            FilterErrorKind::UnexpectedEnd => f.write_str("unexpected end of filter")?,
+            FilterErrorKind::Unexpected(text) => write!(f, "unexpected `{}` in filter", text)?,
+            FilterErrorKind::InvalidString => f.write_str("invalid string literal in filter")?,
+            FilterErrorKind::InvalidNumber => f.write_str("invalid number in filter")?,
+            FilterErrorKind::Undefined(name) => write!(f, "{} is not defined", name)?,
+            FilterErrorKind::TooDeep => f.write_str("filter nested too deeply")?,
+        }
+        write!(f, " at byte {}", self.offset)
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// An error raised while running a [`Filter`](crate::jq::Filter).
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    /// An operation that does not apply to its input, such as `.[]` on a
+    /// number, `1 + "a"` or a division by zero. Carries a description in
+    /// the words jq uses.
+    Invalid(String),
+    /// Raised by `error`. Carries the value it was given.
+    Raised(JsonValue),
+    /// Expressions were nested too deeply while running, usually by a
+    /// recursion that does not end.
+    TooDeep,
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::Invalid(message) => f.write_str(message),
+            EvalError::Raised(JsonValue::String(message)) => f.write_str(message),
+            EvalError::Raised(value) => match crate::serializer::to_string(value) {
+                Ok(json) => write!(f, "{} (not a string)", json),
+                Err(_) => f.write_str("error raised with a value that is not JSON"),
+            },
+            EvalError::TooDeep => f.write_str("expressions nested too deeply"),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
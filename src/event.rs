@@ -0,0 +1,28 @@
+use crate::number::Number;
+use crate::position::Span;
+
+/// What an [`Event`] reports.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventKind<'a> {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    /// The key of an object member. The events for its value follow.
+    Key(&'a str),
+    String(&'a str),
+    Number(Number),
+    Bool(bool),
+    Null,
+}
+
+/// One step through a document, as produced by
+/// [`Parser::next_event`](crate::parser::Parser::next_event).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event<'a> {
+    pub kind: EventKind<'a>,
+    /// Where the token behind the event is. For an end event this is the
+    /// closing delimiter, or an empty span where a missing one would go
+    /// when parsing in recovery mode.
+    pub span: Span,
+}
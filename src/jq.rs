@@ -0,0 +1,2250 @@
+use crate::errors::{EvalError, FilterError, FilterErrorKind};
+use crate::map::Map;
+use crate::number::Number;
+use crate::parser::{JsonValue, Parser};
+use crate::serializer::to_string;
+
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+/// How deeply a run may nest by default: see [`Filter::max_depth`].
+const MAX_DEPTH: usize = 1000;
+
+/// How many steps of a chain run nested inside one another before the
+/// outputs so far are collected and the rest of the chain starts afresh.
+const STRETCH: usize = 32;
+
+/// How deeply terms may nest in the text of a filter.
+const MAX_NESTING: usize = 64;
+
+/// The longest string, in bytes, that multiplying a string may build.
+const MAX_REPEAT_LENGTH: usize = 1 << 26;
+
+/// The functions written in Rust, with the number of arguments each takes.
+const NATIVE: &[(&str, usize)] = &[
+    ("empty", 0),
+    ("error", 0),
+    ("error", 1),
+    ("not", 0),
+    ("length", 0),
+    ("keys", 0),
+    ("keys_unsorted", 0),
+    ("has", 1),
+    ("to_entries", 0),
+    ("from_entries", 0),
+    ("type", 0),
+    ("tostring", 0),
+    ("tojson", 0),
+    ("tonumber", 0),
+    ("sort", 0),
+    ("range", 2),
+    ("recurse", 1),
+];
+
+/// The functions written in the filter language itself.
+const PRELUDE: &str = r#"
+def map(f): [.[] | f];
+def select(f): if f then . else empty end;
+def recurse: recurse(.[]?);
+def values: select(. != null);
+def with_entries(f): to_entries | map(f) | from_entries;
+def add: reduce .[] as $x (null; . + $x);
+def any: reduce .[] as $x (false; . or $x);
+def all: reduce .[] as $x (true; . and $x);
+def range($n): range(0; $n);
+def sort_by(f): [range(0; length) as $i | [[.[$i] | f], $i, .[$i]]] | sort | map(.[2]);
+def join($sep):
+  reduce .[] as $x (null;
+    (if . == null then "" else . + $sep end) + ($x | if . == null then "" else tostring end)
+  ) // "";
+"#;
+
+/// A compiled filter in a subset of the [jq](https://jqlang.github.io/jq/)
+/// language, for transforming `JsonValue`s the way a shell script would
+/// with `jq`.
+///
+/// A filter turns one input into any number of outputs. It is built from:
+///
+/// - `.`, `.foo`, `."foo"`, `.[e]`, `.[from:to]` and `.[]`, with `?` after
+///   any of them to drop errors, and `..` for every value below the input;
+/// - pipes `|` and the comma `,`, which runs two filters one after the
+///   other;
+/// - literals, `[...]` and `{...}`, where `{a, $b, "c": e, (e): e}` works
+///   as in jq;
+/// - `+ - * / %`, `== != < <= > >=`, `and`, `or` and `//`;
+/// - `if`, `try ... catch`, `reduce e as $x (init; update)` and
+///   `e as $x | body`;
+/// - `def name(f; $x): body;`, which may call itself.
+///
+/// The functions at hand are `empty`, `error`, `not`, `length`, `keys`,
+/// `keys_unsorted`, `has`, `to_entries`, `from_entries`, `with_entries`,
+/// `type`, `tostring`, `tojson`, `tonumber`, `sort`, `sort_by`, `map`,
+/// `select`, `recurse`, `values`, `add`, `any`, `all`, `range` and `join`.
+///
+/// Values are ordered and compared the way jq does: `null`, `false`,
+/// `true`, numbers, strings, arrays, objects.
+///
+/// ```
+/// use jimson::jq::Filter;
+/// use jimson::parser::{JsonValue, Parser};
+///
+/// let input = r#"{"users": [{"name": "ann", "age": 31}, {"name": "bob", "age": 17}]}"#;
+/// let value = Parser::new(input).unwrap().parse_root().unwrap();
+///
+/// let filter = Filter::parse("[.users[] | select(.age >= 18) | .name]").unwrap();
+/// let out = filter.run(&value).unwrap();
+/// assert_eq!(out, [JsonValue::Array(vec![JsonValue::String("ann".into())])]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Filter {
+    prelude: Vec<Def>,
+    body: Expr,
+    max_depth: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Def {
+    name: String,
+    params: Vec<String>,
+    body: Expr,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Identity,
+    Literal(JsonValue),
+    /// A term and the indexing after it, kept flat so that a long path does
+    /// not nest.
+    Path(Box<Expr>, Vec<Suffix>),
+    Try(Box<Expr>, Option<Box<Expr>>),
+    /// Chains of operators are kept flat, so that a long one does not
+    /// nest.
+    Pipe(Vec<Expr>),
+    Comma(Vec<Expr>),
+    Neg(Box<Expr>),
+    /// The first operand, then each operator with the operand to its
+    /// right, applied left to right.
+    Binary(Box<Expr>, Vec<(BinOp, Expr)>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Alternative(Vec<Expr>),
+    /// `elif` becomes a nested `If`, and a missing `else` is `.`.
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+    Array(Option<Box<Expr>>),
+    /// The key and value filters of each entry.
+    Object(Vec<(Expr, Expr)>),
+    Var(String),
+    /// `source as $name | body`.
+    Bind(Box<Expr>, String, Box<Expr>),
+    /// `reduce source as $name (init; update)`.
+    Reduce(Box<Expr>, String, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    /// A run of `def`s and the filter they are visible in.
+    Define(Vec<Def>, Box<Expr>),
+}
+
+/// What comes after a term in a path. The expressions in it run on the
+/// input of the whole path.
+#[derive(Debug, Clone)]
+enum Suffix {
+    /// `.name` or `[e]`.
+    Index(Expr),
+    /// `[from:to]`.
+    Slice(Option<Expr>, Option<Expr>),
+    /// `[]`.
+    Iterate,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Filter {
+    /// Compiles a filter. Calls to functions and uses of variables that
+    /// are not defined are reported here rather than when it runs.
+    pub fn parse(filter: &str) -> Result<Self, FilterError> {
+        let mut parser = FilterParser {
+            tokens: lex(PRELUDE).expect("the prelude is valid"),
+            pos: 0,
+            end: PRELUDE.len(),
+            nesting: 0,
+            scope: NATIVE
+                .iter()
+                .map(|&(name, arity)| Name::Func(name.to_owned(), arity))
+                .collect(),
+        };
+        let mut prelude = Vec::new();
+        while parser.pos < parser.tokens.len() {
+            prelude.push(parser.def().expect("the prelude is valid"));
+        }
+
+        parser.tokens = lex(filter)?;
+        parser.pos = 0;
+        parser.end = filter.len();
+        let body = match parser.tokens.is_empty() {
+            true => Expr::Identity,
+            false => parser.pipe()?,
+        };
+        if let Some((token, offset)) = parser.tokens.get(parser.pos) {
+            let kind = FilterErrorKind::Unexpected(token.text());
+            return Err(FilterError {
+                kind,
+                offset: *offset,
+            });
+        }
+        Ok(Filter {
+            prelude,
+            body,
+            max_depth: MAX_DEPTH,
+        })
+    }
+
+    /// Sets how deeply a run may nest before it fails with
+    /// [`EvalError::TooDeep`]; the default is 1000.
+    ///
+    /// Each expression that is still running, such as a call waiting on its
+    /// body, nests a level, so this mostly limits recursion in `def`s.
+    /// Paths, pipes and chains of operators run flat however long they are,
+    /// and `..` walks values of any depth. Every level takes up to a
+    /// kilobyte or so of stack in a release build, and more in a debug
+    /// build, so a larger limit needs a thread with a larger stack.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Runs the filter on `input` and collects its outputs. The first error
+    /// ends the run.
+    pub fn run(&self, input: &JsonValue) -> Result<Vec<JsonValue>, EvalError> {
+        let env = self
+            .prelude
+            .iter()
+            .fold(Env(None), |env, def| env.push(Binding::Func(def)));
+        let mut outputs = Vec::new();
+        eval(
+            &self.body,
+            &env,
+            &Cell::new(self.max_depth),
+            input.clone(),
+            &mut |value| {
+                outputs.push(value);
+                Ok(())
+            },
+        )?;
+        Ok(outputs)
+    }
+}
+
+impl std::str::FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    /// `.name`
+    Field(String),
+    /// `$name`
+    Var(String),
+    Number(JsonValue),
+    Str(String),
+    Dot,
+    DotDot,
+    Punct(&'static str),
+}
+
+impl Token {
+    fn text(&self) -> String {
+        match self {
+            Token::Ident(name) => name.clone(),
+            Token::Field(name) => format!(".{}", name),
+            Token::Var(name) => format!("${}", name),
+            Token::Number(n) => to_string(n).unwrap_or_default(),
+            Token::Str(_) => "string".to_owned(),
+            Token::Dot => ".".to_owned(),
+            Token::DotDot => "..".to_owned(),
+            Token::Punct(p) => (*p).to_owned(),
+        }
+    }
+}
+
+/// Longest first, so that `//` is not read as two `/`.
+const PUNCT: &[&str] = &[
+    "//", "==", "!=", "<=", ">=", "|", ",", ":", ";", "(", ")", "[", "]", "{", "}", "+", "-", "*",
+    "/", "%", "<", ">", "?",
+];
+
+fn is_ident_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '_'
+}
+
+fn is_ident(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+fn lex(src: &str) -> Result<Vec<(Token, usize)>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let error = |kind, offset| FilterError { kind, offset };
+    while let Some(ch) = src[pos..].chars().next() {
+        let start = pos;
+        let rest = &src[pos..];
+        let ident_len = |s: &str| s.find(|ch| !is_ident(ch)).unwrap_or(s.len());
+        let token = match ch {
+            ' ' | '\t' | '\n' | '\r' => {
+                pos += 1;
+                continue;
+            }
+            '#' => {
+                pos += rest.find('\n').unwrap_or(rest.len());
+                continue;
+            }
+            '.' if rest[1..].starts_with(is_ident_start) => {
+                let len = ident_len(&rest[1..]);
+                pos += 1 + len;
+                Token::Field(rest[1..1 + len].to_owned())
+            }
+            '.' if rest[1..].starts_with('.') => {
+                pos += 2;
+                Token::DotDot
+            }
+            '.' => {
+                pos += 1;
+                Token::Dot
+            }
+            '$' if rest[1..].starts_with(is_ident_start) => {
+                let len = ident_len(&rest[1..]);
+                pos += 1 + len;
+                Token::Var(rest[1..1 + len].to_owned())
+            }
+            ch if is_ident_start(ch) => {
+                pos += ident_len(rest);
+                Token::Ident(rest[..pos - start].to_owned())
+            }
+            '0'..='9' => {
+                let mut len = rest
+                    .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+                    .unwrap_or(rest.len());
+                if rest[len..].starts_with(['e', 'E']) {
+                    let exp = &rest[len + 1..];
+                    let sign = usize::from(exp.starts_with(['+', '-']));
+                    let digits = exp[sign..]
+                        .find(|ch: char| !ch.is_ascii_digit())
+                        .unwrap_or(exp.len() - sign);
+                    len += 1 + sign + digits;
+                }
+                pos += len;
+                match Parser::new(&rest[..len]).and_then(|mut parser| parser.parse_root()) {
+                    Ok(n) => Token::Number(n),
+                    Err(_) => return Err(error(FilterErrorKind::InvalidNumber, start)),
+                }
+            }
+            '"' => {
+                let (s, len) = lex_string(rest).map_err(|at| {
+                    let kind = match at < rest.len() {
+                        true => FilterErrorKind::InvalidString,
+                        false => FilterErrorKind::UnexpectedEnd,
+                    };
+                    error(kind, start + at)
+                })?;
+                pos += len;
+                Token::Str(s)
+            }
+            _ => match PUNCT.iter().find(|p| rest.starts_with(**p)) {
+                Some(p) => {
+                    pos += p.len();
+                    Token::Punct(p)
+                }
+                None => {
+                    let kind = FilterErrorKind::Unexpected(ch.to_string());
+                    return Err(error(kind, start));
+                }
+            },
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+/// Reads the string literal at the start of `src`, and returns it with the
+/// length of its text. On error, returns the offset of the problem.
+fn lex_string(src: &str) -> Result<(String, usize), usize> {
+    let mut s = String::new();
+    let mut chars = src.char_indices().skip(1);
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '"' => return Ok((s, i + 1)),
+            '\\' => {
+                let ch = match chars.next().ok_or(src.len())?.1 {
+                    '"' => '"',
+                    '\\' => '\\',
+                    '/' => '/',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'u' => {
+                        let hex = |chars: &mut std::iter::Skip<std::str::CharIndices>| {
+                            let digits: String = chars.by_ref().take(4).map(|(_, ch)| ch).collect();
+                            match digits.len() == 4
+                                && digits.chars().all(|ch| ch.is_ascii_hexdigit())
+                            {
+                                true => u32::from_str_radix(&digits, 16).ok(),
+                                false => None,
+                            }
+                        };
+                        let high = hex(&mut chars).ok_or(i)?;
+                        let code = match high {
+                            0xD800..=0xDBFF => {
+                                let low = match (chars.next(), chars.next()) {
+                                    (Some((_, '\\')), Some((_, 'u'))) => hex(&mut chars),
+                                    _ => None,
+                                };
+                                match low {
+                                    Some(low @ 0xDC00..=0xDFFF) => {
+                                        0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                                    }
+                                    _ => return Err(i),
+                                }
+                            }
+                            code => code,
+                        };
+                        char::from_u32(code).ok_or(i)?
+                    }
+                    _ => return Err(i),
+                };
+                s.push(ch);
+            }
+            ch if ch < ' ' => return Err(i),
+            ch => s.push(ch),
+        }
+    }
+    Err(src.len())
+}
+
+/// A function or variable that is in scope while parsing.
+#[derive(Debug, PartialEq)]
+enum Name {
+    Func(String, usize),
+    Var(String),
+}
+
+struct FilterParser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// The length of the text, for errors at its end.
+    end: usize,
+    /// How many terms the parser is inside.
+    nesting: usize,
+    scope: Vec<Name>,
+}
+
+impl FilterParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.end, |(_, offset)| *offset)
+    }
+
+    fn unexpected(&self) -> FilterError {
+        let kind = match self.peek() {
+            Some(token) => FilterErrorKind::Unexpected(token.text()),
+            None => FilterErrorKind::UnexpectedEnd,
+        };
+        FilterError {
+            kind,
+            offset: self.offset(),
+        }
+    }
+
+    /// Goes one level of nesting deeper, which the caller undoes.
+    fn deeper(&mut self) -> Result<(), FilterError> {
+        if self.nesting == MAX_NESTING {
+            return Err(FilterError {
+                kind: FilterErrorKind::TooDeep,
+                offset: self.offset(),
+            });
+        }
+        self.nesting += 1;
+        Ok(())
+    }
+
+    /// Runs `parse` one level of nesting deeper.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, FilterError>,
+    ) -> Result<T, FilterError> {
+        self.deeper()?;
+        let result = parse(self);
+        self.nesting -= 1;
+        result
+    }
+
+    fn at_def(&self) -> bool {
+        matches!(self.peek(), Some(Token::Ident(name)) if name == "def")
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Punct(p)) if *p == punct);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Ident(name)) if name == keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), FilterError> {
+        match self.eat(punct) {
+            true => Ok(()),
+            false => Err(self.unexpected()),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), FilterError> {
+        match self.eat_keyword(keyword) {
+            true => Ok(()),
+            false => Err(self.unexpected()),
+        }
+    }
+
+    fn var_name(&mut self) -> Result<String, FilterError> {
+        match self.peek() {
+            Some(Token::Var(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn pipe(&mut self) -> Result<Expr, FilterError> {
+        let scope = self.scope.len();
+        let mut defs = Vec::new();
+        let body = loop {
+            if !self.at_def() {
+                break self.stages();
+            }
+            match self.def() {
+                Ok(def) => defs.push(def),
+                Err(e) => break Err(e),
+            }
+        };
+        self.scope.truncate(scope);
+        let body = body?;
+        match defs.is_empty() {
+            true => Ok(body),
+            false => Ok(Expr::Define(defs, Box::new(body))),
+        }
+    }
+
+    /// Reads `a | b | ...`, after any definitions at the start of a pipe.
+    fn stages(&mut self) -> Result<Expr, FilterError> {
+        let mut stages = vec![self.comma()?];
+        while self.eat("|") {
+            if self.at_def() {
+                stages.push(self.nested(Self::pipe)?);
+                break;
+            }
+            stages.push(self.comma()?);
+        }
+        Ok(chain(stages, Expr::Pipe))
+    }
+
+    /// Reads `def name(params): body;`, and leaves the function in scope.
+    fn def(&mut self) -> Result<Def, FilterError> {
+        self.expect_keyword("def")?;
+        let name = match self.peek() {
+            Some(Token::Ident(name)) if !is_keyword(name) => name.clone(),
+            _ => return Err(self.unexpected()),
+        };
+        self.pos += 1;
+        // Either a filter, or a `$name` that is also bound as a variable.
+        let mut params = Vec::new();
+        if self.eat("(") {
+            loop {
+                match self.peek() {
+                    Some(Token::Ident(param)) if !is_keyword(param) => {
+                        params.push((param.clone(), false))
+                    }
+                    Some(Token::Var(param)) => params.push((param.clone(), true)),
+                    _ => return Err(self.unexpected()),
+                }
+                self.pos += 1;
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(";")?;
+            }
+        }
+        self.expect(":")?;
+
+        self.scope.push(Name::Func(name.clone(), params.len()));
+        let depth = self.scope.len();
+        for (param, is_var) in &params {
+            self.scope.push(Name::Func(param.clone(), 0));
+            if *is_var {
+                self.scope.push(Name::Var(param.clone()));
+            }
+        }
+        let body = self.nested(Self::pipe);
+        self.scope.truncate(depth);
+        let mut body = body?;
+        self.expect(";")?;
+
+        for (param, is_var) in params.iter().rev() {
+            if *is_var {
+                let source = Expr::Call(param.clone(), Vec::new());
+                body = Expr::Bind(Box::new(source), param.clone(), Box::new(body));
+            }
+        }
+        let params = params.into_iter().map(|(param, _)| param).collect();
+        Ok(Def { name, params, body })
+    }
+
+    fn comma(&mut self) -> Result<Expr, FilterError> {
+        let mut items = vec![self.alternative()?];
+        while self.eat(",") {
+            items.push(self.alternative()?);
+        }
+        Ok(chain(items, Expr::Comma))
+    }
+
+    fn alternative(&mut self) -> Result<Expr, FilterError> {
+        let mut operands = vec![self.or()?];
+        while self.eat("//") {
+            operands.push(self.or()?);
+        }
+        Ok(chain(operands, Expr::Alternative))
+    }
+
+    fn or(&mut self) -> Result<Expr, FilterError> {
+        let mut operands = vec![self.and()?];
+        while self.eat_keyword("or") {
+            operands.push(self.and()?);
+        }
+        Ok(chain(operands, Expr::Or))
+    }
+
+    fn and(&mut self) -> Result<Expr, FilterError> {
+        let mut operands = vec![self.comparison()?];
+        while self.eat_keyword("and") {
+            operands.push(self.comparison()?);
+        }
+        Ok(chain(operands, Expr::And))
+    }
+
+    fn comparison(&mut self) -> Result<Expr, FilterError> {
+        let expr = self.additive()?;
+        let ops = [
+            ("==", BinOp::Eq),
+            ("!=", BinOp::Ne),
+            ("<=", BinOp::Le),
+            (">=", BinOp::Ge),
+            ("<", BinOp::Lt),
+            (">", BinOp::Gt),
+        ];
+        match ops.into_iter().find(|(punct, _)| self.eat(punct)) {
+            Some((_, op)) => Ok(Expr::Binary(Box::new(expr), vec![(op, self.additive()?)])),
+            None => Ok(expr),
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr, FilterError> {
+        let first = self.multiplicative()?;
+        let mut rest = Vec::new();
+        loop {
+            let op = match () {
+                _ if self.eat("+") => BinOp::Add,
+                _ if self.eat("-") => BinOp::Sub,
+                _ => return Ok(binary_chain(first, rest)),
+            };
+            rest.push((op, self.multiplicative()?));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, FilterError> {
+        let first = self.unary()?;
+        let mut rest = Vec::new();
+        loop {
+            let op = match () {
+                _ if self.eat("*") => BinOp::Mul,
+                _ if self.eat("/") => BinOp::Div,
+                _ if self.eat("%") => BinOp::Rem,
+                _ => return Ok(binary_chain(first, rest)),
+            };
+            rest.push((op, self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, FilterError> {
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.nested(Self::unary)?)));
+        }
+        let term = self.postfix()?;
+        if !self.eat_keyword("as") {
+            return Ok(term);
+        }
+        let name = self.var_name()?;
+        self.expect("|")?;
+        self.scope.push(Name::Var(name.clone()));
+        let body = self.nested(Self::pipe);
+        self.scope.pop();
+        Ok(Expr::Bind(Box::new(term), name, Box::new(body?)))
+    }
+
+    /// Reads a term and the `.name`, `[...]` and `?` after it.
+    fn postfix(&mut self) -> Result<Expr, FilterError> {
+        let term = self.nested(Self::term)?;
+        let nesting = self.nesting;
+        let expr = self.suffixes(term);
+        self.nesting = nesting;
+        expr
+    }
+
+    /// Reads the suffixes of a term. Indexing adds to the path of the
+    /// term, while a `?` wraps everything before it, so it counts as a
+    /// level of nesting.
+    fn suffixes(&mut self, mut expr: Expr) -> Result<Expr, FilterError> {
+        loop {
+            match self.peek() {
+                Some(Token::Field(name)) => {
+                    let key = Expr::Literal(JsonValue::String(name.clone()));
+                    self.pos += 1;
+                    expr = suffixed(expr, Suffix::Index(key));
+                }
+                Some(Token::Dot) => match self.tokens.get(self.pos + 1) {
+                    Some((Token::Str(key), _)) => {
+                        let key = Expr::Literal(JsonValue::String(key.clone()));
+                        self.pos += 2;
+                        expr = suffixed(expr, Suffix::Index(key));
+                    }
+                    Some((Token::Punct("["), _)) => {
+                        self.pos += 2;
+                        expr = suffixed(expr, self.nested(Self::bracket)?);
+                    }
+                    _ => break,
+                },
+                Some(Token::Punct("[")) => {
+                    self.pos += 1;
+                    expr = suffixed(expr, self.nested(Self::bracket)?);
+                }
+                Some(Token::Punct("?")) => {
+                    self.deeper()?;
+                    self.pos += 1;
+                    expr = Expr::Try(Box::new(expr), None);
+                }
+                _ => break,
+            }
+        }
+        Ok(expr)
+    }
+
+    /// Reads what follows the `[` of an index, slice or iteration.
+    fn bracket(&mut self) -> Result<Suffix, FilterError> {
+        if self.eat("]") {
+            return Ok(Suffix::Iterate);
+        }
+        let from = match self.peek() {
+            Some(Token::Punct(":")) => None,
+            _ => Some(self.pipe()?),
+        };
+        if !self.eat(":") {
+            self.expect("]")?;
+            let key = from.ok_or_else(|| self.unexpected())?;
+            return Ok(Suffix::Index(key));
+        }
+        let to = match self.eat("]") {
+            true => None,
+            false => {
+                let to = self.pipe()?;
+                self.expect("]")?;
+                Some(to)
+            }
+        };
+        Ok(Suffix::Slice(from, to))
+    }
+
+    fn term(&mut self) -> Result<Expr, FilterError> {
+        let offset = self.offset();
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.unexpected());
+        };
+        self.pos += 1;
+        let expr = match token {
+            Token::Dot => match self.peek() {
+                Some(Token::Str(key)) => {
+                    let key = Expr::Literal(JsonValue::String(key.clone()));
+                    self.pos += 1;
+                    suffixed(Expr::Identity, Suffix::Index(key))
+                }
+                _ => Expr::Identity,
+            },
+            Token::Field(name) => {
+                let key = Expr::Literal(JsonValue::String(name));
+                suffixed(Expr::Identity, Suffix::Index(key))
+            }
+            Token::DotDot => Expr::Call("recurse".to_owned(), Vec::new()),
+            Token::Number(n) => Expr::Literal(n),
+            Token::Str(s) => Expr::Literal(JsonValue::String(s)),
+            Token::Var(name) => {
+                if !self.scope.contains(&Name::Var(name.clone())) {
+                    let kind = FilterErrorKind::Undefined(format!("${}", name));
+                    return Err(FilterError { kind, offset });
+                }
+                Expr::Var(name)
+            }
+            Token::Punct("(") => {
+                let expr = self.pipe()?;
+                self.expect(")")?;
+                expr
+            }
+            Token::Punct("[") => match self.eat("]") {
+                true => Expr::Array(None),
+                false => {
+                    let expr = self.pipe()?;
+                    self.expect("]")?;
+                    Expr::Array(Some(Box::new(expr)))
+                }
+            },
+            Token::Punct("{") => self.object()?,
+            Token::Ident(name) => match name.as_str() {
+                "true" => Expr::Literal(JsonValue::Boolean(true)),
+                "false" => Expr::Literal(JsonValue::Boolean(false)),
+                "null" => Expr::Literal(JsonValue::Null),
+                "if" => self.if_rest()?,
+                "try" => {
+                    let body = self.postfix()?;
+                    let handler = match self.eat_keyword("catch") {
+                        true => Some(Box::new(self.postfix()?)),
+                        false => None,
+                    };
+                    Expr::Try(Box::new(body), handler)
+                }
+                "reduce" => {
+                    let source = self.postfix()?;
+                    self.expect_keyword("as")?;
+                    let name = self.var_name()?;
+                    self.expect("(")?;
+                    let init = self.pipe()?;
+                    self.expect(";")?;
+                    self.scope.push(Name::Var(name.clone()));
+                    let update = self.pipe();
+                    self.scope.pop();
+                    let update = update?;
+                    self.expect(")")?;
+                    Expr::Reduce(Box::new(source), name, Box::new(init), Box::new(update))
+                }
+                name if is_keyword(name) => {
+                    self.pos -= 1;
+                    return Err(self.unexpected());
+                }
+                _ => {
+                    let mut args = Vec::new();
+                    if self.eat("(") {
+                        loop {
+                            args.push(self.pipe()?);
+                            if self.eat(")") {
+                                break;
+                            }
+                            self.expect(";")?;
+                        }
+                    }
+                    if !self.scope.contains(&Name::Func(name.clone(), args.len())) {
+                        let kind = FilterErrorKind::Undefined(format!("{}/{}", name, args.len()));
+                        return Err(FilterError { kind, offset });
+                    }
+                    Expr::Call(name, args)
+                }
+            },
+            _ => {
+                self.pos -= 1;
+                return Err(self.unexpected());
+            }
+        };
+        Ok(expr)
+    }
+
+    /// Reads the rest of an `if` after the keyword.
+    fn if_rest(&mut self) -> Result<Expr, FilterError> {
+        let cond = self.pipe()?;
+        self.expect_keyword("then")?;
+        let then = self.pipe()?;
+        let otherwise = if self.eat_keyword("elif") {
+            self.nested(Self::if_rest)?
+        } else if self.eat_keyword("else") {
+            let otherwise = self.pipe()?;
+            self.expect_keyword("end")?;
+            otherwise
+        } else {
+            self.expect_keyword("end")?;
+            Expr::Identity
+        };
+        Ok(Expr::If(
+            Box::new(cond),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    /// Reads the entries of an object after its `{`, and the `}`.
+    fn object(&mut self) -> Result<Expr, FilterError> {
+        let mut entries = Vec::new();
+        if self.eat("}") {
+            return Ok(Expr::Object(entries));
+        }
+        loop {
+            let offset = self.offset();
+            let key = match self.peek().cloned() {
+                Some(Token::Var(name)) => {
+                    self.pos += 1;
+                    if !self.scope.contains(&Name::Var(name.clone())) {
+                        let kind = FilterErrorKind::Undefined(format!("${}", name));
+                        return Err(FilterError { kind, offset });
+                    }
+                    let key = Expr::Literal(JsonValue::String(name.clone()));
+                    entries.push((key, Expr::Var(name)));
+                    None
+                }
+                Some(Token::Ident(name) | Token::Str(name)) => {
+                    self.pos += 1;
+                    Some(Expr::Literal(JsonValue::String(name)))
+                }
+                Some(Token::Punct("(")) => {
+                    self.pos += 1;
+                    let key = self.pipe()?;
+                    self.expect(")")?;
+                    if !matches!(self.peek(), Some(Token::Punct(":"))) {
+                        return Err(self.unexpected());
+                    }
+                    Some(key)
+                }
+                _ => return Err(self.unexpected()),
+            };
+            if let Some(key) = key {
+                let value = match self.eat(":") {
+                    true => self.object_value()?,
+                    // `{a}` is short for `{a: .a}`.
+                    false => suffixed(Expr::Identity, Suffix::Index(key.clone())),
+                };
+                entries.push((key, value));
+            }
+            if self.eat("}") {
+                return Ok(Expr::Object(entries));
+            }
+            self.expect(",")?;
+        }
+    }
+
+    /// Reads the value of an object entry, which may be piped but stops at
+    /// a comma.
+    fn object_value(&mut self) -> Result<Expr, FilterError> {
+        let mut stages = vec![self.alternative()?];
+        while self.eat("|") {
+            stages.push(self.alternative()?);
+        }
+        Ok(chain(stages, Expr::Pipe))
+    }
+}
+
+/// Builds a chain of operands, or returns the only one.
+fn chain(mut operands: Vec<Expr>, build: fn(Vec<Expr>) -> Expr) -> Expr {
+    match operands.len() {
+        1 => operands.pop().expect("one operand"),
+        _ => build(operands),
+    }
+}
+
+/// Builds a run of arithmetic, or returns its only operand.
+fn binary_chain(first: Expr, rest: Vec<(BinOp, Expr)>) -> Expr {
+    match rest.is_empty() {
+        true => first,
+        false => Expr::Binary(Box::new(first), rest),
+    }
+}
+
+/// Adds `suffix` to the path of `expr`, or starts one.
+fn suffixed(expr: Expr, suffix: Suffix) -> Expr {
+    match expr {
+        Expr::Path(term, mut suffixes) => {
+            suffixes.push(suffix);
+            Expr::Path(term, suffixes)
+        }
+        expr => Expr::Path(Box::new(expr), vec![suffix]),
+    }
+}
+
+fn is_keyword(name: &str) -> bool {
+    matches!(
+        name,
+        "def"
+            | "if"
+            | "then"
+            | "elif"
+            | "else"
+            | "end"
+            | "as"
+            | "reduce"
+            | "foreach"
+            | "try"
+            | "catch"
+            | "label"
+            | "import"
+            | "include"
+            | "and"
+            | "or"
+            | "__loc__"
+    )
+}
+
+type Out<'o> = &'o mut dyn FnMut(JsonValue) -> Result<(), EvalError>;
+
+/// The variables and functions in scope while running, innermost first.
+#[derive(Clone)]
+struct Env<'f>(Option<Rc<Scope<'f>>>);
+
+struct Scope<'f> {
+    binding: Binding<'f>,
+    parent: Env<'f>,
+}
+
+enum Binding<'f> {
+    Var(&'f str, JsonValue),
+    Func(&'f Def),
+    /// A filter passed to a function, run in the caller's scope.
+    Closure(&'f str, &'f Expr, Env<'f>),
+}
+
+impl<'f> Env<'f> {
+    fn push(&self, binding: Binding<'f>) -> Env<'f> {
+        Env(Some(Rc::new(Scope {
+            binding,
+            parent: self.clone(),
+        })))
+    }
+
+    fn scopes(&self) -> impl Iterator<Item = &Rc<Scope<'f>>> {
+        std::iter::successors(self.0.as_ref(), |scope| scope.parent.0.as_ref())
+    }
+
+    fn var(&self, name: &str) -> Option<&JsonValue> {
+        self.scopes().find_map(|scope| match &scope.binding {
+            Binding::Var(var, value) if *var == name => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Finds a function, along with the scope it was defined in, which
+    /// holds the function itself so that it can call itself.
+    fn function(&self, name: &str, arity: usize) -> Option<(&Binding<'f>, Env<'f>)> {
+        self.scopes().find_map(|scope| {
+            let found = match &scope.binding {
+                Binding::Func(def) => def.name == name && def.params.len() == arity,
+                Binding::Closure(param, _, _) => *param == name && arity == 0,
+                Binding::Var(..) => false,
+            };
+            found.then(|| (&scope.binding, Env(Some(scope.clone()))))
+        })
+    }
+}
+
+/// The function that runs one kind of expression.
+type Run =
+    for<'f> fn(&'f Expr, &Env<'f>, &Cell<usize>, JsonValue, Out<'_>) -> Result<(), EvalError>;
+
+/// Runs `expr` on `input`, passing each output to `out`.
+fn eval<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    // This frame is on the stack at every level of nesting. Choosing the
+    // function to run, which takes the expression apart itself, and calling
+    // it from one place keeps the frame small, even in debug builds.
+    let run: Run = match expr {
+        _ if single(expr) => eval_single,
+        Expr::Identity | Expr::Literal(_) | Expr::Var(_) | Expr::Array(None) => unreachable!(),
+        Expr::Path(..) => eval_path,
+        Expr::Try(..) => eval_try,
+        Expr::Pipe(_) => eval_pipe,
+        Expr::Comma(_) => eval_comma,
+        Expr::Neg(_) => eval_neg,
+        Expr::Binary(..) => eval_binary,
+        Expr::And(_) | Expr::Or(_) => eval_logic,
+        Expr::Alternative(_) => eval_alternative,
+        Expr::If(..) => eval_if,
+        Expr::Array(Some(_)) => eval_array,
+        Expr::Object(_) => eval_object,
+        Expr::Bind(..) => eval_bind,
+        Expr::Reduce(..) => eval_reduce,
+        Expr::Define(..) => eval_define,
+        Expr::Call(..) => eval_call,
+    };
+    // Each run takes up stack until it returns, so it is charged against
+    // the budget until then.
+    let left = budget.get();
+    if left == 0 {
+        return Err(EvalError::TooDeep);
+    }
+    budget.set(left - 1);
+    let result = run(expr, env, budget, input, out);
+    budget.set(left);
+    result
+}
+
+/// The outputs of a run, and the error that ended it, if any.
+struct Outputs {
+    values: Vec<JsonValue>,
+    end: Result<(), EvalError>,
+}
+
+impl Outputs {
+    /// Runs `run` and keeps its outputs. Running out of depth is returned
+    /// at once, since nothing may catch it.
+    fn collect(run: impl FnOnce(Out<'_>) -> Result<(), EvalError>) -> Result<Self, EvalError> {
+        let mut values = Vec::new();
+        let end = run(&mut |value| {
+            values.push(value);
+            Ok(())
+        });
+        if let Err(EvalError::TooDeep) = end {
+            return Err(EvalError::TooDeep);
+        }
+        Ok(Outputs { values, end })
+    }
+
+    /// Passes the outputs on again, then ends the way the run did.
+    fn replay(&self, out: Out<'_>) -> Result<(), EvalError> {
+        self.values
+            .iter()
+            .try_for_each(|value| out(value.clone()))?;
+        self.end.clone()
+    }
+}
+
+/// Runs the left operand of a chain of operators.
+type Left<'l> = &'l dyn Fn(Out<'_>) -> Result<(), EvalError>;
+
+/// Runs the operators of a chain after its left operand.
+type Chain<'c, 'f, T> = &'c dyn Fn(Left<'_>, &'f [T], Out<'_>) -> Result<(), EvalError>;
+
+/// Runs `steps` one after another, each on every output of the one before,
+/// starting from `input`, and passes the outputs of the last to `out`.
+///
+/// Steps stream into one another, which nests a level for each. A long
+/// chain is run a stretch of [`STRETCH`] steps at a time instead, with the
+/// outputs of each stretch collected for the next.
+fn steps<'f, S>(
+    steps: &'f [S],
+    input: JsonValue,
+    step: &dyn Fn(&'f S, JsonValue, Out<'_>) -> Result<(), EvalError>,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    match steps.len() <= STRETCH {
+        true => stream(steps, input, step, out),
+        false => stretched_steps(steps, input, step, out),
+    }
+}
+
+fn stretched_steps<'f, S>(
+    steps: &'f [S],
+    input: JsonValue,
+    step: &dyn Fn(&'f S, JsonValue, Out<'_>) -> Result<(), EvalError>,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let mut stretches = steps.chunks(STRETCH);
+    let last = stretches.next_back().expect("more than one stretch");
+    let mut outputs = Outputs {
+        values: vec![input],
+        end: Ok(()),
+    };
+    for stretch in stretches {
+        outputs = Outputs::collect(|collect| {
+            outputs.replay(&mut |value| stream(stretch, value, step, collect))
+        })?;
+    }
+    outputs.replay(&mut |value| stream(last, value, step, out))
+}
+
+fn stream<'f, S>(
+    steps: &'f [S],
+    input: JsonValue,
+    step: &dyn Fn(&'f S, JsonValue, Out<'_>) -> Result<(), EvalError>,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    match steps {
+        [] => out(input),
+        [last] => step(last, input, out),
+        [first, rest @ ..] => step(first, input, &mut |value| stream(rest, value, step, out)),
+    }
+}
+
+/// Runs a chain of operators that groups to the left. Each operator runs
+/// its left side for every output of its right, so it nests a level; like
+/// [`steps`], a long chain is run a stretch at a time, each stretch
+/// starting from the collected outputs of the ones before.
+fn operators<'f, T>(
+    first: Left<'_>,
+    rest: &'f [T],
+    run: Chain<'_, 'f, T>,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    match rest.len() <= STRETCH {
+        true => run(first, rest, out),
+        false => stretched_operators(first, rest, run, out),
+    }
+}
+
+fn stretched_operators<'f, T>(
+    first: Left<'_>,
+    rest: &'f [T],
+    run: Chain<'_, 'f, T>,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let mut stretches = rest.chunks(STRETCH);
+    let last = stretches.next_back().expect("more than one stretch");
+    let head = stretches.next().expect("more than one stretch");
+    let mut outputs = Outputs::collect(|collect| run(first, head, collect))?;
+    for stretch in stretches {
+        let left = |out: Out<'_>| outputs.replay(out);
+        let next = Outputs::collect(|collect| run(&left, stretch, collect))?;
+        outputs = next;
+    }
+    run(&|out| outputs.replay(out), last, out)
+}
+
+/// Runs the expressions that are [`single`]. Passing their output on from
+/// here, rather than from inside the run of each operand, keeps the rest
+/// of the pipe from nesting under them.
+fn eval_single<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    _: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let value = match expr {
+        Expr::Identity => input,
+        _ => value(expr, env, &input)?,
+    };
+    out(value)
+}
+
+/// Whether `expr` always has exactly one output, and can be run by
+/// [`value`].
+fn single(expr: &Expr) -> bool {
+    match expr {
+        Expr::Identity | Expr::Literal(_) | Expr::Var(_) | Expr::Array(None) => true,
+        Expr::Path(term, suffixes) => {
+            single(term)
+                && suffixes.iter().all(|suffix| match suffix {
+                    Suffix::Index(key) => single(key),
+                    Suffix::Slice(from, to) => from.iter().chain(to).all(single),
+                    Suffix::Iterate => false,
+                })
+        }
+        Expr::Neg(operand) => single(operand),
+        Expr::Binary(first, rest) => single(first) && rest.iter().all(|(_, b)| single(b)),
+        Expr::And(operands) | Expr::Or(operands) => operands.iter().all(single),
+        _ => false,
+    }
+}
+
+/// Runs an expression that is [`single`] and returns its output, raising
+/// the same errors in the same order as [`eval`] would.
+#[inline(never)]
+fn value(expr: &Expr, env: &Env<'_>, input: &JsonValue) -> Result<JsonValue, EvalError> {
+    let value = |expr| value(expr, env, input);
+    Ok(match expr {
+        Expr::Identity => input.clone(),
+        Expr::Literal(literal) => literal.clone(),
+        Expr::Var(name) => env.var(name).expect("checked when parsed").clone(),
+        Expr::Array(None) => JsonValue::Array(Vec::new()),
+        Expr::Path(term, suffixes) => {
+            let mut target = value(term)?;
+            for suffix in suffixes {
+                target = match suffix {
+                    Suffix::Index(key) => index(&target, &value(key)?)?,
+                    Suffix::Slice(from, to) => {
+                        let to = to.as_ref().map_or(Ok(JsonValue::Null), value)?;
+                        let from = from.as_ref().map_or(Ok(JsonValue::Null), value)?;
+                        slice(&target, &from, &to)?
+                    }
+                    Suffix::Iterate => unreachable!(),
+                };
+            }
+            target
+        }
+        Expr::Neg(operand) => match value(operand)? {
+            JsonValue::Number(n) => arithmetic(BinOp::Sub, &Number::from(0i64), &n),
+            value => return Err(invalid(format!("{} cannot be negated", describe(&value)))),
+        },
+        Expr::Binary(first, rest) => {
+            // The right operands run first, the last one first of all.
+            let mut rights = Vec::with_capacity(rest.len());
+            for (op, right) in rest.iter().rev() {
+                rights.push((*op, value(right)?));
+            }
+            rights
+                .iter()
+                .rev()
+                .try_fold(value(first)?, |a, (op, b)| binary(*op, a, b))?
+        }
+        Expr::And(operands) | Expr::Or(operands) => {
+            let is_and = matches!(expr, Expr::And(_));
+            let (first, rest) = operands.split_first().expect("at least one operand");
+            let mut result = value(first)?;
+            for operand in rest {
+                result = match truthy(&result) == is_and {
+                    true => JsonValue::Boolean(truthy(&value(operand)?)),
+                    false => JsonValue::Boolean(!is_and),
+                };
+            }
+            result
+        }
+        _ => unreachable!("not single"),
+    })
+}
+
+fn eval_pipe<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Pipe(stages) = expr else {
+        unreachable!()
+    };
+    let stage = |stage: &'f Expr, value, out: Out<'_>| eval(stage, env, budget, value, out);
+    steps(stages, input, &stage, out)
+}
+
+fn eval_comma<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Comma(items) = expr else {
+        unreachable!()
+    };
+    items
+        .iter()
+        .try_for_each(|item| eval(item, env, budget, input.clone(), out))
+}
+
+fn eval_define<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Define(defs, rest) = expr else {
+        unreachable!()
+    };
+    let env = defs
+        .iter()
+        .fold(env.clone(), |env, def| env.push(Binding::Func(def)));
+    eval(rest, &env, budget, input, out)
+}
+
+fn eval_path<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Path(term, suffixes) = expr else {
+        unreachable!()
+    };
+    let step = |suffix: &'f Suffix, target: JsonValue, out: Out<'_>| match suffix {
+        Suffix::Index(key) => eval(key, env, budget, input.clone(), &mut |key| {
+            out(index(&target, &key)?)
+        }),
+        Suffix::Slice(from, to) => {
+            eval_or_null(to.as_ref(), env, budget, input.clone(), &mut |to| {
+                eval_or_null(from.as_ref(), env, budget, input.clone(), &mut |from| {
+                    out(slice(&target, &from, &to)?)
+                })
+            })
+        }
+        Suffix::Iterate => match target {
+            JsonValue::Array(items) => items.into_iter().try_for_each(&mut *out),
+            JsonValue::Object(map) => map.into_iter().try_for_each(|(_, value)| out(value)),
+            _ => Err(invalid(format!(
+                "Cannot iterate over {}",
+                describe(&target)
+            ))),
+        },
+    };
+    eval(term, env, budget, input.clone(), &mut |target| {
+        steps(suffixes, target, &step, out)
+    })
+}
+
+fn eval_neg<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Neg(operand) = expr else {
+        unreachable!()
+    };
+    eval(operand, env, budget, input, &mut |value| match &value {
+        JsonValue::Number(n) => out(arithmetic(BinOp::Sub, &Number::from(0i64), n)),
+        _ => Err(invalid(format!("{} cannot be negated", describe(&value)))),
+    })
+}
+
+fn eval_try<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Try(body, handler) = expr else {
+        unreachable!()
+    };
+    match guarded(&mut *out, |out| eval(body, env, budget, input.clone(), out))? {
+        Ok(()) => Ok(()),
+        // Running out of depth is not the filter's to handle.
+        Err(EvalError::TooDeep) => Err(EvalError::TooDeep),
+        Err(e) => match handler {
+            Some(handler) => eval(handler, env, budget, e.into_value(), out),
+            None => Ok(()),
+        },
+    }
+}
+
+fn eval_binary<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Binary(first, rest) = expr else {
+        unreachable!()
+    };
+    let first = |out: Out<'_>| eval(first, env, budget, input.clone(), out);
+    let run = |left: Left<'_>, rest: &'f [(BinOp, Expr)], out: Out<'_>| {
+        operations(left, rest, env, budget, &input, out)
+    };
+    operators(&first, rest, &run, out)
+}
+
+/// Runs `left` and the operations after it, each output of the right
+/// operand before those of the left, as jq does.
+fn operations<'f>(
+    left: Left<'_>,
+    rest: &'f [(BinOp, Expr)],
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: &JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Some(((op, right), rest)) = rest.split_last() else {
+        return left(out);
+    };
+    eval(right, env, budget, input.clone(), &mut |b| {
+        operations(left, rest, env, budget, input, &mut |a| {
+            out(binary(*op, a, &b)?)
+        })
+    })
+}
+
+fn eval_logic<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let (is_and, operands) = match expr {
+        Expr::And(operands) => (true, operands),
+        Expr::Or(operands) => (false, operands),
+        _ => unreachable!(),
+    };
+    let (first, rest) = operands.split_first().expect("at least one operand");
+    let first = |out: Out<'_>| eval(first, env, budget, input.clone(), out);
+    let run = |left: Left<'_>, rest: &'f [Expr], out: Out<'_>| {
+        logic(is_and, left, rest, env, budget, &input, out)
+    };
+    operators(&first, rest, &run, out)
+}
+
+/// Runs `left` and the operands after it, chained with `and`, or with
+/// `is_and` false, `or`.
+fn logic<'f>(
+    is_and: bool,
+    left: Left<'_>,
+    operands: &'f [Expr],
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: &JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Some((right, operands)) = operands.split_last() else {
+        return left(out);
+    };
+    logic(is_and, left, operands, env, budget, input, &mut |a| {
+        if truthy(&a) != is_and {
+            return out(JsonValue::Boolean(!is_and));
+        }
+        eval(right, env, budget, input.clone(), &mut |b| {
+            out(JsonValue::Boolean(truthy(&b)))
+        })
+    })
+}
+
+fn eval_alternative<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Alternative(operands) = expr else {
+        unreachable!()
+    };
+    // `//` groups to the right, so each operand is tried in turn until one
+    // has outputs that are neither `null` nor `false`.
+    let (last, operands) = operands.split_last().expect("at least one operand");
+    for left in operands {
+        let mut any = false;
+        let result = guarded(&mut *out, |out| {
+            eval(
+                left,
+                env,
+                budget,
+                input.clone(),
+                &mut |a| match truthy(&a) {
+                    true => {
+                        any = true;
+                        out(a)
+                    }
+                    false => Ok(()),
+                },
+            )
+        })?;
+        // Other errors on the left count as no output.
+        if let Err(EvalError::TooDeep) = result {
+            return Err(EvalError::TooDeep);
+        }
+        if any {
+            return Ok(());
+        }
+    }
+    eval(last, env, budget, input, out)
+}
+
+fn eval_if<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::If(cond, then, otherwise) = expr else {
+        unreachable!()
+    };
+    if single(cond) {
+        let branch = match truthy(&value(cond, env, &input)?) {
+            true => then,
+            false => otherwise,
+        };
+        return eval(branch, env, budget, input, out);
+    }
+    eval(cond, env, budget, input.clone(), &mut |c| {
+        let branch = if truthy(&c) { then } else { otherwise };
+        eval(branch, env, budget, input.clone(), out)
+    })
+}
+
+fn eval_array<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Array(Some(body)) = expr else {
+        unreachable!()
+    };
+    let mut items = Vec::new();
+    eval(body, env, budget, input, &mut |value| {
+        items.push(value);
+        Ok(())
+    })?;
+    out(JsonValue::Array(items))
+}
+
+fn eval_object<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Object(entries) = expr else {
+        unreachable!()
+    };
+    // Each entry adds itself to every object built from the ones before.
+    let entry = |(key, value): &'f (Expr, Expr), object: JsonValue, out: Out<'_>| {
+        let JsonValue::Object(map) = object else {
+            unreachable!("entries build objects")
+        };
+        eval(key, env, budget, input.clone(), &mut |key| {
+            let JsonValue::String(key) = key else {
+                return Err(invalid(format!(
+                    "Object keys must be strings, not {}",
+                    type_name(&key)
+                )));
+            };
+            eval(value, env, budget, input.clone(), &mut |value| {
+                let mut map = map.clone();
+                map.insert(key.clone(), value);
+                out(JsonValue::Object(map))
+            })
+        })
+    };
+    steps(entries, JsonValue::Object(Map::new()), &entry, out)
+}
+
+fn eval_bind<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Bind(source, name, body) = expr else {
+        unreachable!()
+    };
+    eval(source, env, budget, input.clone(), &mut |value| {
+        let env = env.push(Binding::Var(name, value));
+        eval(body, &env, budget, input.clone(), out)
+    })
+}
+
+fn eval_reduce<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Reduce(source, name, init, update) = expr else {
+        unreachable!()
+    };
+    eval(init, env, budget, input.clone(), &mut |init| {
+        let mut acc = Some(init);
+        eval(source, env, budget, input.clone(), &mut |item| {
+            let env = env.push(Binding::Var(name, item));
+            let current = acc.take().unwrap_or(JsonValue::Null);
+            // The last output is the new state; none leaves null.
+            eval(update, &env, budget, current, &mut |value| {
+                acc = Some(value);
+                Ok(())
+            })
+        })?;
+        out(acc.take().unwrap_or(JsonValue::Null))
+    })
+}
+
+fn eval_call<'f>(
+    expr: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let Expr::Call(name, args) = expr else {
+        unreachable!()
+    };
+    match env.function(name, args.len()) {
+        Some((&Binding::Closure(_, body, ref closure_env), _)) => {
+            eval(body, closure_env, budget, input, out)
+        }
+        Some((&Binding::Func(def), def_env)) => {
+            let call_env = def
+                .params
+                .iter()
+                .zip(args)
+                .fold(def_env, |call_env, (param, arg)| {
+                    call_env.push(Binding::Closure(param, arg, env.clone()))
+                });
+            eval(&def.body, &call_env, budget, input, out)
+        }
+        _ => native(name, args, env, budget, input, out),
+    }
+}
+
+/// Like [`eval`], with a missing expression standing for `null`.
+fn eval_or_null<'f>(
+    expr: Option<&'f Expr>,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    match expr {
+        Some(expr) => eval(expr, env, budget, input, out),
+        None => out(JsonValue::Null),
+    }
+}
+
+/// Runs `body`, and tells apart its own errors, which are returned inside
+/// `Ok`, from those raised further down the pipe by `out`, which are not
+/// its to catch.
+fn guarded(
+    out: Out<'_>,
+    body: impl FnOnce(Out<'_>) -> Result<(), EvalError>,
+) -> Result<Result<(), EvalError>, EvalError> {
+    let mut downstream = None;
+    let result = body(&mut |value| out(value).inspect_err(|e| downstream = Some(e.clone())));
+    match downstream {
+        Some(e) => Err(e),
+        None => Ok(result),
+    }
+}
+
+#[inline(never)]
+fn native<'f>(
+    name: &str,
+    args: &'f [Expr],
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    match (name, args) {
+        ("empty", []) => Ok(()),
+        ("error", []) => Err(EvalError::Raised(input)),
+        ("error", [message]) => eval(message, env, budget, input, &mut |message| {
+            Err(EvalError::Raised(message))
+        }),
+        ("has", [key]) => eval(key, env, budget, input.clone(), &mut |key| {
+            let has = match (&input, &key) {
+                (JsonValue::Object(map), JsonValue::String(key)) => map.contains_key(key),
+                (JsonValue::Array(items), JsonValue::Number(i)) => {
+                    let i = to_f64(i);
+                    i >= 0.0 && i < items.len() as f64
+                }
+                _ => {
+                    return Err(invalid(format!(
+                        "Cannot check whether {} has a {} key",
+                        type_name(&input),
+                        type_name(&key)
+                    )))
+                }
+            };
+            out(JsonValue::Boolean(has))
+        }),
+        ("recurse", [f]) => recurse(f, env, budget, input, out),
+        ("range", [from, upto]) => eval(from, env, budget, input.clone(), &mut |from| {
+            eval(upto, env, budget, input.clone(), &mut |upto| {
+                let (JsonValue::Number(from), JsonValue::Number(upto)) = (&from, &upto) else {
+                    return Err(invalid("Range bounds must be numeric".to_owned()));
+                };
+                let (mut i, upto) = (to_f64(from), to_f64(upto));
+                while i < upto {
+                    out(number(i))?;
+                    i += 1.0;
+                }
+                Ok(())
+            })
+        }),
+        (_, []) => out(builtin(name, input)?),
+        _ => unreachable!("calls are checked when parsed"),
+    }
+}
+
+/// Runs `f` on `input`, then on each of its outputs in turn, depth first.
+/// Walks the tree with a stack of its own, so that deep values do not nest
+/// the run.
+fn recurse<'f>(
+    f: &'f Expr,
+    env: &Env<'f>,
+    budget: &Cell<usize>,
+    input: JsonValue,
+    out: Out<'_>,
+) -> Result<(), EvalError> {
+    let mut stack = vec![(vec![input].into_iter(), Ok(()))];
+    while let Some((values, _)) = stack.last_mut() {
+        let Some(value) = values.next() else {
+            stack.pop().expect("not empty").1?;
+            continue;
+        };
+        out(value.clone())?;
+        let children = Outputs::collect(|collect| eval(f, env, budget, value, collect))?;
+        stack.push((children.values.into_iter(), children.end));
+    }
+    Ok(())
+}
+
+/// Runs a function that takes no arguments and has one output. Kept apart
+/// from [`native`], whose frame stays on the stack while the rest of the
+/// pipe runs.
+#[inline(never)]
+fn builtin(name: &str, input: JsonValue) -> Result<JsonValue, EvalError> {
+    Ok(match name {
+        "not" => JsonValue::Boolean(!truthy(&input)),
+        "length" => match &input {
+            JsonValue::Null => number(0.0),
+            JsonValue::Number(n) => number(to_f64(n).abs()),
+            JsonValue::String(s) => number(s.chars().count() as f64),
+            JsonValue::Array(items) => number(items.len() as f64),
+            JsonValue::Object(map) => number(map.len() as f64),
+            JsonValue::Boolean(_) => {
+                return Err(invalid(format!("{} has no length", describe(&input))))
+            }
+        },
+        "keys" | "keys_unsorted" => match &input {
+            JsonValue::Object(map) => {
+                let mut keys: Vec<_> = map.keys().cloned().collect();
+                if name == "keys" {
+                    keys.sort();
+                }
+                JsonValue::Array(keys.into_iter().map(JsonValue::String).collect())
+            }
+            JsonValue::Array(items) => {
+                JsonValue::Array((0..items.len()).map(|i| number(i as f64)).collect())
+            }
+            _ => return Err(invalid(format!("{} has no keys", describe(&input)))),
+        },
+        "to_entries" => {
+            let entries: Vec<(JsonValue, JsonValue)> = match input {
+                JsonValue::Object(map) => map
+                    .into_iter()
+                    .map(|(key, value)| (JsonValue::String(key), value))
+                    .collect(),
+                JsonValue::Array(items) => items
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, value)| (JsonValue::Number(Number::from(i as u64)), value))
+                    .collect(),
+                _ => return Err(invalid(format!("{} has no keys", describe(&input)))),
+            };
+            JsonValue::Array(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let mut entry = Map::new();
+                        entry.insert("key".to_owned(), key);
+                        entry.insert("value".to_owned(), value);
+                        JsonValue::Object(entry)
+                    })
+                    .collect(),
+            )
+        }
+        "from_entries" => from_entries(&input)?,
+        "type" => JsonValue::String(type_name(&input).to_owned()),
+        "tostring" => match input {
+            JsonValue::String(_) => input,
+            _ => JsonValue::String(to_json(&input)?),
+        },
+        "tojson" => JsonValue::String(to_json(&input)?),
+        "tonumber" => match &input {
+            JsonValue::Number(_) => input,
+            JsonValue::String(s) => match Parser::new(s).and_then(|mut parser| parser.parse_root())
+            {
+                Ok(n @ JsonValue::Number(_)) => n,
+                _ => return Err(invalid(format!("Cannot parse '{}' as a number", s))),
+            },
+            _ => {
+                return Err(invalid(format!(
+                    "{} cannot be parsed as a number",
+                    describe(&input)
+                )))
+            }
+        },
+        "sort" => match input {
+            JsonValue::Array(mut items) => {
+                items.sort_by(order);
+                JsonValue::Array(items)
+            }
+            _ => {
+                return Err(invalid(format!(
+                    "{} cannot be sorted, as it is not an array",
+                    describe(&input)
+                )))
+            }
+        },
+        _ => unreachable!("calls are checked when parsed"),
+    })
+}
+
+fn from_entries(input: &JsonValue) -> Result<JsonValue, EvalError> {
+    let JsonValue::Array(entries) = input else {
+        return Err(invalid(format!("Cannot iterate over {}", describe(input))));
+    };
+    let mut map = Map::new();
+    for entry in entries {
+        let JsonValue::Object(entry) = entry else {
+            return Err(invalid(format!(
+                "Cannot index {} with \"key\"",
+                type_name(entry)
+            )));
+        };
+        let key = ["key", "k", "name", "Name", "K", "Key"]
+            .iter()
+            .find_map(|name| entry.get(name).filter(|key| **key != JsonValue::Null));
+        let key = match key {
+            Some(JsonValue::String(key)) => key.clone(),
+            Some(key) => to_json(key)?,
+            None => "null".to_owned(),
+        };
+        let value = ["value", "v", "Value", "V"]
+            .iter()
+            .find_map(|name| entry.get(name))
+            .cloned()
+            .unwrap_or(JsonValue::Null);
+        map.insert(key, value);
+    }
+    Ok(JsonValue::Object(map))
+}
+
+fn invalid(message: String) -> EvalError {
+    EvalError::Invalid(message)
+}
+
+impl EvalError {
+    /// What `catch` sees.
+    fn into_value(self) -> JsonValue {
+        match self {
+            EvalError::Invalid(message) => JsonValue::String(message),
+            EvalError::Raised(value) => value,
+            EvalError::TooDeep => JsonValue::String(EvalError::TooDeep.to_string()),
+        }
+    }
+}
+
+fn truthy(value: &JsonValue) -> bool {
+    !matches!(value, JsonValue::Null | JsonValue::Boolean(false))
+}
+
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Boolean(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+fn to_json(value: &JsonValue) -> Result<String, EvalError> {
+    to_string(value).map_err(|e| invalid(e.to_string()))
+}
+
+/// The type and text of a value for an error message, such as
+/// `number (1)`, cut short if long.
+fn describe(value: &JsonValue) -> String {
+    let mut text = to_string(value).unwrap_or_default();
+    if let Some((cut, _)) = text.char_indices().nth(30) {
+        text.truncate(cut);
+        text.push_str("...");
+    }
+    format!("{} ({})", type_name(value), text)
+}
+
+fn to_f64(n: &Number) -> f64 {
+    n.as_f64()
+        .or_else(|| n.as_i64().map(|i| i as f64))
+        .or_else(|| n.as_u64().map(|u| u as f64))
+        .unwrap_or(f64::NAN)
+}
+
+/// A number, stored as an integer when it is a whole one that a double
+/// holds exactly.
+fn number(f: f64) -> JsonValue {
+    let n = match f.fract() == 0.0 && f.abs() <= 9_007_199_254_740_992.0 {
+        true => Number::from(f as i64),
+        false => Number::from(f),
+    };
+    JsonValue::Number(n)
+}
+
+fn index(target: &JsonValue, key: &JsonValue) -> Result<JsonValue, EvalError> {
+    match (target, key) {
+        (JsonValue::Object(map), JsonValue::String(key)) => {
+            Ok(map.get(key).cloned().unwrap_or(JsonValue::Null))
+        }
+        (JsonValue::Array(items), JsonValue::Number(i)) => {
+            let i = to_f64(i).floor();
+            let i = if i < 0.0 { items.len() as f64 + i } else { i };
+            match i >= 0.0 {
+                true => Ok(items.get(i as usize).cloned().unwrap_or(JsonValue::Null)),
+                false => Ok(JsonValue::Null),
+            }
+        }
+        (JsonValue::Null, JsonValue::String(_) | JsonValue::Number(_)) => Ok(JsonValue::Null),
+        _ => {
+            let key = match key {
+                JsonValue::String(key) => format!("\"{}\"", key),
+                _ => type_name(key).to_owned(),
+            };
+            Err(invalid(format!(
+                "Cannot index {} with {}",
+                type_name(target),
+                key
+            )))
+        }
+    }
+}
+
+fn slice(target: &JsonValue, from: &JsonValue, to: &JsonValue) -> Result<JsonValue, EvalError> {
+    let len = match target {
+        JsonValue::Null => return Ok(JsonValue::Null),
+        JsonValue::Array(items) => items.len(),
+        JsonValue::String(s) => s.chars().count(),
+        _ => {
+            return Err(invalid(format!(
+                "Cannot index {} with object",
+                type_name(target)
+            )))
+        }
+    };
+    let bound = |bound: &JsonValue, default: usize, round: fn(f64) -> f64| match bound {
+        JsonValue::Null => Ok(default),
+        JsonValue::Number(n) => {
+            let i = round(to_f64(n));
+            let i = if i < 0.0 { len as f64 + i } else { i };
+            Ok(i.clamp(0.0, len as f64) as usize)
+        }
+        _ => Err(invalid(
+            "Start and end indices of an array slice must be numbers".to_owned(),
+        )),
+    };
+    let from = bound(from, 0, f64::floor)?;
+    let to = bound(to, len, f64::ceil)?.max(from);
+    Ok(match target {
+        JsonValue::Array(items) => JsonValue::Array(items[from..to].to_vec()),
+        JsonValue::String(s) => JsonValue::String(s.chars().skip(from).take(to - from).collect()),
+        _ => unreachable!("checked above"),
+    })
+}
+
+fn binary(op: BinOp, a: JsonValue, b: &JsonValue) -> Result<JsonValue, EvalError> {
+    let cmp = |wanted: fn(Ordering) -> bool| Ok(JsonValue::Boolean(wanted(order(&a, b))));
+    let fail = |verb: &str| {
+        Err(invalid(format!(
+            "{} and {} cannot be {}",
+            describe(&a),
+            describe(b),
+            verb
+        )))
+    };
+    match op {
+        BinOp::Eq => cmp(Ordering::is_eq),
+        BinOp::Ne => cmp(Ordering::is_ne),
+        BinOp::Lt => cmp(Ordering::is_lt),
+        BinOp::Le => cmp(Ordering::is_le),
+        BinOp::Gt => cmp(Ordering::is_gt),
+        BinOp::Ge => cmp(Ordering::is_ge),
+        BinOp::Add => match (a, b) {
+            (JsonValue::Null, b) => Ok(b.clone()),
+            (a, JsonValue::Null) => Ok(a),
+            (JsonValue::Number(x), JsonValue::Number(y)) => Ok(arithmetic(op, &x, y)),
+            (JsonValue::String(mut x), JsonValue::String(y)) => {
+                x.push_str(y);
+                Ok(JsonValue::String(x))
+            }
+            (JsonValue::Array(mut x), JsonValue::Array(y)) => {
+                x.extend(y.iter().cloned());
+                Ok(JsonValue::Array(x))
+            }
+            (JsonValue::Object(mut x), JsonValue::Object(y)) => {
+                for (key, value) in y {
+                    x.insert(key.clone(), value.clone());
+                }
+                Ok(JsonValue::Object(x))
+            }
+            (a, b) => Err(invalid(format!(
+                "{} and {} cannot be added",
+                describe(&a),
+                describe(b)
+            ))),
+        },
+        BinOp::Sub => match (&a, b) {
+            (JsonValue::Number(x), JsonValue::Number(y)) => Ok(arithmetic(op, x, y)),
+            (JsonValue::Array(x), JsonValue::Array(y)) => Ok(JsonValue::Array(
+                x.iter()
+                    .filter(|item| !y.iter().any(|other| order(item, other).is_eq()))
+                    .cloned()
+                    .collect(),
+            )),
+            _ => fail("subtracted"),
+        },
+        BinOp::Mul => match (&a, b) {
+            (JsonValue::Number(x), JsonValue::Number(y)) => Ok(arithmetic(op, x, y)),
+            (JsonValue::Object(_), JsonValue::Object(_)) => Ok(deep_merge(a, b)),
+            (JsonValue::String(s), JsonValue::Number(n))
+            | (JsonValue::Number(n), JsonValue::String(s)) => {
+                let times = to_f64(n) as i64;
+                if times <= 0 {
+                    return Ok(JsonValue::Null);
+                }
+                match s.len().checked_mul(times as usize) {
+                    Some(len) if len <= MAX_REPEAT_LENGTH => {
+                        Ok(JsonValue::String(s.repeat(times as usize)))
+                    }
+                    _ => fail("multiplied because the result is too long"),
+                }
+            }
+            _ => fail("multiplied"),
+        },
+        BinOp::Div => match (&a, b) {
+            (JsonValue::Number(_), JsonValue::Number(y)) if to_f64(y) == 0.0 => {
+                fail("divided because the divisor is zero")
+            }
+            (JsonValue::Number(x), JsonValue::Number(y)) => Ok(arithmetic(op, x, y)),
+            (JsonValue::String(s), JsonValue::String(sep)) => {
+                let parts: Vec<_> = match (s.is_empty(), sep.is_empty()) {
+                    (true, _) => Vec::new(),
+                    (false, true) => s.chars().map(String::from).collect(),
+                    (false, false) => s.split(sep.as_str()).map(String::from).collect(),
+                };
+                Ok(JsonValue::Array(
+                    parts.into_iter().map(JsonValue::String).collect(),
+                ))
+            }
+            _ => fail("divided"),
+        },
+        BinOp::Rem => match (&a, b) {
+            (JsonValue::Number(x), JsonValue::Number(y)) => {
+                let (x, y) = (to_f64(x) as i64, to_f64(y) as i64);
+                match y {
+                    0 => fail("divided because the divisor is zero"),
+                    // Only `i64::MIN % -1` overflows.
+                    _ => Ok(number(x.checked_rem(y).unwrap_or(0) as f64)),
+                }
+            }
+            _ => fail("divided"),
+        },
+    }
+}
+
+fn arithmetic(op: BinOp, x: &Number, y: &Number) -> JsonValue {
+    if let (Some(x), Some(y)) = (x.as_i64(), y.as_i64()) {
+        let exact = match op {
+            BinOp::Add => x.checked_add(y),
+            BinOp::Sub => x.checked_sub(y),
+            BinOp::Mul => x.checked_mul(y),
+            _ => None,
+        };
+        if let Some(n) = exact {
+            return JsonValue::Number(Number::from(n));
+        }
+    }
+    let (x, y) = (to_f64(x), to_f64(y));
+    number(match op {
+        BinOp::Add => x + y,
+        BinOp::Sub => x - y,
+        BinOp::Mul => x * y,
+        _ => x / y,
+    })
+}
+
+/// Merges `b` into `a`, recursing where both have an object under a key.
+fn deep_merge(a: JsonValue, b: &JsonValue) -> JsonValue {
+    match (a, b) {
+        (JsonValue::Object(mut a), JsonValue::Object(b)) => {
+            for (key, value) in b {
+                let merged = match a.get(key) {
+                    Some(old) => deep_merge(old.clone(), value),
+                    None => value.clone(),
+                };
+                a.insert(key.clone(), merged);
+            }
+            JsonValue::Object(a)
+        }
+        (_, b) => b.clone(),
+    }
+}
+
+/// jq's total order: `null`, `false`, `true`, numbers, strings, arrays,
+/// then objects, which compare their sorted keys first and then the values
+/// under them. Numbers compare by value, so `-0` equals `0`, and NaN comes
+/// below every other number.
+fn order(a: &JsonValue, b: &JsonValue) -> Ordering {
+    let rank = |value: &JsonValue| match value {
+        JsonValue::Null => 0,
+        JsonValue::Boolean(false) => 1,
+        JsonValue::Boolean(true) => 2,
+        JsonValue::Number(_) => 3,
+        JsonValue::String(_) => 4,
+        JsonValue::Array(_) => 5,
+        JsonValue::Object(_) => 6,
+    };
+    match (a, b) {
+        (JsonValue::Number(x), JsonValue::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            _ => {
+                let (x, y) = (to_f64(x), to_f64(y));
+                x.partial_cmp(&y)
+                    .unwrap_or_else(|| y.is_nan().cmp(&x.is_nan()))
+            }
+        },
+        (JsonValue::String(x), JsonValue::String(y)) => x.cmp(y),
+        (JsonValue::Array(x), JsonValue::Array(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(x, y)| order(x, y))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (JsonValue::Object(x), JsonValue::Object(y)) => {
+            let mut x_keys: Vec<_> = x.keys().collect();
+            let mut y_keys: Vec<_> = y.keys().collect();
+            x_keys.sort();
+            y_keys.sort();
+            x_keys.cmp(&y_keys).then_with(|| {
+                x_keys
+                    .iter()
+                    .map(|key| order(&x[key.as_str()], &y[key.as_str()]))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
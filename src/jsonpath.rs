@@ -0,0 +1,1024 @@
+use crate::errors::{JsonPathError, JsonPathErrorKind};
+use crate::number::Number;
+use crate::parser::{JsonValue, Parser};
+use crate::regex::{Regex, RegexError};
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt::Write;
+
+/// The largest index that I-JSON numbers can hold exactly, 2^53-1.
+const MAX_INDEX: i64 = (1 << 53) - 1;
+
+/// How deeply filters, parentheses and function calls may nest.
+const MAX_NESTING: usize = 64;
+
+/// A compiled JSONPath query (RFC 9535), such as
+/// `$.store.book[?@.price < 10].title`.
+///
+/// A query is a `$` followed by segments, each of which selects from the
+/// nodes the one before it selected:
+///
+/// - `.name`, `['name']` and `[0]` select a member or element, and `.*`
+///   or `[*]` all of them. Negative indices count from the end.
+/// - `[start:end:step]` selects a slice of an array.
+/// - `[?expr]` selects the members or elements for which a filter holds.
+///   Inside it `@` is the node being tested and `$` the root, and
+///   expressions can compare values, test that a query selects something,
+///   and call `length`, `count`, `match`, `search` and `value`.
+/// - `[a, b]` selects with each selector in turn, and `..` before a
+///   selector applies it to the node and everything below it.
+///
+/// `match` and `search` take I-Regexp (RFC 9485) patterns. Of the `\p{..}`
+/// categories only `L`, `Lu`, `Ll`, `N` and `Cc` are known. A literal
+/// pattern using another is an error; one read from the document never
+/// matches.
+///
+/// ```
+/// use jimson::jsonpath::JsonPath;
+/// use jimson::parser::{JsonValue, Parser};
+///
+/// let input = r#"{"store": {"book": [
+///     {"title": "Sayings of the Century", "price": 8.95},
+///     {"title": "Sword of Honour", "price": 12.99},
+///     {"title": "Moby Dick", "price": 8.99}
+/// ]}}"#;
+/// let value = Parser::new(input).unwrap().parse_root().unwrap();
+///
+/// let path = JsonPath::parse("$.store.book[?@.price < 10].title").unwrap();
+/// let found = path.query(&value);
+/// assert_eq!(found[0].0, "$['store']['book'][0]['title']");
+/// assert_eq!(found[1].0, "$['store']['book'][2]['title']");
+/// assert_eq!(found[1].1, &JsonValue::String("Moby Dick".into()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Child(Vec<Selector>),
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Debug, Clone)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Filter(Logical),
+}
+
+#[derive(Debug, Clone)]
+enum Logical {
+    Or(Vec<Logical>),
+    And(Vec<Logical>),
+    Not(Box<Logical>),
+    /// True if the query selects at least one node.
+    Exists(Query),
+    Compare(Comparable, Op, Comparable),
+    /// A call to `match` or `search`.
+    Call(Call),
+}
+
+#[derive(Debug, Clone)]
+struct Query {
+    /// Starts at `$` rather than `@`.
+    absolute: bool,
+    segments: Vec<Segment>,
+}
+
+/// Something with a value, or nothing: a literal, a query that selects at
+/// most one node, or a function. Function arguments are these too, where a
+/// query may select any number of nodes if the function takes a node list.
+#[derive(Debug, Clone)]
+enum Comparable {
+    Literal(JsonValue),
+    Query(Query),
+    Call(Call),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+struct Call {
+    function: Function,
+    args: Vec<Comparable>,
+    /// The pattern of `match` or `search`, compiled up front when it is a
+    /// literal. `Some(None)` if that literal is not a valid pattern.
+    regex: Option<Option<Regex>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Length,
+    Count,
+    Match,
+    Search,
+    Value,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Param {
+    /// A single value, or nothing.
+    Value,
+    /// Any number of nodes.
+    Nodes,
+}
+
+impl Function {
+    fn params(self) -> &'static [Param] {
+        match self {
+            Function::Length => &[Param::Value],
+            Function::Count | Function::Value => &[Param::Nodes],
+            Function::Match | Function::Search => &[Param::Value, Param::Value],
+        }
+    }
+
+    /// Whether the function returns true or false, rather than a value.
+    fn is_logical(self) -> bool {
+        matches!(self, Function::Match | Function::Search)
+    }
+}
+
+impl JsonPath {
+    /// Compiles a query.
+    pub fn parse(path: &str) -> Result<Self, JsonPathError> {
+        let mut parser = PathParser {
+            src: path,
+            pos: 0,
+            nesting: 0,
+        };
+        if !parser.eat('$') {
+            return Err(parser.unexpected());
+        }
+        let segments = parser.segments()?;
+        if parser.pos < path.len() {
+            return Err(parser.unexpected());
+        }
+        Ok(JsonPath { segments })
+    }
+
+    /// Runs the query against `value`, and returns the nodes it selects in
+    /// order, each with its normalized path such as `$['book'][0]`.
+    pub fn query<'v>(&self, value: &'v JsonValue) -> Vec<(String, &'v JsonValue)> {
+        select(&self.segments, vec![("$".to_owned(), value)], value)
+    }
+}
+
+impl std::str::FromStr for JsonPath {
+    type Err = JsonPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl JsonValue {
+    /// Runs a JSONPath query, as [`JsonPath::query`] does.
+    ///
+    /// ```
+    /// use jimson::parser::Parser;
+    ///
+    /// let value = Parser::new(r#"{"a": [1, 2, 3, 4]}"#).unwrap().parse_root().unwrap();
+    /// let found = value.query("$.a[-2:]").unwrap();
+    /// let paths: Vec<_> = found.iter().map(|(path, _)| path.as_str()).collect();
+    /// assert_eq!(paths, ["$['a'][2]", "$['a'][3]"]);
+    /// ```
+    pub fn query(&self, path: &str) -> Result<Vec<(String, &JsonValue)>, JsonPathError> {
+        Ok(JsonPath::parse(path)?.query(self))
+    }
+}
+
+/// One step down from an array or object.
+enum Edge<'v> {
+    Key(&'v str),
+    Index(usize),
+}
+
+/// Keeps track of where a node is. Queries inside filters only need the
+/// nodes, so they keep track of nothing.
+trait Trail: Sized {
+    fn join(&self, edge: Edge<'_>) -> Self;
+}
+
+impl Trail for () {
+    fn join(&self, _: Edge<'_>) -> Self {}
+}
+
+/// A normalized path, as in RFC 9535 section 2.7.
+impl Trail for String {
+    fn join(&self, edge: Edge<'_>) -> Self {
+        let mut path = self.clone();
+        match edge {
+            Edge::Index(i) => {
+                let _ = write!(path, "[{}]", i);
+            }
+            Edge::Key(key) => {
+                path.push_str("['");
+                for ch in key.chars() {
+                    match ch {
+                        '\'' => path.push_str("\\'"),
+                        '\\' => path.push_str("\\\\"),
+                        '\u{8}' => path.push_str("\\b"),
+                        '\u{c}' => path.push_str("\\f"),
+                        '\n' => path.push_str("\\n"),
+                        '\r' => path.push_str("\\r"),
+                        '\t' => path.push_str("\\t"),
+                        ch if ch < ' ' => {
+                            let _ = write!(path, "\\u{:04x}", ch as u32);
+                        }
+                        ch => path.push(ch),
+                    }
+                }
+                path.push_str("']");
+            }
+        }
+        path
+    }
+}
+
+fn children(value: &JsonValue) -> Vec<(Edge<'_>, &JsonValue)> {
+    match value {
+        JsonValue::Object(map) => map
+            .iter()
+            .map(|(key, value)| (Edge::Key(key), value))
+            .collect(),
+        JsonValue::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (Edge::Index(i), value))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn select<'v, T: Trail>(
+    segments: &[Segment],
+    mut nodes: Vec<(T, &'v JsonValue)>,
+    root: &'v JsonValue,
+) -> Vec<(T, &'v JsonValue)> {
+    for segment in segments {
+        let mut selected = Vec::new();
+        for (trail, value) in &nodes {
+            match segment {
+                Segment::Child(selectors) => apply(selectors, trail, value, root, &mut selected),
+                Segment::Descendant(selectors) => {
+                    descend(selectors, trail, value, root, &mut selected)
+                }
+            }
+        }
+        nodes = selected;
+    }
+    nodes
+}
+
+/// Applies the selectors to `value` and then to everything below it, in
+/// document order.
+fn descend<'v, T: Trail>(
+    selectors: &[Selector],
+    trail: &T,
+    value: &'v JsonValue,
+    root: &'v JsonValue,
+    out: &mut Vec<(T, &'v JsonValue)>,
+) {
+    apply(selectors, trail, value, root, out);
+    for (edge, child) in children(value) {
+        descend(selectors, &trail.join(edge), child, root, out);
+    }
+}
+
+fn apply<'v, T: Trail>(
+    selectors: &[Selector],
+    trail: &T,
+    value: &'v JsonValue,
+    root: &'v JsonValue,
+    out: &mut Vec<(T, &'v JsonValue)>,
+) {
+    for selector in selectors {
+        match (selector, value) {
+            (Selector::Name(name), JsonValue::Object(map)) => {
+                if let Some((key, child)) = map.get_key_value(name) {
+                    out.push((trail.join(Edge::Key(key)), child));
+                }
+            }
+            (Selector::Wildcard, _) => out.extend(
+                children(value)
+                    .into_iter()
+                    .map(|(edge, child)| (trail.join(edge), child)),
+            ),
+            (Selector::Index(i), JsonValue::Array(items)) => {
+                let len = items.len() as i64;
+                let i = if *i < 0 { len + i } else { *i };
+                if (0..len).contains(&i) {
+                    let i = i as usize;
+                    out.push((trail.join(Edge::Index(i)), &items[i]));
+                }
+            }
+            (Selector::Slice(start, end, step), JsonValue::Array(items)) => {
+                for i in slice(*start, *end, *step, items.len()) {
+                    out.push((trail.join(Edge::Index(i)), &items[i]));
+                }
+            }
+            (Selector::Filter(expr), _) => {
+                for (edge, child) in children(value) {
+                    if test(expr, child, root) {
+                        out.push((trail.join(edge), child));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The indices a slice selects from an array of `len` elements, following
+/// RFC 9535 section 2.3.4.2.2.
+fn slice(start: Option<i64>, end: Option<i64>, step: Option<i64>, len: usize) -> Vec<usize> {
+    let len = len as i64;
+    let step = step.unwrap_or(1);
+    let normalize = |i: i64| if i >= 0 { i } else { len + i };
+    let mut indices = Vec::new();
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        let mut i = lower;
+        while i < upper {
+            indices.push(i as usize);
+            i += step;
+        }
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = normalize(end.unwrap_or(-len - 1)).clamp(-1, len - 1);
+        let mut i = upper;
+        while lower < i {
+            indices.push(i as usize);
+            i += step;
+        }
+    }
+    indices
+}
+
+fn nodes<'a>(query: &Query, current: &'a JsonValue, root: &'a JsonValue) -> Vec<&'a JsonValue> {
+    let start = if query.absolute { root } else { current };
+    select(&query.segments, vec![((), start)], root)
+        .into_iter()
+        .map(|(_, value)| value)
+        .collect()
+}
+
+fn test(expr: &Logical, current: &JsonValue, root: &JsonValue) -> bool {
+    match expr {
+        Logical::Or(terms) => terms.iter().any(|term| test(term, current, root)),
+        Logical::And(terms) => terms.iter().all(|term| test(term, current, root)),
+        Logical::Not(expr) => !test(expr, current, root),
+        Logical::Exists(query) => !nodes(query, current, root).is_empty(),
+        Logical::Compare(left, op, right) => {
+            let left = value(left, current, root);
+            let right = value(right, current, root);
+            compare(left.as_deref(), *op, right.as_deref())
+        }
+        Logical::Call(call) => call_logical(call, current, root),
+    }
+}
+
+/// The value of a comparable, or `None` for nothing.
+fn value<'a>(
+    comparable: &'a Comparable,
+    current: &'a JsonValue,
+    root: &'a JsonValue,
+) -> Option<Cow<'a, JsonValue>> {
+    match comparable {
+        Comparable::Literal(value) => Some(Cow::Borrowed(value)),
+        Comparable::Query(query) => match nodes(query, current, root)[..] {
+            [value] => Some(Cow::Borrowed(value)),
+            _ => None,
+        },
+        Comparable::Call(call) => call_value(call, current, root),
+    }
+}
+
+fn call_value<'a>(
+    call: &'a Call,
+    current: &'a JsonValue,
+    root: &'a JsonValue,
+) -> Option<Cow<'a, JsonValue>> {
+    let count = |n: usize| Some(Cow::Owned(JsonValue::Number(Number::from(n as u64))));
+    let arg_nodes = || match &call.args[0] {
+        Comparable::Query(query) => nodes(query, current, root),
+        _ => unreachable!("node list arguments are checked when parsed"),
+    };
+    match call.function {
+        Function::Length => match value(&call.args[0], current, root)?.as_ref() {
+            JsonValue::String(s) => count(s.chars().count()),
+            JsonValue::Array(items) => count(items.len()),
+            JsonValue::Object(map) => count(map.len()),
+            _ => None,
+        },
+        Function::Count => count(arg_nodes().len()),
+        Function::Value => match arg_nodes()[..] {
+            [value] => Some(Cow::Borrowed(value)),
+            _ => None,
+        },
+        Function::Match | Function::Search => unreachable!("checked when parsed"),
+    }
+}
+
+fn call_logical(call: &Call, current: &JsonValue, root: &JsonValue) -> bool {
+    let text = value(&call.args[0], current, root);
+    let pattern = value(&call.args[1], current, root);
+    let (Some(JsonValue::String(text)), Some(JsonValue::String(pattern))) =
+        (text.as_deref(), pattern.as_deref())
+    else {
+        return false;
+    };
+    let compiled;
+    let regex = match &call.regex {
+        Some(regex) => regex.as_ref(),
+        None => {
+            compiled = Regex::new(pattern).ok();
+            compiled.as_ref()
+        }
+    };
+    match regex {
+        Some(regex) if call.function == Function::Match => regex.is_match(text),
+        Some(regex) => regex.is_found(text),
+        None => false,
+    }
+}
+
+fn compare(left: Option<&JsonValue>, op: Op, right: Option<&JsonValue>) -> bool {
+    match op {
+        Op::Eq => equal(left, right),
+        Op::Ne => !equal(left, right),
+        Op::Lt => less(left, right),
+        Op::Le => less(left, right) || equal(left, right),
+        Op::Gt => less(right, left),
+        Op::Ge => less(right, left) || equal(left, right),
+    }
+}
+
+/// Nothing equals only nothing.
+fn equal(left: Option<&JsonValue>, right: Option<&JsonValue>) -> bool {
+    match (left, right) {
+        (None, None) => true,
+        (Some(left), Some(right)) => same(left, right),
+        _ => false,
+    }
+}
+
+/// Only numbers and strings are ordered; strings by code point.
+fn less(left: Option<&JsonValue>, right: Option<&JsonValue>) -> bool {
+    match (left, right) {
+        (Some(JsonValue::Number(a)), Some(JsonValue::Number(b))) => {
+            compare_numbers(a, b) == Some(Ordering::Less)
+        }
+        (Some(JsonValue::String(a)), Some(JsonValue::String(b))) => a < b,
+        _ => false,
+    }
+}
+
+/// Deep equality, where `1` and `1.0` are the same number and the order of
+/// object members does not matter.
+pub(crate) fn same(left: &JsonValue, right: &JsonValue) -> bool {
+    match (left, right) {
+        (JsonValue::Number(a), JsonValue::Number(b)) => {
+            compare_numbers(a, b) == Some(Ordering::Equal)
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+        }
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| same(a, b)))
+        }
+        _ => left == right,
+    }
+}
+
+fn compare_numbers(a: &Number, b: &Number) -> Option<Ordering> {
+    let exact = |n: &Number| {
+        n.as_i64()
+            .map(i128::from)
+            .or_else(|| n.as_u64().map(i128::from))
+    };
+    match (exact(a), exact(b)) {
+        (Some(a), Some(b)) => Some(a.cmp(&b)),
+        _ => {
+            let approx = |n: &Number| n.as_f64().or_else(|| exact(n).map(|i| i as f64));
+            approx(a)?.partial_cmp(&approx(b)?)
+        }
+    }
+}
+
+struct PathParser<'a> {
+    src: &'a str,
+    pos: usize,
+    /// How many filters, parentheses and calls the parser is inside.
+    nesting: usize,
+}
+
+impl PathParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        let found = self.peek() == Some(ch);
+        if found {
+            self.pos += ch.len_utf8();
+        }
+        found
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let found = self.src[self.pos..].starts_with(s);
+        if found {
+            self.pos += s.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, ch: char) -> Result<(), JsonPathError> {
+        match self.eat(ch) {
+            true => Ok(()),
+            false => Err(self.unexpected()),
+        }
+    }
+
+    fn skip_space(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, kind: JsonPathErrorKind, offset: usize) -> JsonPathError {
+        JsonPathError { kind, offset }
+    }
+
+    fn unexpected(&self) -> JsonPathError {
+        let kind = match self.peek() {
+            Some(ch) => JsonPathErrorKind::UnexpectedChar(ch),
+            None => JsonPathErrorKind::UnexpectedEnd,
+        };
+        self.error(kind, self.pos)
+    }
+
+    /// Runs `parse` one level of nesting deeper.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, JsonPathError>,
+    ) -> Result<T, JsonPathError> {
+        if self.nesting == MAX_NESTING {
+            return Err(self.error(JsonPathErrorKind::TooDeep, self.pos));
+        }
+        self.nesting += 1;
+        let result = parse(self);
+        self.nesting -= 1;
+        result
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>, JsonPathError> {
+        let mut segments = Vec::new();
+        loop {
+            let before = self.pos;
+            self.skip_space();
+            if self.eat('[') {
+                segments.push(Segment::Child(self.bracketed()?));
+            } else if self.eat_str("..") {
+                let selectors = match self.eat('[') {
+                    true => self.bracketed()?,
+                    false => vec![self.dotted()?],
+                };
+                segments.push(Segment::Descendant(selectors));
+            } else if self.eat('.') {
+                segments.push(Segment::Child(vec![self.dotted()?]));
+            } else {
+                // The space belongs to whatever follows the query.
+                self.pos = before;
+                return Ok(segments);
+            }
+        }
+    }
+
+    /// Reads the `*` or member name after a `.` or `..`.
+    fn dotted(&mut self) -> Result<Selector, JsonPathError> {
+        if self.eat('*') {
+            return Ok(Selector::Wildcard);
+        }
+        let start = self.pos;
+        match self.peek() {
+            Some(ch) if ch.is_ascii_alphabetic() || ch == '_' || !ch.is_ascii() => {}
+            _ => return Err(self.unexpected()),
+        }
+        while self
+            .peek()
+            .is_some_and(|ch| ch.is_ascii_alphanumeric() || ch == '_' || !ch.is_ascii())
+        {
+            self.bump();
+        }
+        Ok(Selector::Name(self.src[start..self.pos].to_owned()))
+    }
+
+    /// Reads the selectors after a `[`, and the `]`.
+    fn bracketed(&mut self) -> Result<Vec<Selector>, JsonPathError> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_space();
+            selectors.push(self.selector()?);
+            self.skip_space();
+            if self.eat(']') {
+                return Ok(selectors);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, JsonPathError> {
+        match self.peek() {
+            Some('\'' | '"') => return Ok(Selector::Name(self.string()?)),
+            Some('*') => {
+                self.pos += 1;
+                return Ok(Selector::Wildcard);
+            }
+            Some('?') => {
+                self.pos += 1;
+                self.skip_space();
+                return Ok(Selector::Filter(self.nested(Self::logical_or)?));
+            }
+            _ => {}
+        }
+        let start = self.int()?;
+        self.skip_space();
+        if !self.eat(':') {
+            return match start {
+                Some(i) => Ok(Selector::Index(i)),
+                None => Err(self.unexpected()),
+            };
+        }
+        self.skip_space();
+        let end = self.int()?;
+        self.skip_space();
+        let step = match self.eat(':') {
+            true => {
+                self.skip_space();
+                self.int()?
+            }
+            false => None,
+        };
+        Ok(Selector::Slice(start, end, step))
+    }
+
+    /// Reads an index or slice bound, if there is one.
+    fn int(&mut self) -> Result<Option<i64>, JsonPathError> {
+        let start = self.pos;
+        self.eat('-');
+        let digits = self.pos;
+        while self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let text = &self.src[start..self.pos];
+        let leading_zero = self.src[digits..self.pos].starts_with('0') && text.len() > 1;
+        match text {
+            "" => Ok(None),
+            _ if leading_zero || text == "-" => {
+                Err(self.error(JsonPathErrorKind::InvalidNumber, start))
+            }
+            _ => match text.parse::<i64>() {
+                Ok(i) if (-MAX_INDEX..=MAX_INDEX).contains(&i) => Ok(Some(i)),
+                _ => Err(self.error(JsonPathErrorKind::InvalidNumber, start)),
+            },
+        }
+    }
+
+    /// Reads a string literal in single or double quotes.
+    fn string(&mut self) -> Result<String, JsonPathError> {
+        let quote = self.bump();
+        let mut s = String::new();
+        loop {
+            let at = self.pos;
+            let invalid = move || JsonPathError {
+                kind: JsonPathErrorKind::InvalidString,
+                offset: at,
+            };
+            match self.bump() {
+                None => return Err(self.unexpected()),
+                ch if ch == quote => return Ok(s),
+                Some('\\') => {
+                    let ch = match self.bump() {
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some(ch @ ('/' | '\\')) => ch,
+                        ch if ch == quote => quote.expect("a quote"),
+                        Some('u') => self.unicode_escape().ok_or_else(invalid)?,
+                        _ => return Err(invalid()),
+                    };
+                    s.push(ch);
+                }
+                Some(ch) if ch < ' ' => return Err(invalid()),
+                Some(ch) => s.push(ch),
+            }
+        }
+    }
+
+    /// Reads the hex digits of a `\u` escape, and a second escape for the
+    /// low half of a surrogate pair.
+    fn unicode_escape(&mut self) -> Option<char> {
+        let high = self.hex4()?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                if !self.eat_str("\\u") {
+                    return None;
+                }
+                let low = self.hex4().filter(|low| (0xDC00..=0xDFFF).contains(low))?;
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            0xDC00..=0xDFFF => return None,
+            _ => high,
+        };
+        char::from_u32(code)
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits = self.src.get(self.pos..self.pos + 4)?;
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.pos += 4;
+        u32::from_str_radix(digits, 16).ok()
+    }
+
+    fn logical_or(&mut self) -> Result<Logical, JsonPathError> {
+        let mut terms = vec![self.logical_and()?];
+        loop {
+            let before = self.pos;
+            self.skip_space();
+            if !self.eat_str("||") {
+                self.pos = before;
+                break;
+            }
+            self.skip_space();
+            terms.push(self.logical_and()?);
+        }
+        Ok(match terms.len() {
+            1 => terms.pop().expect("one term"),
+            _ => Logical::Or(terms),
+        })
+    }
+
+    fn logical_and(&mut self) -> Result<Logical, JsonPathError> {
+        let mut terms = vec![self.basic()?];
+        loop {
+            let before = self.pos;
+            self.skip_space();
+            if !self.eat_str("&&") {
+                self.pos = before;
+                break;
+            }
+            self.skip_space();
+            terms.push(self.basic()?);
+        }
+        Ok(match terms.len() {
+            1 => terms.pop().expect("one term"),
+            _ => Logical::And(terms),
+        })
+    }
+
+    fn basic(&mut self) -> Result<Logical, JsonPathError> {
+        if self.eat('!') {
+            self.skip_space();
+            let expr = match self.eat('(') {
+                true => self.parenthesized()?,
+                false => {
+                    let start = self.pos;
+                    let operand = self.comparable()?;
+                    test_expr(operand, start)?
+                }
+            };
+            return Ok(Logical::Not(Box::new(expr)));
+        }
+        if self.eat('(') {
+            return self.parenthesized();
+        }
+        let start = self.pos;
+        let left = self.comparable()?;
+        let before = self.pos;
+        self.skip_space();
+        let Some(op) = self.op() else {
+            self.pos = before;
+            return test_expr(left, start);
+        };
+        self.skip_space();
+        let right_start = self.pos;
+        let right = self.comparable()?;
+        check_value(&left, start)?;
+        check_value(&right, right_start)?;
+        Ok(Logical::Compare(left, op, right))
+    }
+
+    /// Reads the rest of a logical expression after its `(`.
+    fn parenthesized(&mut self) -> Result<Logical, JsonPathError> {
+        self.skip_space();
+        let expr = self.nested(Self::logical_or)?;
+        self.skip_space();
+        self.expect(')')?;
+        Ok(expr)
+    }
+
+    fn op(&mut self) -> Option<Op> {
+        let ops = [
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ];
+        ops.into_iter()
+            .find_map(|(text, op)| self.eat_str(text).then_some(op))
+    }
+
+    fn comparable(&mut self) -> Result<Comparable, JsonPathError> {
+        let start = self.pos;
+        match self.peek() {
+            Some(ch @ ('@' | '$')) => {
+                self.pos += 1;
+                Ok(Comparable::Query(Query {
+                    absolute: ch == '$',
+                    segments: self.segments()?,
+                }))
+            }
+            Some('\'' | '"') => Ok(Comparable::Literal(JsonValue::String(self.string()?))),
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => {
+                while self
+                    .peek()
+                    .is_some_and(|ch| ch.is_ascii_digit() || "+-.eE".contains(ch))
+                {
+                    self.pos += 1;
+                }
+                Parser::new(&self.src[start..self.pos])
+                    .and_then(|mut parser| parser.parse_root())
+                    .map(Comparable::Literal)
+                    .map_err(|_| self.error(JsonPathErrorKind::InvalidNumber, start))
+            }
+            Some(ch) if ch.is_ascii_lowercase() => {
+                while self
+                    .peek()
+                    .is_some_and(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
+                {
+                    self.pos += 1;
+                }
+                let name = &self.src[start..self.pos];
+                if self.peek() == Some('(') {
+                    return self.call(name, start).map(Comparable::Call);
+                }
+                match name {
+                    "true" => Ok(Comparable::Literal(JsonValue::Boolean(true))),
+                    "false" => Ok(Comparable::Literal(JsonValue::Boolean(false))),
+                    "null" => Ok(Comparable::Literal(JsonValue::Null)),
+                    _ => Err(self.unexpected()),
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Reads a function call from its `(`.
+    fn call(&mut self, name: &str, start: usize) -> Result<Call, JsonPathError> {
+        let function = match name {
+            "length" => Function::Length,
+            "count" => Function::Count,
+            "match" => Function::Match,
+            "search" => Function::Search,
+            "value" => Function::Value,
+            _ => {
+                let kind = JsonPathErrorKind::UnknownFunction(name.to_owned());
+                return Err(self.error(kind, start));
+            }
+        };
+        self.pos += 1;
+        let mut args = Vec::new();
+        self.skip_space();
+        if !self.eat(')') {
+            loop {
+                self.skip_space();
+                let at = self.pos;
+                args.push((self.nested(Self::argument)?, at));
+                self.skip_space();
+                if self.eat(')') {
+                    break;
+                }
+                self.expect(',')?;
+            }
+        }
+
+        let params = function.params();
+        if args.len() != params.len() {
+            return Err(self.error(JsonPathErrorKind::NotWellTyped, start));
+        }
+        for ((arg, at), param) in args.iter().zip(params) {
+            match param {
+                Param::Value => check_value(arg, *at)?,
+                Param::Nodes if matches!(arg, Comparable::Query(_)) => {}
+                Param::Nodes => return Err(self.error(JsonPathErrorKind::NotWellTyped, *at)),
+            }
+        }
+        let regex = match (function.is_logical(), &args[..]) {
+            (true, [_, (Comparable::Literal(JsonValue::String(pattern)), at)]) => {
+                match Regex::new(pattern) {
+                    Ok(regex) => Some(Some(regex)),
+                    Err(RegexError::Invalid) => Some(None),
+                    Err(RegexError::Unsupported) => {
+                        return Err(self.error(JsonPathErrorKind::UnsupportedPattern, *at))
+                    }
+                }
+            }
+            _ => None,
+        };
+        let args = args.into_iter().map(|(arg, _)| arg).collect();
+        Ok(Call {
+            function,
+            args,
+            regex,
+        })
+    }
+
+    fn argument(&mut self) -> Result<Comparable, JsonPathError> {
+        let start = self.pos;
+        // None of the functions takes a logical expression.
+        let not_well_typed = self.error(JsonPathErrorKind::NotWellTyped, start);
+        if matches!(self.peek(), Some('!' | '(')) {
+            return Err(not_well_typed);
+        }
+        let arg = self.comparable()?;
+        let before = self.pos;
+        self.skip_space();
+        if self.op().is_some() || self.eat_str("&&") || self.eat_str("||") {
+            return Err(not_well_typed);
+        }
+        self.pos = before;
+        Ok(arg)
+    }
+}
+
+/// Checks that a comparable stands for a single value or nothing.
+fn check_value(comparable: &Comparable, at: usize) -> Result<(), JsonPathError> {
+    let fits = match comparable {
+        Comparable::Literal(_) => true,
+        Comparable::Query(query) => query.is_singular(),
+        Comparable::Call(call) => !call.function.is_logical(),
+    };
+    match fits {
+        true => Ok(()),
+        false => Err(JsonPathError {
+            kind: JsonPathErrorKind::NotWellTyped,
+            offset: at,
+        }),
+    }
+}
+
+/// Turns a comparable with no comparison after it into a test: a query
+/// that must select something, or a call to `match` or `search`.
+fn test_expr(operand: Comparable, at: usize) -> Result<Logical, JsonPathError> {
+    match operand {
+        Comparable::Query(query) => Ok(Logical::Exists(query)),
+        Comparable::Call(call) if call.function.is_logical() => Ok(Logical::Call(call)),
+        _ => Err(JsonPathError {
+            kind: JsonPathErrorKind::NotWellTyped,
+            offset: at,
+        }),
+    }
+}
+
+impl Query {
+    /// Whether the query can select at most one node: it only has names
+    /// and indices, one per segment.
+    fn is_singular(&self) -> bool {
+        self.segments.iter().all(|segment| match segment {
+            Segment::Child(selectors) => {
+                matches!(selectors[..], [Selector::Name(_) | Selector::Index(_)])
+            }
+            Segment::Descendant(_) => false,
+        })
+    }
+}
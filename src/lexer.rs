@@ -1,7 +1,8 @@
 use crate::errors::{ErrorKind, JsonError};
+use crate::options::Dialect;
+use crate::position::{Position, Span};
 
 use super::token::*;
-use std::{iter::Peekable, str::Chars};
 
 type Result<T> = std::result::Result<T, JsonError>;
 /// The whitespace characters allowd in JSON according to the
@@ -14,103 +15,477 @@ type Result<T> = std::result::Result<T, JsonError>;
 /// %x0D )              ; Carriage return
 const WHITESPACES: [char; 4] = ['\u{0020}', '\u{0009}', '\u{000A}', '\u{000D}'];
 
-#[derive(Debug)]
-pub(crate) struct Lexer<'a> {
-    pub(crate) input_iter: Peekable<Chars<'a>>,
-    pub(crate) line: usize,
+/// Splits JSON text into tokens.
+///
+/// The lexer is an iterator over `Result<Token, JsonError>`. After an error
+/// it skips past the bad input and carries on, so a whole document can be
+/// tokenized even if it contains mistakes. Whitespace is skipped unless
+/// [`Lexer::with_trivia`] asks for it. [`Lexer::with_dialect`] selects the
+/// grammar; the default is strict RFC 8259.
+///
+/// ```
+/// use jimson::{lexer::Lexer, token::TokenType};
+///
+/// let tokens: Vec<_> = Lexer::new(r#"{"id": 7}"#)
+///     .unwrap()
+///     .map(|tok| tok.unwrap().token_type)
+///     .collect();
+/// assert_eq!(
+///     tokens,
+///     [
+///         TokenType::Lbrace,
+///         TokenType::Str,
+///         TokenType::Colon,
+///         TokenType::Number,
+///         TokenType::Rbrace,
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Lexer<'a> {
+    input: &'a str,
+    /// The position of the next character to be read.
+    position: Position,
+    /// Whether to produce `Whitespace` and `Comment` tokens.
+    trivia: bool,
+    dialect: Dialect,
 }
 
 impl<'a> Lexer<'a> {
     /// Initializes a new lexer with the given input.
-    pub(crate) fn new(input: &'a str) -> Result<Self> {
+    pub fn new(input: &'a str) -> Result<Self> {
         if input.is_empty() {
-            return Err(JsonError::compose(ErrorKind::EmptyInput, None));
+            return Err(JsonError::compose(
+                ErrorKind::EmptyInput,
+                Span::empty(Position::START),
+            ));
         }
         Ok(Self {
-            input_iter: input.chars().peekable(),
-            line: 1,
+            input,
+            position: Position::START,
+            trivia: false,
+            dialect: Dialect::Json,
         })
     }
 
+    /// Whether to produce `Whitespace` and `Comment` tokens instead of
+    /// skipping whitespace and comments.
+    pub fn with_trivia(mut self, trivia: bool) -> Self {
+        self.trivia = trivia;
+        self
+    }
+
+    /// Sets the grammar to tokenize.
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    fn json5(&self) -> bool {
+        self.dialect == Dialect::Json5
+    }
+
+    /// Consumes a run of whitespace.
+    fn read_whitespace(&mut self) {
+        let dialect = self.dialect;
+        while self.advance_if(|ch| is_whitespace(ch, dialect)).is_some() {}
+    }
+
+    /// The position of the next character to be read.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
     /// Advances the iterator on the input.
-    pub(crate) fn advance(&mut self) -> Option<char> {
-        self.input_iter.next()
+    fn advance(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.position = self.position.after(ch);
+        Some(ch)
     }
 
     /// Peeks at the next character of the input
-    pub(crate) fn peek(&mut self) -> Option<char> {
-        self.input_iter.peek().copied()
-    }
-
-    /// Produces the next token.
-    pub(crate) fn next_token(&mut self) -> Option<Token> {
-        self.skip_whitespace();
-        match self.input_iter.peek() {
-            Some('{') => {
-                self.advance();
-                Some(Token {
-                    token_type: TokenType::Lbrace,
-                })
+    fn peek(&self) -> Option<char> {
+        self.input[self.position.offset..].chars().next()
+    }
+
+    /// Consumes the next character if `pred` holds for it.
+    fn advance_if(&mut self, pred: impl Fn(char) -> bool) -> Option<char> {
+        self.peek().filter(|&ch| pred(ch))?;
+        self.advance()
+    }
+
+    /// Builds an error spanning from `start` to the current position.
+    fn error_from(&self, kind: ErrorKind, start: Position) -> JsonError {
+        JsonError::compose(kind, Span::new(start, self.position))
+    }
+
+    /// Builds a token spanning from `start` to the current position.
+    fn token(&self, token_type: TokenType, start: Position) -> Token<'a> {
+        Token {
+            token_type,
+            lexeme: &self.input[start.offset..self.position.offset],
+            span: Span::new(start, self.position),
+            value: None,
+        }
+    }
+
+    /// Produces the next token, or `None` at the end of the input.
+    pub fn next_token(&mut self) -> Option<Result<Token<'a>>> {
+        if !self.trivia {
+            if let Err(e) = self.skip_whitespace() {
+                return Some(Err(e));
             }
-            Some('}') => {
-                self.advance();
-                Some(Token {
-                    token_type: TokenType::Rbrace,
-                })
+        }
+        let start = self.position;
+        let ch = self.peek()?;
+        let json5 = self.json5();
+        let token_type = match ch {
+            '{' => TokenType::Lbrace,
+            '}' => TokenType::Rbrace,
+            '[' => TokenType::LSqBracket,
+            ']' => TokenType::RSqbracket,
+            ':' => TokenType::Colon,
+            ',' => TokenType::Comma,
+            '"' => return Some(self.read_string()),
+            '\'' if json5 => return Some(self.read_string()),
+            '-' | '0'..='9' => return Some(self.read_number()),
+            '+' | '.' if json5 => return Some(self.read_number()),
+            '/' if self.dialect.allows_comments() && self.at_comment() => {
+                return Some(
+                    self.read_comment()
+                        .map(|()| self.token(TokenType::Comment, start)),
+                );
             }
-            Some(':') => {
-                self.advance();
-                Some(Token {
-                    token_type: TokenType::Colon,
-                })
+            ch if is_whitespace(ch, self.dialect) => {
+                self.read_whitespace();
+                return Some(Ok(self.token(TokenType::Whitespace, start)));
             }
-            Some(',') => {
-                self.advance();
-                Some(Token {
-                    token_type: TokenType::Comma,
-                })
+            ch if is_identifier_start(ch) => {
+                while self.advance_if(is_identifier_part).is_some() {}
+                let token_type = match &self.input[start.offset..self.position.offset] {
+                    "true" => TokenType::True,
+                    "false" => TokenType::False,
+                    "null" => TokenType::Null,
+                    "Infinity" | "NaN" if json5 => TokenType::Number,
+                    _ => TokenType::Identifier,
+                };
+                return Some(Ok(self.token(token_type, start)));
             }
-            Some('"') => {
-                self.advance();
-                Some(Token {
-                    token_type: TokenType::Str,
-                })
+            ch => TokenType::InvalidChar(ch),
+        };
+        self.advance();
+        Some(Ok(self.token(token_type, start)))
+    }
+
+    /// Consumes whitespace, and comments if the dialect allows them, and
+    /// advances the iterator. Fails on a block comment that is not closed.
+    fn skip_whitespace(&mut self) -> Result<()> {
+        loop {
+            self.read_whitespace();
+            if !(self.dialect.allows_comments() && self.at_comment()) {
+                return Ok(());
             }
-            Some('[') => {
-                self.advance();
-                Some(Token {
-                    token_type: TokenType::LSqBracket,
-                })
+            self.read_comment()?;
+        }
+    }
+
+    /// Whether the input continues with `//` or `/*`.
+    fn at_comment(&self) -> bool {
+        let rest = &self.input[self.position.offset..];
+        rest.starts_with("//") || rest.starts_with("/*")
+    }
+
+    /// Reads a `//` comment up to the end of its line, or a `/* */` comment
+    /// up to and including the closing `*/`.
+    fn read_comment(&mut self) -> Result<()> {
+        let start = self.position;
+        self.advance();
+        if self.advance() == Some('/') {
+            while self.advance_if(|ch| !is_line_terminator(ch)).is_some() {}
+            return Ok(());
+        }
+        match self.input[self.position.offset..].find("*/") {
+            Some(len) => {
+                while self.position.offset < start.offset + 2 + len + 2 {
+                    self.advance();
+                }
+                Ok(())
             }
-            Some(']') => {
-                self.advance();
-                Some(Token {
-                    token_type: TokenType::RSqbracket,
-                })
+            None => {
+                while self.advance().is_some() {}
+                Err(self.error_from(ErrorKind::UnclosedComment, start))
             }
-            Some('\'') => Some(Token {
-                token_type: TokenType::InvalidChar('\''),
-            }),
-            None => None,
-            Some(ch) if !ch.is_ascii_digit() => Some(Token {
-                token_type: TokenType::Character(*ch),
-            }),
-            Some(ch) if ch.is_ascii_digit() => Some(Token {
-                token_type: TokenType::Digit,
-            }),
-            Some(ch) => Some(Token {
-                token_type: TokenType::InvalidChar(*ch),
+        }
+    }
+
+    /// Reads a string token, decoding escape sequences along the way.
+    ///
+    /// On error the rest of the string is skipped, so that lexing can go on
+    /// after it.
+    fn read_string(&mut self) -> Result<Token<'a>> {
+        let start = self.position;
+        let quote = self.advance().expect("opening quote");
+        match self.read_string_contents(start, quote) {
+            Ok(value) => Ok(Token {
+                value: Some(value),
+                ..self.token(TokenType::Str, start)
             }),
+            Err(e) => {
+                // A raw line break most likely means the closing quote is
+                // missing; don't swallow the next line looking for it.
+                if !(e.kind == ErrorKind::ControlCharacterInString && self.position.column == 1) {
+                    self.skip_rest_of_string(quote);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Reads the characters of a string up to and including the closing
+    /// `quote`. The opening quote, which starts at `start`, must already have
+    /// been consumed.
+    fn read_string_contents(&mut self, start: Position, quote: char) -> Result<String> {
+        let mut string = String::new();
+        loop {
+            let char_start = self.position;
+            let Some(ch) = self.advance() else {
+                return Err(self.error_from(ErrorKind::UnclosedDelimiter(TokenType::Str), start));
+            };
+            match ch {
+                ch if ch == quote => return Ok(string),
+                '\\' => string.extend(self.read_escape(char_start)?),
+                // JSON5 only requires line breaks to be escaped.
+                '\n' | '\r' if self.json5() => {
+                    return Err(self.error_from(ErrorKind::ControlCharacterInString, char_start))
+                }
+                // RFC 8259 requires control characters to be escaped.
+                '\u{0000}'..='\u{001F}' if !self.json5() => {
+                    return Err(self.error_from(ErrorKind::ControlCharacterInString, char_start))
+                }
+                _ => string.push(ch),
+            }
+        }
+    }
+
+    /// Skips to just past the closing `quote` of the current string. Stops
+    /// early at a line break, since strings cannot span lines.
+    fn skip_rest_of_string(&mut self, quote: char) {
+        while let Some(ch) = self.advance_if(|ch| ch != '\n') {
+            match ch {
+                ch if ch == quote => return,
+                '\\' => {
+                    self.advance_if(|ch| ch != '\n');
+                }
+                _ => {}
+            }
         }
     }
 
-    /// Consumes whitespace in the input and advances the iterator.
-    pub(crate) fn skip_whitespace(&mut self) {
-        while self.peek().filter(|ch| WHITESPACES.contains(ch)).is_some() {
-            let current = self.advance();
-            if current == Some('\n') {
-                self.line += 1;
+    /// Decodes a single escape sequence. The backslash, which starts at
+    /// `start`, must already have been consumed.
+    ///
+    /// A `\uXXXX` escape of a high surrogate has to be followed by another
+    /// `\uXXXX` escape of a low surrogate; the pair is joined into one
+    /// character.
+    ///
+    /// JSON5 adds `\'`, `\v`, `\0` and `\xXX`, lets any other character
+    /// that is not a digit escape itself, and turns a backslash before a
+    /// line break into a line continuation, which yields no character.
+    fn read_escape(&mut self, start: Position) -> Result<Option<char>> {
+        let escaped = match self.advance() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{0008}',
+            Some('f') => '\u{000C}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                let code = self.read_hex_quad(start)?;
+                let code = match code {
+                    0xD800..=0xDBFF => {
+                        if self.advance() != Some('\\') || self.advance() != Some('u') {
+                            return Err(self.error_from(ErrorKind::LoneSurrogate(code), start));
+                        }
+                        let low = self.read_hex_quad(start)?;
+                        if !(0xDC00..=0xDFFF).contains(&low) {
+                            return Err(self.error_from(ErrorKind::LoneSurrogate(code), start));
+                        }
+                        0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00)
+                    }
+                    0xDC00..=0xDFFF => {
+                        return Err(self.error_from(ErrorKind::LoneSurrogate(code), start))
+                    }
+                    _ => code,
+                };
+                // Surrogates have been ruled out above, so every remaining
+                // code point is a valid `char`.
+                char::from_u32(code).expect("non-surrogate code point")
             }
+            Some(ch) if self.json5() => match ch {
+                'v' => '\u{000B}',
+                '0' if self.peek().filter(char::is_ascii_digit).is_none() => '\0',
+                'x' => {
+                    let Some(code) = self.read_hex(2) else {
+                        return Err(self.error_from(ErrorKind::InvalidBackslashEscape, start));
+                    };
+                    char::from_u32(code).expect("code point below 0x100")
+                }
+                '\r' => {
+                    self.advance_if(|ch| ch == '\n');
+                    return Ok(None);
+                }
+                ch if is_line_terminator(ch) => return Ok(None),
+                ch if !ch.is_ascii_digit() => ch,
+                _ => return Err(self.error_from(ErrorKind::InvalidBackslashEscape, start)),
+            },
+            _ => return Err(self.error_from(ErrorKind::InvalidBackslashEscape, start)),
+        };
+        Ok(Some(escaped))
+    }
+
+    /// Reads the four hexadecimal digits of a `\uXXXX` escape that starts at
+    /// `start`.
+    fn read_hex_quad(&mut self, start: Position) -> Result<u32> {
+        self.read_hex(4)
+            .ok_or_else(|| self.error_from(ErrorKind::InvalidUnicodeEscape, start))
+    }
+
+    /// Reads exactly `count` hexadecimal digits.
+    fn read_hex(&mut self, count: usize) -> Option<u32> {
+        let mut code = 0;
+        for _ in 0..count {
+            code = code * 16 + self.advance().and_then(|ch| ch.to_digit(16))?;
+        }
+        Some(code)
+    }
+
+    /// Reads a number token according to the RFC 8259 grammar:
+    ///
+    /// ```text
+    /// number = [ minus ] int [ frac ] [ exp ]
+    /// int    = zero / ( digit1-9 *DIGIT )
+    /// frac   = decimal-point 1*DIGIT
+    /// exp    = e [ minus / plus ] 1*DIGIT
+    /// ```
+    ///
+    /// JSON5 also allows a `+` sign, hexadecimal integers such as `0x1F`, a
+    /// leading or trailing decimal point, and `Infinity` and `NaN` after a
+    /// sign.
+    ///
+    /// On error the rest of the malformed number is skipped.
+    fn read_number(&mut self) -> Result<Token<'a>> {
+        let start = self.position;
+        let result = self.read_number_parts(start);
+        if result.is_err() {
+            while self
+                .advance_if(|ch| ch.is_ascii_alphanumeric() || ch == '.')
+                .is_some()
+            {}
+        }
+        result.map(|()| self.token(TokenType::Number, start))
+    }
+
+    /// Does the work of `read_number`.
+    fn read_number_parts(&mut self, start: Position) -> Result<()> {
+        let json5 = self.json5();
+        self.advance_if(|ch| ch == '-' || (json5 && ch == '+'));
+        if json5 && self.peek().is_some_and(is_identifier_start) {
+            while self.advance_if(is_identifier_part).is_some() {}
+            // Only reachable after a sign, so the word starts one byte in.
+            return match &self.input[start.offset + 1..self.position.offset] {
+                "Infinity" | "NaN" => Ok(()),
+                _ => Err(self.error_from(ErrorKind::InvalidNumber, start)),
+            };
+        }
+        let mut int_digits = true;
+        match self.advance_if(|ch| ch.is_ascii_digit()) {
+            Some('0') if json5 && self.advance_if(|ch| matches!(ch, 'x' | 'X')).is_some() => {
+                if self.peek().filter(char::is_ascii_hexdigit).is_none() {
+                    return Err(self.error_from(ErrorKind::InvalidNumber, start));
+                }
+                while self.advance_if(|ch| ch.is_ascii_hexdigit()).is_some() {}
+                return self.check_number_end(start);
+            }
+            Some('0') => {
+                if self.peek().filter(char::is_ascii_digit).is_some() {
+                    return Err(self.error_from(ErrorKind::NumberWithLeadingZero, start));
+                }
+            }
+            Some(_) => self.read_digits(),
+            None if json5 && self.peek() == Some('.') => int_digits = false,
+            None => return Err(self.error_from(ErrorKind::InvalidNumber, start)),
+        }
+        if self.advance_if(|ch| ch == '.').is_some() {
+            // JSON5 needs digits on at least one side of the point.
+            if json5 && int_digits {
+                self.read_digits();
+            } else {
+                self.expect_digits(start)?;
+            }
+        }
+        if self.advance_if(|ch| matches!(ch, 'e' | 'E')).is_some() {
+            self.advance_if(|ch| matches!(ch, '+' | '-'));
+            self.expect_digits(start)?;
         }
+        self.check_number_end(start)
     }
+
+    /// Fails if the number that starts at `start` runs straight into more
+    /// letters, digits or points.
+    fn check_number_end(&self, start: Position) -> Result<()> {
+        // Catch things like `0x14` or `1.2.3` here rather than letting them
+        // surface later as a confusing syntax error.
+        if self
+            .peek()
+            .filter(|ch| ch.is_ascii_alphanumeric() || *ch == '.')
+            .is_some()
+        {
+            return Err(self.error_from(ErrorKind::InvalidNumber, start));
+        }
+        Ok(())
+    }
+
+    /// Reads a run of ASCII digits.
+    fn read_digits(&mut self) {
+        while self.advance_if(|ch| ch.is_ascii_digit()).is_some() {}
+    }
+
+    /// Like `read_digits`, but at least one digit is required.
+    fn expect_digits(&mut self, start: Position) -> Result<()> {
+        if self.peek().filter(char::is_ascii_digit).is_none() {
+            return Err(self.error_from(ErrorKind::InvalidNumber, start));
+        }
+        self.read_digits();
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token()
+    }
+}
+
+/// Whether `ch` is whitespace in `dialect`. JSON5 adds the ECMAScript
+/// whitespace and line terminators.
+pub(crate) fn is_whitespace(ch: char, dialect: Dialect) -> bool {
+    WHITESPACES.contains(&ch)
+        || (dialect == Dialect::Json5
+            && (ch == '\u{FEFF}' || (ch.is_whitespace() && ch != '\u{0085}')))
+}
+
+pub(crate) fn is_line_terminator(ch: char) -> bool {
+    matches!(ch, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+fn is_identifier_start(ch: char) -> bool {
+    ch.is_alphabetic() || ch == '_' || ch == '$'
+}
+
+fn is_identifier_part(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_' || ch == '$'
 }
@@ -1,4 +1,24 @@
+pub mod comment;
+pub mod cst;
+pub mod diagnostic;
 pub mod errors;
+pub mod event;
+pub mod jq;
+pub mod jsonpath;
 pub mod lexer;
+pub mod lines;
+pub mod map;
+pub mod merge;
+pub mod number;
+pub mod options;
 pub mod parser;
+pub mod patch;
+pub mod pointer;
+pub mod position;
+pub mod pretty;
+pub mod push;
+mod regex;
+pub mod sequence;
+pub mod serializer;
+mod stream;
 pub mod token;
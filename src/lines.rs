@@ -0,0 +1,205 @@
+use crate::errors::{ErrorKind, IoError, JsonError, SerializeError};
+use crate::options::ParserOptions;
+use crate::parser::{JsonValue, Parser};
+use crate::position::{Position, Span};
+use crate::serializer::to_string;
+
+use std::io::{BufRead, Read, Write};
+
+/// Reads [JSON Lines](https://jsonlines.org/), also known as NDJSON: one
+/// value per line, as in a `.jsonl` log.
+///
+/// Lines end with `\n` or `\r\n`, and each is parsed on its own with the
+/// reader's [`ParserOptions`]. Positions in errors count from the start of
+/// the whole input, so `span.start.line` is the line of the bad record.
+/// `max_input_size` applies to each line, and a longer one is skipped
+/// without being read into memory.
+///
+/// By default a blank line is an error, and the first error ends the
+/// iteration. I/O errors always end it.
+///
+/// ```
+/// use jimson::lines::JsonLinesReader;
+///
+/// let input = "{\"id\": 1}\n\n{\"id\": 2\n[3]\n";
+/// let mut reader = JsonLinesReader::new(input.as_bytes())
+///     .skip_blank_lines(true)
+///     .continue_on_error(true);
+///
+/// assert!(reader.next().unwrap().is_ok());
+/// let e = reader.next().unwrap().unwrap_err();
+/// assert_eq!(e.span.start.line, 3);
+/// assert!(reader.next().unwrap().is_ok());
+/// assert!(reader.next().is_none());
+/// ```
+#[derive(Debug)]
+pub struct JsonLinesReader<R> {
+    reader: R,
+    options: ParserOptions,
+    skip_blank_lines: bool,
+    continue_on_error: bool,
+    /// Where the next line starts.
+    position: Position,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl<R: BufRead> JsonLinesReader<R> {
+    /// Creates a reader that parses each line as strict JSON with the
+    /// default limits.
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParserOptions::default())
+    }
+
+    /// Create a reader that parses each line with the given limits and
+    /// strictness settings.
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        JsonLinesReader {
+            reader,
+            options,
+            skip_blank_lines: false,
+            continue_on_error: false,
+            position: Position::START,
+            buf: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Skip lines that are empty or hold only whitespace, rather than
+    /// reporting them as errors.
+    pub fn skip_blank_lines(mut self, enabled: bool) -> Self {
+        self.skip_blank_lines = enabled;
+        self
+    }
+
+    /// Carry on with the next line after a bad record, rather than ending
+    /// the iteration with its error.
+    pub fn continue_on_error(mut self, enabled: bool) -> Self {
+        self.continue_on_error = enabled;
+        self
+    }
+
+    /// The underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Ends the iteration with an I/O error met reading the line at `start`.
+    fn io_error(&mut self, e: std::io::Error, start: Position) -> JsonError {
+        self.done = true;
+        JsonError::compose(ErrorKind::Io(e.into()), Span::empty(start))
+    }
+}
+
+impl<R: BufRead> Iterator for JsonLinesReader<R> {
+    type Item = Result<JsonValue, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.buf.clear();
+            let start = self.position;
+            // A line and its `\r\n` that fit the limit are read whole, and
+            // anything longer is cut short.
+            let max = self.options.max_input_size;
+            let read = match max {
+                Some(max) => (&mut self.reader)
+                    .take(max as u64 + 2)
+                    .read_until(b'\n', &mut self.buf),
+                None => self.reader.read_until(b'\n', &mut self.buf),
+            };
+            let mut n = match read {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(self.io_error(e, start))),
+            };
+            let too_large = max.is_some_and(|max| n > max + 1 && !self.buf.ends_with(b"\n"));
+            if too_large {
+                n += match self.reader.skip_until(b'\n') {
+                    Ok(skipped) => skipped,
+                    Err(e) => return Some(Err(self.io_error(e, start))),
+                };
+            }
+            self.position = Position {
+                line: start.line + 1,
+                column: 1,
+                offset: start.offset + n,
+            };
+            if too_large {
+                if !self.continue_on_error {
+                    self.done = true;
+                }
+                let kind = ErrorKind::InputTooLarge;
+                return Some(Err(JsonError::compose(kind, Span::empty(start))));
+            }
+
+            let line = self.buf.strip_suffix(b"\n").unwrap_or(&self.buf);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            let result = match std::str::from_utf8(line) {
+                Ok(text)
+                    if self.skip_blank_lines && text.trim_matches([' ', '\t', '\r']).is_empty() =>
+                {
+                    continue
+                }
+                Ok(text) => Parser::with_options(text, self.options.clone())
+                    .and_then(|mut parser| parser.parse_root())
+                    .map_err(|e| e.rebase(start)),
+                Err(e) => {
+                    // Checked by `from_utf8` above.
+                    let valid = std::str::from_utf8(&line[..e.valid_up_to()]).expect("valid UTF-8");
+                    let at = valid.chars().fold(Position::START, Position::after);
+                    let kind = ErrorKind::Io(IoError::invalid_utf8());
+                    Err(JsonError::compose(kind, Span::empty(at.rebase(start))))
+                }
+            };
+            if result.is_err() && !self.continue_on_error {
+                self.done = true;
+            }
+            return Some(result);
+        }
+        self.done = true;
+        None
+    }
+}
+
+/// Writes JSON Lines: each value as compact JSON, on a line of its own.
+///
+/// ```
+/// use jimson::lines::JsonLinesWriter;
+/// use jimson::parser::JsonValue;
+///
+/// let mut writer = JsonLinesWriter::new(Vec::new());
+/// writer.write(&JsonValue::String("a\nb".into())).unwrap();
+/// writer.write(&JsonValue::Array(vec![JsonValue::Null])).unwrap();
+/// assert_eq!(writer.into_inner(), b"\"a\\nb\"\n[null]\n");
+/// ```
+#[derive(Debug)]
+pub struct JsonLinesWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    /// Creates a writer that writes to `writer`.
+    pub fn new(writer: W) -> Self {
+        JsonLinesWriter { writer }
+    }
+
+    /// Writes `value` and the newline after it. A value that cannot be
+    /// serialized leaves nothing behind.
+    pub fn write(&mut self, value: &JsonValue) -> Result<(), SerializeError> {
+        let mut line = to_string(value)?;
+        line.push('\n');
+        self.writer
+            .write_all(line.as_bytes())
+            .map_err(SerializeError::Io)
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// The underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
@@ -0,0 +1,433 @@
+use std::collections::HashMap;
+
+/// A map from string keys to values that remembers insertion order.
+///
+/// Lookup by key goes through a hash index, so it is O(1) on average, and
+/// iteration visits the entries in the order they were inserted. Entries can
+/// also be accessed by their position.
+///
+/// Two maps are equal if they contain the same key-value pairs, regardless
+/// of order.
+#[derive(Clone)]
+pub struct Map<V> {
+    entries: Vec<(String, V)>,
+    indices: HashMap<String, usize>,
+}
+
+impl<V> Map<V> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Map {
+            entries: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    /// Creates an empty map with room for at least `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Map {
+            entries: Vec::with_capacity(capacity),
+            indices: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.indices.clear();
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    /// Returns a reference to the value stored for `key`.
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.get_index_of(key).map(|i| &self.entries[i].1)
+    }
+
+    /// Returns a mutable reference to the value stored for `key`.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.get_index_of(key).map(|i| &mut self.entries[i].1)
+    }
+
+    /// Returns the stored key and its value.
+    pub fn get_key_value(&self, key: &str) -> Option<(&String, &V)> {
+        self.get_index_of(key).and_then(|i| self.get_index(i))
+    }
+
+    /// Returns the position of `key` in the map.
+    pub fn get_index_of(&self, key: &str) -> Option<usize> {
+        self.indices.get(key).copied()
+    }
+
+    /// Returns the key-value pair at position `index`.
+    pub fn get_index(&self, index: usize) -> Option<(&String, &V)> {
+        self.entries.get(index).map(|(k, v)| (k, v))
+    }
+
+    /// Returns the key and a mutable reference to the value at position
+    /// `index`.
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&String, &mut V)> {
+        self.entries.get_mut(index).map(|(k, v)| (&*k, v))
+    }
+
+    /// Inserts a key-value pair.
+    ///
+    /// If the key was already present its value is replaced in place, keeping
+    /// its original position, and the old value is returned. Otherwise the
+    /// pair is appended at the end.
+    pub fn insert(&mut self, key: String, value: V) -> Option<V> {
+        match self.get_index_of(&key) {
+            Some(i) => Some(std::mem::replace(&mut self.entries[i].1, value)),
+            None => {
+                self.push(key, value);
+                None
+            }
+        }
+    }
+
+    /// Removes `key` and returns its value, preserving the order of the
+    /// remaining entries. Same as [`Map::shift_remove`].
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        self.shift_remove(key)
+    }
+
+    /// Removes `key` by shifting every later entry down by one, which keeps
+    /// the order intact. This is O(n).
+    pub fn shift_remove(&mut self, key: &str) -> Option<V> {
+        let index = self.indices.remove(key)?;
+        let (_, value) = self.entries.remove(index);
+        for (k, _) in &self.entries[index..] {
+            *self.indices.get_mut(k).expect("key is indexed") -= 1;
+        }
+        Some(value)
+    }
+
+    /// Removes `key` by moving the last entry into its place. This is O(1)
+    /// but disturbs the order.
+    pub fn swap_remove(&mut self, key: &str) -> Option<V> {
+        let index = self.indices.remove(key)?;
+        let (_, value) = self.entries.swap_remove(index);
+        if let Some((moved, _)) = self.entries.get(index) {
+            *self.indices.get_mut(moved).expect("key is indexed") = index;
+        }
+        Some(value)
+    }
+
+    /// Returns the entry for `key`, for in-place manipulation.
+    pub fn entry(&mut self, key: String) -> Entry<'_, V> {
+        match self.get_index_of(&key) {
+            Some(index) => Entry::Occupied(OccupiedEntry { map: self, index }),
+            None => Entry::Vacant(VacantEntry { map: self, key }),
+        }
+    }
+
+    /// Iterates over the entries in order.
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter {
+            inner: self.entries.iter(),
+        }
+    }
+
+    /// Iterates over the entries in order, with mutable values.
+    pub fn iter_mut(&mut self) -> IterMut<'_, V> {
+        IterMut {
+            inner: self.entries.iter_mut(),
+        }
+    }
+
+    /// Iterates over the keys in order.
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &String> + ExactSizeIterator {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    /// Iterates over the values in order.
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    /// Iterates over mutable references to the values in order.
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+
+    /// Appends a pair whose key is known not to be present.
+    fn push(&mut self, key: String, value: V) -> usize {
+        let index = self.entries.len();
+        self.indices.insert(key.clone(), index);
+        self.entries.push((key, value));
+        index
+    }
+}
+
+impl<V> Default for Map<V> {
+    fn default() -> Self {
+        Map::new()
+    }
+}
+
+impl<V: std::fmt::Debug> std::fmt::Debug for Map<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<V: PartialEq> PartialEq for Map<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<V> std::ops::Index<usize> for Map<V> {
+    type Output = V;
+
+    /// Returns the value at position `index`. Panics if it is out of bounds.
+    fn index(&self, index: usize) -> &V {
+        &self.entries[index].1
+    }
+}
+
+impl<V> std::ops::IndexMut<usize> for Map<V> {
+    fn index_mut(&mut self, index: usize) -> &mut V {
+        &mut self.entries[index].1
+    }
+}
+
+impl<V> std::ops::Index<&str> for Map<V> {
+    type Output = V;
+
+    /// Returns the value for `key`. Panics if the key is not present.
+    fn index(&self, key: &str) -> &V {
+        self.get(key).expect("key not found in map")
+    }
+}
+
+impl<V> std::ops::IndexMut<&str> for Map<V> {
+    fn index_mut(&mut self, key: &str) -> &mut V {
+        self.get_mut(key).expect("key not found in map")
+    }
+}
+
+impl<V> FromIterator<(String, V)> for Map<V> {
+    fn from_iter<I: IntoIterator<Item = (String, V)>>(iter: I) -> Self {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<V> Extend<(String, V)> for Map<V> {
+    fn extend<I: IntoIterator<Item = (String, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<'a, V> IntoIterator for &'a Map<V> {
+    type Item = (&'a String, &'a V);
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a mut Map<V> {
+    type Item = (&'a String, &'a mut V);
+    type IntoIter = IterMut<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<V> IntoIterator for Map<V> {
+    type Item = (String, V);
+    type IntoIter = std::vec::IntoIter<(String, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+/// An iterator over the entries of a [`Map`], in order.
+pub struct Iter<'a, V> {
+    inner: std::slice::Iter<'a, (String, V)>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (&'a String, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<V> DoubleEndedIterator for Iter<'_, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<V> ExactSizeIterator for Iter<'_, V> {}
+
+/// A mutable iterator over the entries of a [`Map`], in order.
+pub struct IterMut<'a, V> {
+    inner: std::slice::IterMut<'a, (String, V)>,
+}
+
+impl<'a, V> Iterator for IterMut<'a, V> {
+    type Item = (&'a String, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (&*k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<V> DoubleEndedIterator for IterMut<'_, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, v)| (&*k, v))
+    }
+}
+
+impl<V> ExactSizeIterator for IterMut<'_, V> {}
+
+/// A view into a single entry of a [`Map`], which may be vacant or
+/// occupied.
+pub enum Entry<'a, V> {
+    Occupied(OccupiedEntry<'a, V>),
+    Vacant(VacantEntry<'a, V>),
+}
+
+impl<'a, V> Entry<'a, V> {
+    /// Returns the key of this entry.
+    pub fn key(&self) -> &str {
+        match self {
+            Entry::Occupied(e) => e.key(),
+            Entry::Vacant(e) => e.key(),
+        }
+    }
+
+    /// Inserts `default` if the entry is vacant, and returns the value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default),
+        }
+    }
+
+    /// Inserts the result of `default` if the entry is vacant, and returns
+    /// the value.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default()),
+        }
+    }
+
+    /// Inserts `V::default()` if the entry is vacant, and returns the value.
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Calls `f` on the value if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(e) = &mut self {
+            f(e.get_mut());
+        }
+        self
+    }
+}
+
+/// An occupied entry of a [`Map`].
+pub struct OccupiedEntry<'a, V> {
+    map: &'a mut Map<V>,
+    index: usize,
+}
+
+impl<'a, V> OccupiedEntry<'a, V> {
+    /// Returns the key of this entry.
+    pub fn key(&self) -> &str {
+        &self.map.entries[self.index].0
+    }
+
+    /// Returns the position of this entry in the map.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns a reference to the value.
+    pub fn get(&self) -> &V {
+        &self.map.entries[self.index].1
+    }
+
+    /// Returns a mutable reference to the value.
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.entries[self.index].1
+    }
+
+    /// Converts the entry into a mutable reference bound to the map.
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.entries[self.index].1
+    }
+
+    /// Replaces the value and returns the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry, preserving the order of the rest of the map.
+    pub fn shift_remove(self) -> V {
+        let key = self.map.entries[self.index].0.clone();
+        self.map.shift_remove(&key).expect("entry is occupied")
+    }
+
+    /// Removes the entry by swapping in the last entry of the map.
+    pub fn swap_remove(self) -> V {
+        let key = self.map.entries[self.index].0.clone();
+        self.map.swap_remove(&key).expect("entry is occupied")
+    }
+}
+
+/// A vacant entry of a [`Map`].
+pub struct VacantEntry<'a, V> {
+    map: &'a mut Map<V>,
+    key: String,
+}
+
+impl<'a, V> VacantEntry<'a, V> {
+    /// Returns the key that would be used on insertion.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Appends the value to the map under this entry's key.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let index = self.map.push(self.key, value);
+        &mut self.map.entries[index].1
+    }
+}
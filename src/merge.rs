@@ -0,0 +1,84 @@
+use crate::map::Map;
+use crate::parser::JsonValue;
+
+impl JsonValue {
+    /// Applies a JSON Merge Patch (RFC 7396).
+    ///
+    /// An object patch changes the members it names: `null` removes one,
+    /// and any other value is merged into the member in the same way.
+    /// Members it does not name are left alone, and a value that is not an
+    /// object becomes one first. Any other patch replaces the value whole.
+    ///
+    /// ```
+    /// use jimson::parser::Parser;
+    ///
+    /// let parse = |s: &str| Parser::new(s).unwrap().parse_root().unwrap();
+    /// let mut user = parse(r#"{"name": "ann", "email": "a@x", "prefs": {"theme": "dark"}}"#);
+    /// user.merge_patch(&parse(r#"{"email": null, "prefs": {"lang": "en"}}"#));
+    /// assert_eq!(user, parse(r#"{"name": "ann", "prefs": {"theme": "dark", "lang": "en"}}"#));
+    /// ```
+    pub fn merge_patch(&mut self, patch: &JsonValue) {
+        let JsonValue::Object(patch) = patch else {
+            *self = patch.clone();
+            return;
+        };
+        if !matches!(self, JsonValue::Object(_)) {
+            *self = JsonValue::Object(Map::new());
+        }
+        let JsonValue::Object(map) = self else {
+            unreachable!("made an object above");
+        };
+        for (key, value) in patch {
+            match value {
+                JsonValue::Null => {
+                    map.shift_remove(key);
+                }
+                value => map
+                    .entry(key.clone())
+                    .or_insert(JsonValue::Null)
+                    .merge_patch(value),
+            }
+        }
+    }
+}
+
+/// Computes a merge patch that turns `from` into `to` when applied with
+/// [`JsonValue::merge_patch`].
+///
+/// Members are named only where they differ: removed ones as `null`, and
+/// objects on both sides by what changed inside them. Merge patches
+/// cannot set a member to `null`, so those in `to` are left out of the
+/// patch, and arrays are always replaced whole.
+///
+/// ```
+/// use jimson::merge;
+/// use jimson::parser::Parser;
+///
+/// let parse = |s: &str| Parser::new(s).unwrap().parse_root().unwrap();
+/// let from = parse(r#"{"a": 1, "b": {"c": 2, "d": 3}}"#);
+/// let to = parse(r#"{"a": 1, "b": {"c": 4}}"#);
+/// let patch = merge::diff(&from, &to);
+/// assert_eq!(patch, parse(r#"{"b": {"c": 4, "d": null}}"#));
+/// ```
+pub fn diff(from: &JsonValue, to: &JsonValue) -> JsonValue {
+    let (JsonValue::Object(from), JsonValue::Object(to)) = (from, to) else {
+        return to.clone();
+    };
+    let mut patch = Map::new();
+    for key in from.keys().filter(|key| !to.contains_key(key)) {
+        patch.insert(key.clone(), JsonValue::Null);
+    }
+    for (key, value) in to {
+        match from.get(key) {
+            Some(old) if old == value => {}
+            _ if *value == JsonValue::Null => {}
+            Some(old) => {
+                patch.insert(key.clone(), diff(old, value));
+            }
+            None => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    JsonValue::Object(patch)
+}
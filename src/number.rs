@@ -0,0 +1,120 @@
+/// A JSON number.
+///
+/// Integers that fit in an `i64` or `u64` are stored exactly; everything
+/// else (numbers with a fraction or an exponent, and integers too large
+/// for 64 bits) is stored as an `f64`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Number {
+    n: N,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum N {
+    /// Always zero or greater.
+    PosInt(u64),
+    /// Always less than zero.
+    NegInt(i64),
+    Float(f64),
+}
+
+impl Number {
+    /// Creates a number from a float. Returns `None` if the float is
+    /// infinite or NaN, since JSON cannot represent those.
+    pub fn from_f64(f: f64) -> Option<Self> {
+        f.is_finite().then_some(Number { n: N::Float(f) })
+    }
+
+    /// Returns `true` if the number is an integer that fits in an `i64`.
+    pub fn is_i64(&self) -> bool {
+        match self.n {
+            N::PosInt(u) => u <= i64::MAX as u64,
+            N::NegInt(_) => true,
+            N::Float(_) => false,
+        }
+    }
+
+    /// Returns `true` if the number is a non-negative integer that fits in
+    /// a `u64`.
+    pub fn is_u64(&self) -> bool {
+        matches!(self.n, N::PosInt(_))
+    }
+
+    /// Returns `true` if the number is stored as a float.
+    pub fn is_f64(&self) -> bool {
+        matches!(self.n, N::Float(_))
+    }
+
+    /// Returns the number as an `i64`, or `None` if it is a float or does
+    /// not fit.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.n {
+            N::PosInt(u) => i64::try_from(u).ok(),
+            N::NegInt(i) => Some(i),
+            N::Float(_) => None,
+        }
+    }
+
+    /// Returns the number as a `u64`, or `None` if it is a float or
+    /// negative.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.n {
+            N::PosInt(u) => Some(u),
+            N::NegInt(_) | N::Float(_) => None,
+        }
+    }
+
+    /// Returns the number as an `f64`, or `None` if it is an integer that
+    /// cannot be represented exactly as one.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.n {
+            N::PosInt(u) => {
+                let f = u as f64;
+                // `u64::MAX as f64` rounds up to 2^64, which is out of range
+                // for the cast back.
+                (f < 18_446_744_073_709_551_616.0 && f as u64 == u).then_some(f)
+            }
+            N::NegInt(i) => {
+                let f = i as f64;
+                (f as i64 == i).then_some(f)
+            }
+            N::Float(f) => Some(f),
+        }
+    }
+}
+
+impl From<u64> for Number {
+    fn from(u: u64) -> Self {
+        Number { n: N::PosInt(u) }
+    }
+}
+
+impl From<f64> for Number {
+    /// Stores any float, including infinities and NaN. Such values can be
+    /// held in a `JsonValue` but are refused by the serializer; use
+    /// [`Number::from_f64`] to reject them up front.
+    fn from(f: f64) -> Self {
+        Number { n: N::Float(f) }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(i: i64) -> Self {
+        if i < 0 {
+            Number { n: N::NegInt(i) }
+        } else {
+            Number {
+                n: N::PosInt(i as u64),
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.n {
+            N::PosInt(u) => write!(f, "{}", u),
+            N::NegInt(i) => write!(f, "{}", i),
+            N::Float(x) => write!(f, "{:?}", x),
+        }
+    }
+}
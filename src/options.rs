@@ -0,0 +1,174 @@
+/// The default limit on how deeply arrays and objects may nest.
+pub const DEFAULT_MAX_DEPTH: usize = 15;
+
+/// The grammar accepted by [`Parser`](crate::parser::Parser) and
+/// [`Lexer`](crate::lexer::Lexer).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /// Strict RFC 8259 JSON.
+    #[default]
+    Json,
+    /// [JSON5](https://spec.json5.org/): unquoted keys, single-quoted and
+    /// multi-line strings, trailing commas, comments, hexadecimal numbers,
+    /// leading and trailing decimal points, `+` signs, and `Infinity` and
+    /// `NaN`.
+    Json5,
+    /// JSON with comments, as used by VS Code's `settings.json`: RFC 8259
+    /// plus `//` and `/* */` comments and trailing commas.
+    Jsonc,
+}
+
+impl Dialect {
+    /// Whether `//` and `/* */` comments are allowed.
+    pub fn allows_comments(self) -> bool {
+        self != Dialect::Json
+    }
+
+    /// Whether a comma may follow the last element of an array or object.
+    pub fn allows_trailing_commas(self) -> bool {
+        self != Dialect::Json
+    }
+}
+
+/// What the parser does when an object has the same key more than once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Raise `ErrorKind::DuplicateKey`, pointing at both occurrences.
+    Error,
+    /// Keep the first value and ignore later ones.
+    FirstWins,
+    /// Keep the last value, at the position where the key first appeared.
+    #[default]
+    LastWins,
+    /// Keep every value: each key gets an array of all its values in
+    /// document order, even a key that appears once, so that `{"a": [1]}`
+    /// still reads differently from `{"a": 1, "a": 1}`.
+    CollectAll,
+}
+
+/// Limits and strictness settings for [`Parser`](crate::parser::Parser).
+///
+/// Every limit fails the parse with its own `ErrorKind` when exceeded. The
+/// defaults allow nesting up to 15 levels, leave every other limit off,
+/// accept any value as the root but reject anything after it, let the last
+/// of several duplicate keys win, and accept strict RFC 8259 JSON only.
+///
+/// ```
+/// use jimson::{options::ParserOptions, parser::Parser};
+///
+/// let options = ParserOptions::new()
+///     .max_depth(64)
+///     .max_string_length(Some(1 << 20))
+///     .max_input_size(Some(16 << 20));
+/// let mut parser = Parser::with_options(r#"{"ok": true}"#, options).unwrap();
+/// assert!(parser.parse_root().is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserOptions {
+    pub(crate) max_depth: usize,
+    pub(crate) max_string_length: Option<usize>,
+    pub(crate) max_object_members: Option<usize>,
+    pub(crate) max_array_elements: Option<usize>,
+    pub(crate) max_input_size: Option<usize>,
+    pub(crate) allow_trailing_content: bool,
+    pub(crate) duplicate_keys: DuplicateKeys,
+    pub(crate) dialect: Dialect,
+    pub(crate) attach_comments: bool,
+    pub(crate) require_object_or_array: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_string_length: None,
+            max_object_members: None,
+            max_array_elements: None,
+            max_input_size: None,
+            allow_trailing_content: false,
+            duplicate_keys: DuplicateKeys::default(),
+            dialect: Dialect::default(),
+            attach_comments: false,
+            require_object_or_array: false,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// Creates options with the defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many arrays and objects may be nested inside each other.
+    /// Exceeding it raises `ErrorKind::NestingTooDeep`.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Limits the length in bytes of a decoded string, keys included.
+    /// Exceeding it raises `ErrorKind::StringTooLong`.
+    pub fn max_string_length(mut self, length: Option<usize>) -> Self {
+        self.max_string_length = length;
+        self
+    }
+
+    /// Limits the number of members of any one object, counting each
+    /// repeat of a key. Exceeding it raises `ErrorKind::TooManyObjectMembers`.
+    pub fn max_object_members(mut self, members: Option<usize>) -> Self {
+        self.max_object_members = members;
+        self
+    }
+
+    /// Limits the number of elements of any one array. Exceeding it raises
+    /// `ErrorKind::TooManyArrayElements`.
+    pub fn max_array_elements(mut self, elements: Option<usize>) -> Self {
+        self.max_array_elements = elements;
+        self
+    }
+
+    /// Limits the size of the whole input in bytes. Exceeding it raises
+    /// `ErrorKind::InputTooLarge` before any parsing is done.
+    pub fn max_input_size(mut self, size: Option<usize>) -> Self {
+        self.max_input_size = size;
+        self
+    }
+
+    /// Whether to stop after the root value and ignore whatever follows it,
+    /// instead of raising `ErrorKind::InvalidSyntax`.
+    pub fn allow_trailing_content(mut self, allow: bool) -> Self {
+        self.allow_trailing_content = allow;
+        self
+    }
+
+    /// Sets what to do when an object has the same key more than once.
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Sets the grammar to parse.
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Whether to attach the comments in the input to the nearest value,
+    /// for [`Parser::comments`](crate::parser::Parser::comments). Off by
+    /// default; comments are then skipped. Only has an effect in a dialect
+    /// that allows comments.
+    pub fn attach_comments(mut self, attach: bool) -> Self {
+        self.attach_comments = attach;
+        self
+    }
+
+    /// Whether the root value must be an object or an array, as the
+    /// obsolete RFC 4627 required. A root of any other type then raises
+    /// `ErrorKind::NotObjectOrArray`. Off by default, since RFC 8259 allows
+    /// any value as the root.
+    pub fn require_object_or_array(mut self, require: bool) -> Self {
+        self.require_object_or_array = require;
+        self
+    }
+}
@@ -1,16 +1,67 @@
+use super::comment::{Attacher, Comment, PathSegment};
 use super::errors::*;
+use super::event::{Event, EventKind};
 use super::lexer::*;
+use super::map::Map;
+use super::number::Number;
+use super::options::{Dialect, DuplicateKeys, ParserOptions};
+use super::position::{Position, Span};
+use super::push::Pushed;
+use super::stream::{PushLexer, ReaderLexer};
 use super::token::*;
 
 use std::collections::HashMap;
-
-const MAX_DEPTH: u8 = 15;
+use std::io::Read;
 
 /// A basic parser for JSON.
 #[derive(Debug)]
 pub struct Parser<'l> {
-    lexer: Lexer<'l>,
-    depth: u8,
+    lexer: Source<'l>,
+    /// A token that has been looked at but not consumed yet.
+    peeked: Option<Option<Result<CowToken<'l>, JsonError>>>,
+    options: ParserOptions,
+    /// The arrays and objects that are open, innermost last.
+    stack: Vec<Frame>,
+    /// Whether the root value has been started.
+    started: bool,
+    /// Whether the document is done, or has failed.
+    done: bool,
+    /// A step that has been worked out but not handed out yet.
+    pending: Option<(Step, Span)>,
+    /// The text of the last `Key` or `String` event.
+    text: String,
+    /// The errors collected so far, when parsing in recovery mode.
+    recovered: Option<Vec<JsonError>>,
+    /// Collects comments, when they are to be attached to values.
+    attacher: Option<Attacher>,
+    /// Where the last token consumed ends.
+    last_end: Position,
+}
+
+/// Where the parser gets its tokens from.
+#[derive(Debug)]
+enum Source<'l> {
+    Str(Lexer<'l>),
+    Reader(ReaderLexer<'l>),
+    Push(PushLexer),
+}
+
+impl<'l> Source<'l> {
+    fn next_token(&mut self) -> Option<Result<CowToken<'l>, JsonError>> {
+        match self {
+            Source::Str(lexer) => lexer.next_token().map(|tok| tok.map(CowToken::from)),
+            Source::Reader(lexer) => lexer.next_token(),
+            Source::Push(lexer) => lexer.next_token(),
+        }
+    }
+
+    fn position(&self) -> Position {
+        match self {
+            Source::Str(lexer) => lexer.position(),
+            Source::Reader(lexer) => lexer.position(),
+            Source::Push(lexer) => lexer.position(),
+        }
+    }
 }
 
 /// A JSON value.
@@ -22,299 +73,1161 @@ pub struct Parser<'l> {
 /// 4. A null
 /// 5. An object
 /// 6. An array
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum JsonValue {
-    Object(HashMap<String, JsonValue>),
+    Object(Map<JsonValue>),
     String(String),
     Boolean(bool),
-    Number(f64),
+    Number(Number),
     Null,
     Array(Vec<JsonValue>),
 }
 
+impl JsonValue {
+    /// Returns the number if this value is one.
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            JsonValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an `i64` if it is an integer that fits.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().and_then(Number::as_i64)
+    }
+
+    /// Returns the value as a `u64` if it is a non-negative integer that
+    /// fits.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(Number::as_u64)
+    }
+
+    /// Returns the value as an `f64` if it is a number that can be
+    /// represented exactly as one.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().and_then(Number::as_f64)
+    }
+}
+
+/// The members of an object being parsed, with the duplicate key policy
+/// applied as they are inserted.
+#[derive(Debug)]
+struct Members {
+    map: Map<JsonValue>,
+    policy: DuplicateKeys,
+}
+
+impl Members {
+    fn new(policy: DuplicateKeys) -> Self {
+        Members {
+            map: Map::new(),
+            policy,
+        }
+    }
+
+    fn insert(&mut self, key: String, value: JsonValue) {
+        let Some(index) = self.map.get_index_of(&key) else {
+            let value = match self.policy {
+                DuplicateKeys::CollectAll => JsonValue::Array(vec![value]),
+                _ => value,
+            };
+            self.map.insert(key, value);
+            return;
+        };
+        match self.policy {
+            DuplicateKeys::LastWins => self.map[index] = value,
+            // With `Error` the duplicate has already been reported.
+            DuplicateKeys::FirstWins | DuplicateKeys::Error => {}
+            DuplicateKeys::CollectAll => {
+                if let JsonValue::Array(values) = &mut self.map[index] {
+                    values.push(value);
+                }
+            }
+        }
+    }
+}
+
+/// Builds values out of steps through the document.
+#[derive(Debug)]
+pub(crate) struct Builder {
+    policy: DuplicateKeys,
+    /// The arrays and objects that are open, innermost last.
+    open: Vec<Partial>,
+    /// The root value, once it is complete but the document is not done.
+    root: Option<JsonValue>,
+}
+
+impl Builder {
+    pub(crate) fn new(policy: DuplicateKeys) -> Self {
+        Builder {
+            policy,
+            open: Vec::new(),
+            root: None,
+        }
+    }
+
+    /// Takes in the next step. Returns the value once it is complete.
+    fn add(&mut self, step: Step) -> Option<JsonValue> {
+        let value = match step {
+            Step::StartObject => {
+                let members = Members::new(self.policy);
+                self.open.push(Partial::Object(members, None));
+                return None;
+            }
+            Step::StartArray => {
+                self.open.push(Partial::Array(Vec::new()));
+                return None;
+            }
+            Step::Key(key) => {
+                if let Some(Partial::Object(_, pending)) = self.open.last_mut() {
+                    *pending = Some(key);
+                }
+                return None;
+            }
+            Step::EndObject | Step::EndArray => match self.open.pop()? {
+                Partial::Object(members, _) => JsonValue::Object(members.map),
+                Partial::Array(array) => JsonValue::Array(array),
+            },
+            Step::Value(value) => value,
+        };
+        match self.open.last_mut() {
+            None => return Some(value),
+            Some(Partial::Array(array)) => array.push(value),
+            Some(Partial::Object(members, key)) => {
+                members.insert(key.take().unwrap_or_default(), value)
+            }
+        }
+        None
+    }
+}
+
+/// An array or object being built.
+#[derive(Debug)]
+enum Partial {
+    Array(Vec<JsonValue>),
+    /// The members so far, and the key of the member whose value is next.
+    Object(Members, Option<String>),
+}
+
+/// A step through the document: an event, with its text owned.
+#[derive(Debug)]
+enum Step {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    Key(String),
+    /// A string, number, boolean or null.
+    Value(JsonValue),
+}
+
+/// An array or object that the parser is inside of.
+#[derive(Debug)]
+enum Frame {
+    Array {
+        /// The number of elements so far, placeholders included.
+        len: usize,
+        state: State,
+    },
+    Object {
+        /// The number of members so far, duplicates included.
+        len: usize,
+        state: State,
+        /// The span of the first occurrence of each key. Only kept when
+        /// duplicate keys are errors.
+        keys: Option<HashMap<String, Span>>,
+    },
+}
+
+/// Where the parser is inside an array or object.
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// Right after the opening brace of an object.
+    Start,
+    /// Before an element, or the key of a member.
+    Next,
+    /// After the key of a member.
+    Colon,
+    /// After an element or member.
+    AfterValue,
+    /// Recovery closed the container, and only its end is left to report,
+    /// with the given span.
+    Close(Span),
+}
+
+/// Whether `tok` can be an unquoted object key in JSON5, which allows any
+/// ECMAScript identifier name, reserved words included.
+fn is_identifier_key(tok: &CowToken) -> bool {
+    match tok.token_type {
+        TokenType::Identifier | TokenType::True | TokenType::False | TokenType::Null => true,
+        TokenType::Number => tok.lexeme == "Infinity" || tok.lexeme == "NaN",
+        _ => false,
+    }
+}
+
 enum ContinueBreak {
     Continue,
-    Break,
+    /// The array or object is done. Carries the span of its closer, or an
+    /// empty span where a missing closer would go.
+    Break(Span),
 }
 
 impl<'l> Parser<'l> {
-    /// Numbers can be negative, and have decimal point.
-    const NUMBER_CONSTRAINTS: [char; 2] = ['-', '.'];
-
     /// Create a new parser for the JSON data.
     pub fn new(input: &'l str) -> Result<Self, JsonError> {
-        let lexer = Lexer::new(input)?;
-        Ok(Self { lexer, depth: 0 })
+        Self::with_options(input, ParserOptions::default())
+    }
+
+    /// Create a new parser for the JSON data with the given limits and
+    /// strictness settings.
+    pub fn with_options(input: &'l str, options: ParserOptions) -> Result<Self, JsonError> {
+        if options.max_input_size.is_some_and(|max| input.len() > max) {
+            return Err(JsonError::compose(
+                ErrorKind::InputTooLarge,
+                Span::empty(Position::START),
+            ));
+        }
+        let lexer = Lexer::new(input)?
+            .with_dialect(options.dialect)
+            .with_trivia(options.attach_comments);
+        Ok(Self::with_source(Source::Str(lexer), options))
+    }
+
+    /// Create a new parser that reads the JSON data from `reader`, through
+    /// an internal buffer, instead of needing all of it in memory.
+    ///
+    /// The parser produces the same values and errors, with the same
+    /// positions, as one given the whole input as a string. Failures to
+    /// read are reported as `ErrorKind::Io`.
+    ///
+    /// ```
+    /// use jimson::parser::{JsonValue, Parser};
+    ///
+    /// let input = std::io::Cursor::new(b"[1, 2, 3]");
+    /// let value = Parser::from_reader(input).unwrap().parse_root().unwrap();
+    /// assert_eq!(value, Parser::new("[1, 2, 3]").unwrap().parse_root().unwrap());
+    /// ```
+    pub fn from_reader(reader: impl Read + 'l) -> Result<Self, JsonError> {
+        Self::from_reader_with_options(reader, ParserOptions::default())
+    }
+
+    /// Like [`Parser::from_reader`], with the given limits and strictness
+    /// settings.
+    pub fn from_reader_with_options(
+        reader: impl Read + 'l,
+        options: ParserOptions,
+    ) -> Result<Self, JsonError> {
+        let lexer = ReaderLexer::new(reader, &options)?;
+        Ok(Self::with_source(Source::Reader(lexer), options))
+    }
+
+    /// Create a parser that is pushed its input by a
+    /// [`PushParser`](crate::push::PushParser).
+    pub(crate) fn pushed(options: ParserOptions) -> Parser<'static> {
+        let lexer = PushLexer::new(&options);
+        Parser::with_source(Source::Push(lexer), options)
     }
 
-    /// Parses a JSON object.
-    fn parse_object(&mut self) -> Result<JsonValue, JsonError> {
-        let mut obj_store = HashMap::new();
+    fn with_source(lexer: Source<'l>, options: ParserOptions) -> Self {
+        let attacher = options.attach_comments.then(Attacher::default);
+        Self {
+            lexer,
+            peeked: None,
+            options,
+            stack: Vec::new(),
+            started: false,
+            done: false,
+            pending: None,
+            text: String::new(),
+            recovered: None,
+            attacher,
+            last_end: Position::START,
+        }
+    }
 
-        if let Some('}') = self.lexer.peek() {
-            self.lexer.advance();
-            self.depth -= 1;
-            return Ok(JsonValue::Object(obj_store));
+    /// The comments found by the last parse, each attached to the nearest
+    /// value. Always empty unless
+    /// [`ParserOptions::attach_comments`](crate::options::ParserOptions::attach_comments)
+    /// is on.
+    pub fn comments(&self) -> &[Comment] {
+        self.attacher
+            .as_ref()
+            .map_or(&[], |attacher| &attacher.comments)
+    }
+
+    /// Consumes the next token.
+    fn next(&mut self) -> Option<Result<CowToken<'l>, JsonError>> {
+        let tok = match self.peeked.take() {
+            Some(tok) => tok,
+            None => self.lex(),
+        };
+        if let Some(Ok(tok)) = &tok {
+            self.last_end = tok.span.end;
         }
+        tok
+    }
+
+    /// Looks at the next token without consuming it.
+    fn peek(&mut self) -> Option<&Result<CowToken<'l>, JsonError>> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex());
+        }
+        self.peeked.as_ref().and_then(Option::as_ref)
+    }
+
+    /// Reads the next token from the lexer, handing comments to the
+    /// attacher. Whitespace and comments only reach this far when comments
+    /// are being attached.
+    fn lex(&mut self) -> Option<Result<CowToken<'l>, JsonError>> {
         loop {
-            let key = match self.parse() {
-                Ok(JsonValue::String(s)) => s,
-                Err(_) => {
-                    return Err(JsonError::compose(
-                        ErrorKind::ObjectKeyNotString,
-                        Some(self.lexer.line),
-                    ))
+            let tok = self.lexer.next_token()?;
+            match (&tok, &mut self.attacher) {
+                (Ok(t), _) if t.token_type == TokenType::Whitespace => {}
+                (Ok(t), Some(attacher)) if t.token_type == TokenType::Comment => {
+                    attacher.comment(&t.lexeme, t.span)
                 }
-                _ => unreachable!(),
+                _ => return Some(tok),
+            }
+        }
+    }
+
+    /// Steps into a member or element, for attaching comments.
+    fn enter(&mut self, segment: impl FnOnce() -> PathSegment) {
+        if let Some(attacher) = &mut self.attacher {
+            attacher.enter(segment());
+            attacher.begin_value();
+        }
+    }
+
+    /// Steps back out of a member or element.
+    fn leave(&mut self) {
+        if let Some(attacher) = &mut self.attacher {
+            attacher.leave();
+        }
+    }
+
+    /// Returns the type of the next token, or `None` at the end of the input
+    /// or if the next token is an error.
+    fn peek_type(&mut self) -> Option<TokenType> {
+        match self.peek() {
+            Some(Ok(tok)) => Some(tok.token_type.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the span of the next token, or an empty span at the end of
+    /// the input.
+    fn peek_span(&mut self) -> Span {
+        match self.peek() {
+            Some(Ok(tok)) => tok.span,
+            Some(Err(e)) => e.span,
+            None => Span::empty(self.lexer.position()),
+        }
+    }
+
+    /// Builds an error for the next token, without consuming it.
+    fn error_at_peek(&mut self, kind: ErrorKind) -> JsonError {
+        JsonError::compose(kind, self.peek_span())
+    }
+
+    /// Produces the next event, or `None` once the document is done.
+    ///
+    /// Events come in document order. Every array and object is opened by
+    /// a start event and closed by an end event, and every object member is
+    /// a `Key` event followed by the events of its value. Once the root
+    /// value has ended, the rest of the input is checked like
+    /// [`Parser::parse_root`] does before `None` is returned.
+    ///
+    /// Open arrays and objects are tracked on the heap rather than the call
+    /// stack, so how deep a document may go is only up to
+    /// [`ParserOptions::max_depth`].
+    ///
+    /// ```
+    /// use jimson::event::EventKind;
+    /// use jimson::parser::Parser;
+    ///
+    /// let mut parser = Parser::new(r#"{"a": [true]}"#).unwrap();
+    /// let mut kinds = Vec::new();
+    /// while let Some(event) = parser.next_event().unwrap() {
+    ///     kinds.push(format!("{:?}", event.kind));
+    /// }
+    /// assert_eq!(
+    ///     kinds,
+    ///     ["StartObject", "Key(\"a\")", "StartArray", "Bool(true)", "EndArray", "EndObject"]
+    /// );
+    /// ```
+    pub fn next_event(&mut self) -> Result<Option<Event<'_>>, JsonError> {
+        let Some((step, span)) = self.step()? else {
+            return Ok(None);
+        };
+        let kind = match step {
+            Step::StartObject => EventKind::StartObject,
+            Step::EndObject => EventKind::EndObject,
+            Step::StartArray => EventKind::StartArray,
+            Step::EndArray => EventKind::EndArray,
+            Step::Key(key) => {
+                self.text = key;
+                EventKind::Key(&self.text)
+            }
+            Step::Value(JsonValue::String(s)) => {
+                self.text = s;
+                EventKind::String(&self.text)
+            }
+            Step::Value(JsonValue::Number(n)) => EventKind::Number(n),
+            Step::Value(JsonValue::Boolean(b)) => EventKind::Bool(b),
+            // Only scalars come as a single step.
+            Step::Value(_) => EventKind::Null,
+        };
+        Ok(Some(Event { kind, span }))
+    }
+
+    /// Skips the next value, without building it. Before a key, skips the
+    /// whole member. At the end of an array or object, skips nothing, and
+    /// the end event is still to come.
+    ///
+    /// ```
+    /// use jimson::event::EventKind;
+    /// use jimson::parser::Parser;
+    ///
+    /// let mut parser = Parser::new(r#"[{"big": [1, 2, 3]}, 4]"#).unwrap();
+    /// parser.next_event().unwrap();
+    /// parser.skip_value().unwrap();
+    /// let event = parser.next_event().unwrap().unwrap();
+    /// assert_eq!(event.kind, EventKind::Number(4u64.into()));
+    /// ```
+    pub fn skip_value(&mut self) -> Result<(), JsonError> {
+        let mut depth = 0usize;
+        loop {
+            let Some((step, span)) = self.step()? else {
+                return Ok(());
             };
-            let Some(tok) = self.lexer.next_token() else {
-                return Err(JsonError::compose(ErrorKind::Eof, Some(self.lexer.line)));
+            match step {
+                Step::StartObject | Step::StartArray => depth += 1,
+                Step::EndObject | Step::EndArray if depth == 0 => {
+                    self.pending = Some((step, span));
+                    return Ok(());
+                }
+                Step::EndObject | Step::EndArray => depth -= 1,
+                // The value of the member follows.
+                Step::Key(_) => continue,
+                Step::Value(_) => {}
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Works out the next step through the document.
+    fn step(&mut self) -> Result<Option<(Step, Span)>, JsonError> {
+        if let Some(step) = self.pending.take() {
+            return Ok(Some(step));
+        }
+        if self.done {
+            return Ok(None);
+        }
+        let step = self.advance();
+        if step.is_err() {
+            // Nothing sensible can follow an error that was not recovered
+            // from.
+            self.done = true;
+        }
+        step
+    }
+
+    fn advance(&mut self) -> Result<Option<(Step, Span)>, JsonError> {
+        loop {
+            let step = match self.stack.last() {
+                Some(Frame::Array { state, .. }) => self.array_step(*state)?,
+                Some(Frame::Object { state, .. }) => self.object_step(*state)?,
+                None if !self.started => {
+                    self.started = true;
+                    self.check_root()?;
+                    Some(self.start_value()?)
+                }
+                None => {
+                    self.end_document()?;
+                    None
+                }
             };
-            if tok.token_type != TokenType::Colon {
+            if step.is_some() || self.done {
+                return Ok(step);
+            }
+        }
+    }
+
+    /// Takes the next step inside an array.
+    fn array_step(&mut self, state: State) -> Result<Option<(Step, Span)>, JsonError> {
+        let flow = match state {
+            State::Close(span) => return Ok(Some(self.close(span))),
+            State::AfterValue => match self.check_arr() {
+                Ok(flow) => flow,
+                Err(e) => self.recover(e, 0, TokenType::RSqbracket)?,
+            },
+            _ => {
+                match self.peek_type() {
+                    Some(TokenType::RSqbracket) => {
+                        let tok = self.next().unwrap()?;
+                        return Ok(Some(self.close(tok.span)));
+                    }
+                    Some(TokenType::Comma) => {
+                        let e = self.error_at_peek(ErrorKind::MissingValue);
+                        self.next();
+                        self.report(e)?;
+                        return Ok(None);
+                    }
+                    _ => {}
+                }
+                let len = match self.stack.last() {
+                    Some(Frame::Array { len, .. }) => *len,
+                    _ => 0,
+                };
+                if self
+                    .options
+                    .max_array_elements
+                    .is_some_and(|max| len >= max)
+                {
+                    return Err(self.error_at_peek(ErrorKind::TooManyArrayElements));
+                }
+                self.enter(|| PathSegment::Index(len));
+                return self.value_in(TokenType::RSqbracket).map(Some);
+            }
+        };
+        Ok(self.resume(flow))
+    }
+
+    /// Takes the next step inside an object. Members are reported in
+    /// document order.
+    fn object_step(&mut self, state: State) -> Result<Option<(Step, Span)>, JsonError> {
+        let flow = match state {
+            State::Start => {
+                if let Some(TokenType::Rbrace) = self.peek_type() {
+                    let tok = self.next().unwrap()?;
+                    return Ok(Some(self.close(tok.span)));
+                }
+                self.set_state(State::Next);
+                return Ok(None);
+            }
+            State::Next => return self.key(),
+            State::Colon => {
+                if self.peek_type() != Some(TokenType::Colon) {
+                    let kind = match self.peek() {
+                        Some(_) => ErrorKind::MissingColon,
+                        None => ErrorKind::Eof,
+                    };
+                    let e = self.error_at_peek(kind);
+                    self.leave();
+                    let nesting = self.skip_bad_token();
+                    return self.placeholder(e, nesting, TokenType::Rbrace).map(Some);
+                }
+                self.next();
+                return self.value_in(TokenType::Rbrace).map(Some);
+            }
+            State::AfterValue => match self.check_obj() {
+                Ok(flow) => flow,
+                Err(e) => self.recover(e, 0, TokenType::Rbrace)?,
+            },
+            State::Close(span) => return Ok(Some(self.close(span))),
+        };
+        Ok(self.resume(flow))
+    }
+
+    /// Fails if the root value is not an array or object when the options
+    /// require one.
+    fn check_root(&mut self) -> Result<(), JsonError> {
+        if !self.options.require_object_or_array {
+            return Ok(());
+        }
+        match self.peek() {
+            Some(Ok(tok))
+                if !matches!(tok.token_type, TokenType::Lbrace | TokenType::LSqBracket) =>
+            {
+                Err(self.error_at_peek(ErrorKind::NotObjectOrArray))
+            }
+            // Anything else is reported when the value is read.
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads the key of the next object member.
+    fn key(&mut self) -> Result<Option<(Step, Span)>, JsonError> {
+        let json5 = self.options.dialect == Dialect::Json5;
+        let flow = match self.peek() {
+            Some(Ok(tok))
+                if tok.token_type == TokenType::Str || (json5 && is_identifier_key(tok)) =>
+            {
+                let tok = self.next().unwrap()?;
+                let value = tok.value.or_else(|| Some(tok.lexeme.into_owned()));
+                let key = self.string_value(value, tok.span)?;
+                self.check_key(&key, tok.span)?;
+                self.enter(|| PathSegment::Key(key.clone()));
+                self.set_state(State::Colon);
+                return Ok(Some((Step::Key(key), tok.span)));
+            }
+            Some(Err(_)) => {
+                let e = self.next().unwrap().unwrap_err();
+                self.recover(e, 0, TokenType::Rbrace)?
+            }
+            _ => {
+                let e = self.error_at_peek(ErrorKind::ObjectKeyNotString);
+                let nesting = self.skip_bad_token();
+                self.recover(e, nesting, TokenType::Rbrace)?
+            }
+        };
+        Ok(self.resume(flow))
+    }
+
+    /// Checks a key of the innermost object against the duplicate key
+    /// policy and the member limit.
+    fn check_key(&mut self, key: &str, span: Span) -> Result<(), JsonError> {
+        let max = self.options.max_object_members;
+        let Some(Frame::Object { len, keys, .. }) = self.stack.last_mut() else {
+            return Ok(());
+        };
+        if max.is_some_and(|max| *len >= max) {
+            return Err(JsonError::compose(ErrorKind::TooManyObjectMembers, span));
+        }
+        *len += 1;
+        let Some(keys) = keys else {
+            return Ok(());
+        };
+        match keys.get(key).copied() {
+            Some(first) => {
+                let kind = ErrorKind::DuplicateKey(Box::new(first));
+                self.report(JsonError::compose(kind, span))
+            }
+            None => {
+                keys.insert(key.to_owned(), span);
+                Ok(())
+            }
+        }
+    }
+
+    /// Starts an element or member value of the innermost container. A
+    /// value that cannot be parsed is reported and stands in as a `null`
+    /// placeholder.
+    fn value_in(&mut self, closer: TokenType) -> Result<(Step, Span), JsonError> {
+        // A closer where the value should be is left in place, so that
+        // recovery ends the container on it.
+        if let Some(TokenType::Rbrace | TokenType::RSqbracket) = self.peek_type() {
+            let e = self.error_at_peek(ErrorKind::InvalidSyntax);
+            self.leave();
+            return self.placeholder(e, 0, closer);
+        }
+        match self.start_value() {
+            Ok(step) => Ok(step),
+            Err(e) => {
+                self.leave();
+                self.placeholder(e, 0, closer)
+            }
+        }
+    }
+
+    /// Reports `error` and yields a `null` placeholder for the value it
+    /// spoiled.
+    fn placeholder(
+        &mut self,
+        error: JsonError,
+        nesting: usize,
+        closer: TokenType,
+    ) -> Result<(Step, Span), JsonError> {
+        let span = error.span;
+        let state = match self.recover(error, nesting, closer)? {
+            ContinueBreak::Continue => State::Next,
+            ContinueBreak::Break(span) => State::Close(span),
+        };
+        self.past_value(state);
+        Ok((Step::Value(JsonValue::Null), span))
+    }
+
+    /// Consumes the first token of a value. A scalar is done at once; an
+    /// array or object becomes the innermost container.
+    fn start_value(&mut self) -> Result<(Step, Span), JsonError> {
+        let tok = match self.next() {
+            Some(tok) => tok?,
+            None => {
                 return Err(JsonError::compose(
-                    ErrorKind::MissingColon,
-                    Some(self.lexer.line),
-                ));
+                    ErrorKind::Eof,
+                    Span::empty(self.lexer.position()),
+                ))
             }
-            let value = self.parse()?;
-            obj_store.insert(key, value);
+        };
+        if let Some(attacher) = &mut self.attacher {
+            attacher.begin_value();
+        }
+        let (frame, step) = match tok.token_type {
+            TokenType::Lbrace => {
+                let keys = self.options.duplicate_keys == DuplicateKeys::Error;
+                let frame = Frame::Object {
+                    len: 0,
+                    state: State::Start,
+                    keys: keys.then(HashMap::new),
+                };
+                (frame, Step::StartObject)
+            }
+            TokenType::LSqBracket => {
+                let frame = Frame::Array {
+                    len: 0,
+                    state: State::Next,
+                };
+                (frame, Step::StartArray)
+            }
+            _ => {
+                let value = self.scalar(tok.token_type, tok.value, &tok.lexeme, tok.span)?;
+                if let Some(attacher) = &mut self.attacher {
+                    attacher.end_value(self.last_end.line, false);
+                }
+                self.value_ended();
+                return Ok((Step::Value(value), tok.span));
+            }
+        };
+        if self.stack.len() >= self.options.max_depth {
+            return Err(JsonError::compose(ErrorKind::NestingTooDeep, tok.span));
+        }
+        self.stack.push(frame);
+        Ok((step, tok.span))
+    }
+
+    /// Converts the token of a scalar into its value.
+    fn scalar(
+        &mut self,
+        token_type: TokenType,
+        value: Option<String>,
+        lexeme: &str,
+        span: Span,
+    ) -> Result<JsonValue, JsonError> {
+        match token_type {
+            TokenType::Str => Ok(JsonValue::String(self.string_value(value, span)?)),
+            TokenType::Null => Ok(JsonValue::Null),
+            TokenType::True => Ok(JsonValue::Boolean(true)),
+            TokenType::False => Ok(JsonValue::Boolean(false)),
+            TokenType::Number => self.parse_number(lexeme, span),
+            TokenType::InvalidChar('\'') => Err(JsonError::compose(ErrorKind::SingleQuote, span)),
+            _ => Err(JsonError::compose(ErrorKind::InvalidSyntax, span)),
+        }
+    }
+
+    /// Ends the innermost container, whose closer is at `span`.
+    fn close(&mut self, span: Span) -> (Step, Span) {
+        let step = match self.stack.pop() {
+            Some(Frame::Object { .. }) => Step::EndObject,
+            _ => Step::EndArray,
+        };
+        if let Some(attacher) = &mut self.attacher {
+            attacher.end_value(self.last_end.line, true);
+        }
+        self.value_ended();
+        (step, span)
+    }
 
-            match self.check_obj()? {
-                ContinueBreak::Continue => continue,
-                ContinueBreak::Break => break,
+    /// Carries on in the innermost container after a comma, or ends it.
+    fn resume(&mut self, flow: ContinueBreak) -> Option<(Step, Span)> {
+        match flow {
+            ContinueBreak::Continue => {
+                self.set_state(State::Next);
+                None
             }
+            ContinueBreak::Break(span) => Some(self.close(span)),
+        }
+    }
+
+    /// Moves the innermost container past a value that has been parsed.
+    fn value_ended(&mut self) {
+        if !self.stack.is_empty() {
+            self.leave();
+        }
+        self.past_value(State::AfterValue);
+    }
+
+    /// Moves the innermost container past a value, into `state`.
+    fn past_value(&mut self, new: State) {
+        if let Some(Frame::Array { len, .. }) = self.stack.last_mut() {
+            *len += 1;
+        }
+        self.set_state(new);
+    }
+
+    fn set_state(&mut self, new: State) {
+        match self.stack.last_mut() {
+            Some(Frame::Array { state, .. } | Frame::Object { state, .. }) => *state = new,
+            None => {}
         }
+    }
 
-        Ok(JsonValue::Object(obj_store))
+    /// Checks the rest of the input once the root value has ended.
+    fn end_document(&mut self) -> Result<(), JsonError> {
+        self.done = true;
+        // Parsing the JSON document has finished so there should not be anything
+        // left, unless the options say otherwise.
+        if !self.options.allow_trailing_content {
+            if let Err(e) = self.check_end() {
+                match &mut self.recovered {
+                    Some(errors) => errors.push(e),
+                    None => return Err(e),
+                }
+            }
+        }
+        if let Some(attacher) = &mut self.attacher {
+            attacher.finish();
+        }
+        Ok(())
     }
 
     fn check_arr(&mut self) -> Result<ContinueBreak, JsonError> {
-        match self.lexer.next_token() {
-            Some(tok) if tok.token_type == TokenType::Comma => {
-                self.lexer.skip_whitespace();
-                match self.lexer.peek() {
-                    Some('"') => Ok(ContinueBreak::Continue),
-                    Some(']') | Some(',') => Err(JsonError::compose(
-                        ErrorKind::TrailingComma,
-                        Some(self.lexer.line),
-                    )),
+        match self.peek_type() {
+            Some(TokenType::Comma) => {
+                let comma = self.next().unwrap()?;
+                match self.peek_type() {
+                    Some(TokenType::RSqbracket)
+                        if self.options.dialect.allows_trailing_commas() =>
+                    {
+                        let tok = self.next().unwrap()?;
+                        Ok(ContinueBreak::Break(tok.span))
+                    }
+                    Some(TokenType::RSqbracket | TokenType::Comma) => {
+                        Err(JsonError::compose(ErrorKind::TrailingComma, comma.span))
+                    }
                     _ => Ok(ContinueBreak::Continue),
                 }
             }
-            Some(tok) if tok.token_type == TokenType::RSqbracket => Ok(ContinueBreak::Break),
-            Some(_) => Ok(ContinueBreak::Continue),
-            None => Err(JsonError::compose(
-                ErrorKind::UnclosedDelimiter(TokenType::RSqbracket),
-                Some(self.lexer.line),
-            )),
+            Some(TokenType::RSqbracket) => {
+                let tok = self.next().unwrap()?;
+                Ok(ContinueBreak::Break(tok.span))
+            }
+            // A closing brace here means the array was never closed. Leave the
+            // brace for the object it belongs to.
+            Some(TokenType::Rbrace) => {
+                Err(self.error_at_peek(ErrorKind::UnclosedDelimiter(TokenType::RSqbracket)))
+            }
+            None if self.peek().is_none() => {
+                Err(self.error_at_peek(ErrorKind::UnclosedDelimiter(TokenType::RSqbracket)))
+            }
+            // A bad token, such as an unclosed comment, speaks for itself.
+            None => Err(self.next().unwrap().unwrap_err()),
+            // Two values without a comma between them.
+            _ => Err(self.error_at_peek(ErrorKind::InvalidSyntax)),
         }
     }
 
     fn check_obj(&mut self) -> Result<ContinueBreak, JsonError> {
-        match self.lexer.next_token() {
-            Some(tok) if tok.token_type == TokenType::Comma => {
-                self.lexer.skip_whitespace();
-                match self.lexer.peek() {
-                    Some('"') => Ok(ContinueBreak::Continue),
-                    Some('}') => Err(JsonError::compose(
-                        ErrorKind::TrailingComma,
-                        Some(self.lexer.line),
-                    )),
+        match self.peek_type() {
+            Some(TokenType::Comma) => {
+                let comma = self.next().unwrap()?;
+                match self.peek_type() {
+                    Some(TokenType::Rbrace) if self.options.dialect.allows_trailing_commas() => {
+                        let tok = self.next().unwrap()?;
+                        Ok(ContinueBreak::Break(tok.span))
+                    }
+                    Some(TokenType::Rbrace) => {
+                        Err(JsonError::compose(ErrorKind::TrailingComma, comma.span))
+                    }
                     _ => Ok(ContinueBreak::Continue),
                 }
             }
-            Some(tok) if tok.token_type == TokenType::Rbrace => Ok(ContinueBreak::Break),
-            Some(_) => Err(JsonError::compose(
-                ErrorKind::InvalidSyntax,
-                Some(self.lexer.line),
-            )),
-            None => Err(JsonError::compose(
-                ErrorKind::UnclosedDelimiter(TokenType::Rbrace),
-                Some(self.lexer.line),
-            )),
+            Some(TokenType::Rbrace) => {
+                let tok = self.next().unwrap()?;
+                Ok(ContinueBreak::Break(tok.span))
+            }
+            Some(TokenType::RSqbracket) => {
+                Err(self.error_at_peek(ErrorKind::UnclosedDelimiter(TokenType::Rbrace)))
+            }
+            None if self.peek().is_none() => {
+                Err(self.error_at_peek(ErrorKind::UnclosedDelimiter(TokenType::Rbrace)))
+            }
+            // A bad token, such as an unclosed comment, speaks for itself.
+            None => Err(self.next().unwrap().unwrap_err()),
+            _ => Err(self.error_at_peek(ErrorKind::InvalidSyntax)),
         }
     }
 
     /// Parses the JSON document.
     pub fn parse_root(&mut self) -> Result<JsonValue, JsonError> {
-        // Any whitespace in the beginning.
-        self.lexer.skip_whitespace();
         let value = self.parse()?;
-        self.lexer.skip_whitespace();
+        self.finish_root()?;
+        Ok(value)
+    }
 
-        // Parsing the JSON document has finished so there should not be anything
-        // left.
-        if self.lexer.peek().is_some() {
+    /// Checks the rest of the input once the root value has been parsed.
+    pub(crate) fn finish_root(&mut self) -> Result<(), JsonError> {
+        // The step after the root value does the checking.
+        self.step()?;
+        Ok(())
+    }
+
+    /// Parses the next of a series of root values that follow one another,
+    /// with or without whitespace between them. Returns the value and the
+    /// byte offset where it ends, or `None` at the end of the input.
+    pub(crate) fn parse_next(&mut self) -> Result<Option<(JsonValue, usize)>, JsonError> {
+        if self.peek().is_none() {
+            return Ok(None);
+        }
+        self.started = false;
+        let value = self.parse()?;
+        Ok(Some((value, self.last_end.offset)))
+    }
+
+    /// Fails if there is any input left besides whitespace. A bad token
+    /// there, such as an unclosed comment, is reported as it is.
+    fn check_end(&mut self) -> Result<(), JsonError> {
+        if let Some(Err(e)) = self.peek() {
+            return Err(e.clone());
+        }
+        if self.peek().is_some() {
+            let start = self.peek_span().start;
             return Err(JsonError::compose(
                 ErrorKind::InvalidSyntax,
-                Some(self.lexer.line),
+                Span::empty(start),
             ));
         }
-        Ok(value)
+        Ok(())
     }
 
-    pub(crate) fn parse(&mut self) -> Result<JsonValue, JsonError> {
-        let Some(tok) = self.lexer.next_token() else {
-            return Err(JsonError::compose(ErrorKind::Eof, Some(self.lexer.line)));
-        };
-        match tok.token_type {
-            TokenType::Lbrace => {
-                self.depth += 1;
-                if self.depth > MAX_DEPTH {
-                    return Err(JsonError::compose(
-                        ErrorKind::NestingTooDeep,
-                        Some(self.lexer.line),
-                    ));
-                }
-                self.parse_object()
-            }
-            TokenType::Str => self.parse_string(),
-            TokenType::Character('n') => self.parse_null(),
-            TokenType::Character('t') => self.parse_true(),
-            TokenType::Character('f') => self.parse_false(),
-            TokenType::Digit | TokenType::Character('-') => self.parse_number(),
-            TokenType::LSqBracket => {
-                self.depth += 1;
-                if self.depth > MAX_DEPTH {
-                    return Err(JsonError::compose(
-                        ErrorKind::NestingTooDeep,
-                        Some(self.lexer.line),
-                    ));
+    /// Parses the JSON document, carrying on past errors to report as many
+    /// of them as possible in one pass.
+    ///
+    /// After a bad token the parser skips ahead to the next `,`, `}` or `]`
+    /// and continues from there. An array or object that is not closed gets
+    /// a virtual closer. Values that could not be parsed are replaced with
+    /// `JsonValue::Null` placeholders, so the returned value is only a best
+    /// effort; the errors are returned in the order they were found.
+    pub fn parse_root_with_recovery(&mut self) -> (JsonValue, Vec<JsonError>) {
+        self.recovered = Some(Vec::new());
+        let value = match self.parse_root() {
+            Ok(value) => value,
+            Err(e) => {
+                self.recovered.as_mut().unwrap().push(e);
+                if let Some(attacher) = &mut self.attacher {
+                    attacher.finish();
                 }
-                self.parse_array()
+                JsonValue::Null
             }
-            TokenType::InvalidChar('\'') => Err(JsonError::compose(
-                ErrorKind::SingleQuote,
-                Some(self.lexer.line),
-            )),
-            _ => Err(JsonError::compose(
-                ErrorKind::InvalidSyntax,
-                Some(self.lexer.line),
-            )),
-        }
+        };
+        (value, self.recovered.take().unwrap_or_default())
     }
 
-    /// Parses an array
-    fn parse_array(&mut self) -> Result<JsonValue, JsonError> {
-        self.lexer.skip_whitespace();
-        let mut array: Vec<JsonValue> = Vec::new();
-
+    /// Parses the next value, building it from the steps through the
+    /// document.
+    pub(crate) fn parse(&mut self) -> Result<JsonValue, JsonError> {
+        let mut builder = Builder::new(self.options.duplicate_keys);
         loop {
-            if let Some(']') = self.lexer.peek() {
-                self.lexer.advance();
-                self.depth -= 1;
-                return Ok(JsonValue::Array(array));
-            }
-            if let Some(',') = self.lexer.peek() {
-                self.lexer.advance();
+            let Some((step, _)) = self.step()? else {
                 return Err(JsonError::compose(
-                    ErrorKind::MissingValue,
-                    Some(self.lexer.line),
+                    ErrorKind::Eof,
+                    Span::empty(self.lexer.position()),
                 ));
+            };
+            if let Some(value) = builder.add(step) {
+                return Ok(value);
             }
-            array.push(self.parse()?);
-            match self.check_arr()? {
-                ContinueBreak::Continue => continue,
-                ContinueBreak::Break => break,
+        }
+    }
+
+    /// Whether the input pushed so far is enough for the next step, which
+    /// never needs more than two tokens. Input from a string or a reader is
+    /// always enough.
+    fn ready(&self) -> bool {
+        match &self.lexer {
+            Source::Push(lexer) => {
+                let peeked = matches!(self.peeked, Some(Some(_)));
+                lexer.is_finished() || lexer.significant() + usize::from(peeked) >= 2
             }
+            _ => true,
         }
-        Ok(JsonValue::Array(array))
     }
 
-    /// Parses an integer number value.
-    fn parse_number(&mut self) -> Result<JsonValue, JsonError> {
-        self.lexer.skip_whitespace();
-        if let Some('0') = self.lexer.peek() {
-            return Err(JsonError::compose(
-                ErrorKind::NumberWithLeadingZero,
-                Some(self.lexer.line),
-            ));
+    /// Like [`Parser::next_event`], but tells apart input pushed so far
+    /// that is not enough for the next event from the end of the events.
+    pub(crate) fn next_pushed_event(&mut self) -> Result<Pushed<'_>, JsonError> {
+        if !self.ready() {
+            return Ok(Pushed::NeedMore);
         }
-        let mut string = String::new();
+        Ok(match self.next_event()? {
+            Some(event) => Pushed::Event(event),
+            None => Pushed::Done,
+        })
+    }
 
-        while let Some(ch) = self.lexer.peek() {
-            if !ch.is_ascii_digit() && !Self::NUMBER_CONSTRAINTS.contains(&ch) {
-                //self.lexer.advance();
-                break;
+    /// Builds as much of the root value as the input pushed so far allows.
+    /// Returns the value once the document is done.
+    pub(crate) fn build_pushed(
+        &mut self,
+        builder: &mut Builder,
+    ) -> Result<Option<JsonValue>, JsonError> {
+        while self.ready() {
+            match self.step()? {
+                Some((step, _)) => {
+                    if let Some(value) = builder.add(step) {
+                        builder.root = Some(value);
+                    }
+                }
+                None => return Ok(builder.root.take()),
             }
-            string.push(ch);
-            self.lexer.advance();
         }
-        let number = match string.parse() {
-            Err(e) => {
-                return Err(JsonError::compose(
-                    ErrorKind::ParseNumberError(e),
-                    Some(self.lexer.line),
-                ))
-            }
-            Ok(n) => n,
-        };
+        Ok(None)
+    }
 
-        Ok(JsonValue::Number(number))
+    pub(crate) fn push_lexer(&mut self) -> &mut PushLexer {
+        match &mut self.lexer {
+            Source::Push(lexer) => lexer,
+            _ => unreachable!("not a push parser"),
+        }
     }
 
-    /// Helper function for `parse_null` and
-    /// `parse_boolean`. Reads null and boolean values.
-    fn read_keyword(&mut self, keyword: &'l str) -> Result<(), JsonError> {
-        for c in keyword.chars() {
-            let Some(current) = self.lexer.advance() else {
-                // We're only doing this since we found the character 'n' in a
-                // JSON value. Therefore, if the input stream ends before
-                // we're done comparing with `keyword`,
-                // that can only mean an invalid value.
-                return Err(JsonError::compose(
-                    ErrorKind::InvalidSyntax,
-                    Some(self.lexer.line),
-                ));
-            };
-            if current != c {
-                return Err(JsonError::compose(
-                    ErrorKind::InvalidSyntax,
-                    Some(self.lexer.line),
-                ));
+    /// Records `error` in recovery mode, otherwise returns it. Errors for
+    /// exceeded limits are always returned, since carrying on would defeat
+    /// the limit.
+    fn report(&mut self, error: JsonError) -> Result<(), JsonError> {
+        match &mut self.recovered {
+            Some(errors) if !error.kind.is_limit() => {
+                errors.push(error);
+                Ok(())
+            }
+            _ => Err(error),
+        }
+    }
+
+    /// Reports `error` and, in recovery mode, resynchronizes inside the array
+    /// or object closed by `closer`. Returns whether that container has more
+    /// elements to parse, or where it ends.
+    ///
+    /// `nesting` is the number of arrays and objects that were opened but
+    /// not yet closed when the error was raised; they are skipped too.
+    fn recover(
+        &mut self,
+        error: JsonError,
+        nesting: usize,
+        closer: TokenType,
+    ) -> Result<ContinueBreak, JsonError> {
+        // The container is already known to be unclosed, either because the
+        // input has run out or because a mismatched closer follows.
+        let unclosed = matches!(error.kind, ErrorKind::UnclosedDelimiter(_));
+        let end = Span::empty(error.span.start);
+        self.report(error)?;
+        if unclosed {
+            return Ok(ContinueBreak::Break(end));
+        }
+        self.synchronize(nesting);
+
+        match self.peek_type() {
+            Some(TokenType::Comma) => {
+                self.next();
+                Ok(ContinueBreak::Continue)
+            }
+            Some(tok) if tok == closer => {
+                let tok = self.next().unwrap()?;
+                Ok(ContinueBreak::Break(tok.span))
+            }
+            // A mismatched closer or the end of the input: close the
+            // container here and let the caller deal with what follows.
+            _ => {
+                let e = self.error_at_peek(ErrorKind::UnclosedDelimiter(closer));
+                let end = Span::empty(e.span.start);
+                self.report(e)?;
+                Ok(ContinueBreak::Break(end))
             }
         }
-        Ok(())
     }
 
-    /// Parses the JSON `null` value.
-    fn parse_null(&mut self) -> Result<JsonValue, JsonError> {
-        self.read_keyword("null")?;
-        Ok(JsonValue::Null)
+    /// Consumes the token an error was raised for, unless it is a comma or
+    /// closing delimiter that recovery should resynchronize on. Returns the
+    /// number of arrays and objects this opened.
+    fn skip_bad_token(&mut self) -> usize {
+        match self.peek_type() {
+            Some(TokenType::Comma | TokenType::Rbrace | TokenType::RSqbracket) => 0,
+            Some(TokenType::Lbrace | TokenType::LSqBracket) => {
+                self.next();
+                1
+            }
+            _ => {
+                self.next();
+                0
+            }
+        }
     }
 
-    /// Parses the `true` boolean value.
-    fn parse_true(&mut self) -> Result<JsonValue, JsonError> {
-        self.read_keyword("true")?;
-        Ok(JsonValue::Boolean(true))
+    /// Skips tokens up to the next `,`, `}` or `]` that is outside the
+    /// `nesting` open arrays and objects and any arrays and objects met
+    /// along the way. The delimiter itself is not consumed.
+    fn synchronize(&mut self, mut nesting: usize) {
+        while let Some(tok) = self.peek() {
+            match tok.as_ref().map(|tok| &tok.token_type) {
+                Ok(TokenType::Comma | TokenType::Rbrace | TokenType::RSqbracket)
+                    if nesting == 0 =>
+                {
+                    return
+                }
+                Ok(TokenType::Rbrace | TokenType::RSqbracket) => nesting -= 1,
+                Ok(TokenType::Lbrace | TokenType::LSqBracket) => nesting += 1,
+                _ => {}
+            }
+            self.next();
+        }
     }
 
-    /// Parses the `false` boolean value.
-    fn parse_false(&mut self) -> Result<JsonValue, JsonError> {
-        self.read_keyword("false")?;
-        Ok(JsonValue::Boolean(false))
+    /// Takes the decoded contents of a string token, enforcing the string
+    /// length limit.
+    fn string_value(&self, value: Option<String>, span: Span) -> Result<String, JsonError> {
+        let value = value.unwrap_or_default();
+        if self
+            .options
+            .max_string_length
+            .is_some_and(|max| value.len() > max)
+        {
+            return Err(JsonError::compose(ErrorKind::StringTooLong, span));
+        }
+        Ok(value)
     }
 
-    /// Parses a string key or value from the JSON.
-    fn parse_string(&mut self) -> Result<JsonValue, JsonError> {
-        self.lexer.skip_whitespace();
-        let mut string = String::new();
-        while let Some(ch) = self.lexer.peek() {
-            if ch == '"' {
-                self.lexer.advance();
-                break;
+    /// Converts a number token into a value. Integers are kept exact when
+    /// they fit in an `i64` or `u64`.
+    fn parse_number(&mut self, raw: &str, span: Span) -> Result<JsonValue, JsonError> {
+        // The JSON5 extras; the lexer only lets them through in that dialect.
+        let unsigned = raw.trim_start_matches(['+', '-']);
+        let negative = raw.starts_with('-');
+        if let Some(hex) = unsigned
+            .strip_prefix("0x")
+            .or_else(|| unsigned.strip_prefix("0X"))
+        {
+            let Ok(u) = u64::from_str_radix(hex, 16) else {
+                return Err(JsonError::compose(ErrorKind::NumberOutOfRange, span));
+            };
+            return match negative {
+                false => Ok(JsonValue::Number(u.into())),
+                true => match 0i64.checked_sub_unsigned(u) {
+                    Some(i) => Ok(JsonValue::Number(i.into())),
+                    None => Err(JsonError::compose(ErrorKind::NumberOutOfRange, span)),
+                },
+            };
+        }
+        if unsigned == "Infinity" || unsigned == "NaN" {
+            let x = if unsigned == "NaN" {
+                f64::NAN
+            } else {
+                f64::INFINITY
+            };
+            return Ok(JsonValue::Number(if negative { -x } else { x }.into()));
+        }
+
+        // `-0` has no integer form, so it is kept as the float `-0.0`.
+        let is_float = raw.contains(['.', 'e', 'E']) || raw == "-0";
+        if !is_float {
+            if let Ok(u) = raw.parse::<u64>() {
+                return Ok(JsonValue::Number(u.into()));
             }
-            if ch == '\\' {
-                if let Some('\\') = self.lexer.peek() {
-                    return Err(JsonError::compose(
-                        ErrorKind::InvalidBackslashEscape,
-                        Some(self.lexer.line),
-                    ));
-                }
+            if let Ok(i) = raw.parse::<i64>() {
+                return Ok(JsonValue::Number(i.into()));
             }
-            string.push(ch);
-            self.lexer.advance();
         }
-        Ok(JsonValue::String(string))
+        let number = match raw.parse::<f64>() {
+            Err(e) => return Err(JsonError::compose(ErrorKind::ParseNumberError(e), span)),
+            Ok(n) => n,
+        };
+        // Exponents such as `1e400` overflow to infinity.
+        let Some(number) = Number::from_f64(number) else {
+            return Err(JsonError::compose(ErrorKind::NumberOutOfRange, span));
+        };
+
+        Ok(JsonValue::Number(number))
     }
 }
@@ -0,0 +1,386 @@
+use crate::errors::{PatchError, PatchErrorKind, PointerError};
+use crate::jsonpath::same;
+use crate::map::Map;
+use crate::parser::JsonValue;
+use crate::pointer::{self, JsonPointer};
+
+/// Arrays whose changed middle parts would need more cells than this to
+/// line up are compared element by element instead.
+const MAX_LCS_CELLS: usize = 1 << 20;
+
+/// A JSON Patch (RFC 6902): a list of operations that change a document.
+///
+/// Patches are applied as a whole. If an operation fails, the document
+/// keeps the value it had before the patch.
+///
+/// ```
+/// use jimson::parser::Parser;
+/// use jimson::patch::Patch;
+///
+/// let parse = |s: &str| Parser::new(s).unwrap().parse_root().unwrap();
+/// let mut config = parse(r#"{"replicas": 2, "tags": ["a"]}"#);
+/// let patch = Patch::from_value(&parse(
+///     r#"[
+///         {"op": "test", "path": "/replicas", "value": 2},
+///         {"op": "replace", "path": "/replicas", "value": 3},
+///         {"op": "add", "path": "/tags/-", "value": "b"}
+///     ]"#,
+/// ))
+/// .unwrap();
+/// patch.apply(&mut config).unwrap();
+/// assert_eq!(config, parse(r#"{"replicas": 3, "tags": ["a", "b"]}"#));
+///
+/// let before = parse(r#"{"replicas": 2, "tags": ["a"]}"#);
+/// assert_eq!(Patch::diff(&before, &config).operations().len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Patch {
+    operations: Vec<Operation>,
+}
+
+/// One operation of a [`Patch`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// Adds a member to an object, replacing one with the same key, or
+    /// inserts an element into an array before the one at `path`. A `-`
+    /// index appends, and the root pointer replaces the whole document.
+    Add { path: JsonPointer, value: JsonValue },
+    /// Removes the member or element at `path`.
+    Remove { path: JsonPointer },
+    /// Replaces the value at `path`, which has to exist.
+    Replace { path: JsonPointer, value: JsonValue },
+    /// Removes the value at `from` and adds it at `path`.
+    Move {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    /// Adds a copy of the value at `from` at `path`.
+    Copy {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    /// Checks that the value at `path` equals `value`. Numbers are equal
+    /// if their values are, and the order of object members does not
+    /// matter.
+    Test { path: JsonPointer, value: JsonValue },
+}
+
+impl Patch {
+    /// A patch that applies `operations` in order.
+    pub fn new(operations: Vec<Operation>) -> Self {
+        Patch { operations }
+    }
+
+    /// Reads a patch from its JSON form, an array of operation objects.
+    /// Members other than `op`, `path`, `from` and `value` are ignored.
+    pub fn from_value(patch: &JsonValue) -> Result<Self, PatchError> {
+        let JsonValue::Array(items) = patch else {
+            return Err(PatchError {
+                index: 0,
+                kind: PatchErrorKind::NotAnArray,
+            });
+        };
+        let operations = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                Operation::from_value(item).map_err(|kind| PatchError { index, kind })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Patch { operations })
+    }
+
+    /// The JSON form of the patch.
+    pub fn to_value(&self) -> JsonValue {
+        JsonValue::Array(self.operations.iter().map(Operation::to_value).collect())
+    }
+
+    /// The operations, in the order they are applied.
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Whether the patch has no operations, and so changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Applies the operations in order. On failure `target` is left as it
+    /// was, and the error carries the index of the operation that failed.
+    pub fn apply(&self, target: &mut JsonValue) -> Result<(), PatchError> {
+        let mut patched = target.clone();
+        for (index, operation) in self.operations.iter().enumerate() {
+            operation
+                .apply(&mut patched)
+                .map_err(|kind| PatchError { index, kind })?;
+        }
+        *target = patched;
+        Ok(())
+    }
+
+    /// Computes a patch that turns `from` into `to`.
+    ///
+    /// Only what differs is touched: members are added and removed by key,
+    /// array elements that are kept are lined up so that only the others
+    /// are added or removed, and values that changed are patched inside
+    /// where they are arrays or objects on both sides, and replaced
+    /// otherwise. The order of object members is not compared.
+    pub fn diff(from: &JsonValue, to: &JsonValue) -> Patch {
+        let mut operations = Vec::new();
+        diff(from, to, &JsonPointer::root(), &mut operations);
+        Patch { operations }
+    }
+}
+
+impl Operation {
+    fn from_value(item: &JsonValue) -> Result<Self, PatchErrorKind> {
+        let JsonValue::Object(map) = item else {
+            return Err(PatchErrorKind::NotAnObject);
+        };
+        let pointer = |name| match map.get(name) {
+            Some(JsonValue::String(text)) => {
+                JsonPointer::parse(text).map_err(PatchErrorKind::Pointer)
+            }
+            Some(_) => Err(PatchErrorKind::InvalidMember(name)),
+            None => Err(PatchErrorKind::MissingMember(name)),
+        };
+        let value = || {
+            map.get("value")
+                .cloned()
+                .ok_or(PatchErrorKind::MissingMember("value"))
+        };
+        let op = match map.get("op") {
+            Some(JsonValue::String(op)) => op,
+            Some(_) => return Err(PatchErrorKind::InvalidMember("op")),
+            None => return Err(PatchErrorKind::MissingMember("op")),
+        };
+        let path = pointer("path")?;
+        Ok(match op.as_str() {
+            "add" => Operation::Add {
+                path,
+                value: value()?,
+            },
+            "remove" => Operation::Remove { path },
+            "replace" => Operation::Replace {
+                path,
+                value: value()?,
+            },
+            "move" => Operation::Move {
+                from: pointer("from")?,
+                path,
+            },
+            "copy" => Operation::Copy {
+                from: pointer("from")?,
+                path,
+            },
+            "test" => Operation::Test {
+                path,
+                value: value()?,
+            },
+            _ => return Err(PatchErrorKind::UnknownOp(op.clone())),
+        })
+    }
+
+    fn to_value(&self) -> JsonValue {
+        let (op, path) = match self {
+            Operation::Add { path, .. } => ("add", path),
+            Operation::Remove { path } => ("remove", path),
+            Operation::Replace { path, .. } => ("replace", path),
+            Operation::Move { path, .. } => ("move", path),
+            Operation::Copy { path, .. } => ("copy", path),
+            Operation::Test { path, .. } => ("test", path),
+        };
+        let mut map = Map::new();
+        map.insert("op".to_owned(), JsonValue::String(op.to_owned()));
+        map.insert("path".to_owned(), JsonValue::String(path.to_string()));
+        match self {
+            Operation::Add { value, .. }
+            | Operation::Replace { value, .. }
+            | Operation::Test { value, .. } => {
+                map.insert("value".to_owned(), value.clone());
+            }
+            Operation::Move { from, .. } | Operation::Copy { from, .. } => {
+                map.insert("from".to_owned(), JsonValue::String(from.to_string()));
+            }
+            Operation::Remove { .. } => {}
+        }
+        JsonValue::Object(map)
+    }
+
+    fn apply(&self, target: &mut JsonValue) -> Result<(), PatchErrorKind> {
+        match self {
+            Operation::Add { path, value } => add(target, path, value.clone()),
+            Operation::Remove { path } => {
+                path.remove(target)?;
+                Ok(())
+            }
+            Operation::Replace { path, value } => {
+                *path.get_mut(target)? = value.clone();
+                Ok(())
+            }
+            Operation::Move { from, path } => {
+                let inside = from.tokens().len() < path.tokens().len()
+                    && path.tokens().starts_with(from.tokens());
+                if inside {
+                    return Err(PatchErrorKind::MoveIntoChild);
+                }
+                let value = from.remove(target)?;
+                add(target, path, value)
+            }
+            Operation::Copy { from, path } => {
+                let value = from.get(target)?.clone();
+                add(target, path, value)
+            }
+            Operation::Test { path, value } => match same(path.get(target)?, value) {
+                true => Ok(()),
+                false => Err(PatchErrorKind::TestFailed),
+            },
+        }
+    }
+}
+
+/// Adds `value` at `path`, inserting into arrays rather than replacing
+/// as [`JsonPointer::set`] does.
+fn add(target: &mut JsonValue, path: &JsonPointer, value: JsonValue) -> Result<(), PatchErrorKind> {
+    let Some((parent, last)) = path.split_last() else {
+        *target = value;
+        return Ok(());
+    };
+    match parent.get_mut(target)? {
+        JsonValue::Object(map) => {
+            map.insert(last.to_owned(), value);
+        }
+        JsonValue::Array(items) => {
+            let i = pointer::index(last, items.len())?;
+            if i > items.len() {
+                return Err(PatchErrorKind::Pointer(PointerError::NotFound));
+            }
+            items.insert(i, value);
+        }
+        _ => return Err(PatchErrorKind::Pointer(PointerError::NotFound)),
+    }
+    Ok(())
+}
+
+fn diff(from: &JsonValue, to: &JsonValue, path: &JsonPointer, operations: &mut Vec<Operation>) {
+    if from == to {
+        return;
+    }
+    let child = |token: &str| {
+        let mut child = path.clone();
+        child.push(token);
+        child
+    };
+    match (from, to) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            for key in a.keys().filter(|key| !b.contains_key(key)) {
+                operations.push(Operation::Remove { path: child(key) });
+            }
+            for (key, value) in b {
+                match a.get(key) {
+                    Some(old) => diff(old, value, &child(key), operations),
+                    None => operations.push(Operation::Add {
+                        path: child(key),
+                        value: value.clone(),
+                    }),
+                }
+            }
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => diff_arrays(a, b, path, operations),
+        _ => operations.push(Operation::Replace {
+            path: path.clone(),
+            value: to.clone(),
+        }),
+    }
+}
+
+fn diff_arrays(
+    a: &[JsonValue],
+    b: &[JsonValue],
+    path: &JsonPointer,
+    operations: &mut Vec<Operation>,
+) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    // The elements kept, as pairs of indices, followed by the ends of both
+    // arrays so that every gap ends at one.
+    let mut kept = common(a, b);
+    kept.push((a.len(), b.len()));
+
+    // The index in the array as it is being patched.
+    let mut i = prefix;
+    let (mut x, mut y) = (0, 0);
+    for (next_x, next_y) in kept {
+        let (removed, added) = (&a[x..next_x], &b[y..next_y]);
+        let changed = removed.len().min(added.len());
+        for (old, new) in removed.iter().zip(added) {
+            let mut child = path.clone();
+            child.push(i.to_string());
+            diff(old, new, &child, operations);
+            i += 1;
+        }
+        for _ in changed..removed.len() {
+            let mut child = path.clone();
+            child.push(i.to_string());
+            operations.push(Operation::Remove { path: child });
+        }
+        for value in &added[changed..] {
+            let mut child = path.clone();
+            child.push(i.to_string());
+            operations.push(Operation::Add {
+                path: child,
+                value: value.clone(),
+            });
+            i += 1;
+        }
+        // Past the element kept.
+        i += 1;
+        (x, y) = (next_x + 1, next_y + 1);
+    }
+}
+
+/// The indices of a longest common subsequence of `a` and `b`, in order.
+fn common(a: &[JsonValue], b: &[JsonValue]) -> Vec<(usize, usize)> {
+    if a.is_empty() || b.is_empty() || a.len().saturating_mul(b.len()) > MAX_LCS_CELLS {
+        return Vec::new();
+    }
+    // `lengths[x][y]` is the length of the longest common subsequence of
+    // `a[x..]` and `b[y..]`.
+    let mut lengths = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for x in (0..a.len()).rev() {
+        for y in (0..b.len()).rev() {
+            lengths[x][y] = match a[x] == b[y] {
+                true => lengths[x + 1][y + 1] + 1,
+                false => lengths[x + 1][y].max(lengths[x][y + 1]),
+            };
+        }
+    }
+    let mut kept = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    while x < a.len() && y < b.len() {
+        if a[x] == b[y] {
+            kept.push((x, y));
+            x += 1;
+            y += 1;
+        } else if lengths[x + 1][y] >= lengths[x][y + 1] {
+            x += 1;
+        } else {
+            y += 1;
+        }
+    }
+    kept
+}
+
+impl JsonValue {
+    /// Applies a patch given in its JSON form, as [`Patch::apply`] does.
+    pub fn apply_patch(&mut self, patch: &JsonValue) -> Result<(), PatchError> {
+        Patch::from_value(patch)?.apply(self)
+    }
+}
@@ -0,0 +1,242 @@
+use crate::errors::PointerError;
+use crate::parser::JsonValue;
+
+use std::cmp::Ordering;
+
+/// A JSON Pointer (RFC 6901), such as `/servers/0/name`, parsed into its
+/// reference tokens.
+///
+/// In the text form `~1` stands for `/` and `~0` for `~`; the tokens hold
+/// the unescaped keys. Applied to an array, a token must be an index, or
+/// `-` for the position just past the last element.
+///
+/// ```
+/// use jimson::parser::Parser;
+/// use jimson::pointer::JsonPointer;
+///
+/// let value = Parser::new(r#"{"a/b": [10, 20]}"#).unwrap().parse_root().unwrap();
+/// let pointer: JsonPointer = "/a~1b/1".parse().unwrap();
+/// assert_eq!(pointer.tokens(), ["a/b", "1"]);
+/// assert_eq!(pointer.get(&value).unwrap().as_i64(), Some(20));
+/// assert_eq!(pointer.to_string(), "/a~1b/1");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct JsonPointer {
+    tokens: Vec<String>,
+}
+
+impl JsonPointer {
+    /// The empty pointer, which refers to the whole value.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Parses the text form of a pointer.
+    pub fn parse(pointer: &str) -> Result<Self, PointerError> {
+        if pointer.is_empty() {
+            return Ok(Self::root());
+        }
+        let Some(rest) = pointer.strip_prefix('/') else {
+            return Err(PointerError::MissingSlash);
+        };
+        let mut tokens = Vec::new();
+        let mut offset = 1;
+        for raw in rest.split('/') {
+            tokens.push(unescape(raw, offset)?);
+            offset += raw.len() + 1;
+        }
+        Ok(JsonPointer { tokens })
+    }
+
+    /// The unescaped reference tokens, from the root down.
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// Whether this is the empty pointer, which refers to the whole value.
+    pub fn is_root(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Extends the pointer by one unescaped reference token.
+    pub fn push(&mut self, token: impl Into<String>) {
+        self.tokens.push(token.into());
+    }
+
+    /// The pointer to the array or object holding the value, and the token
+    /// for the value within it. `None` for the root.
+    pub fn split_last(&self) -> Option<(JsonPointer, &str)> {
+        let (last, parent) = self.tokens.split_last()?;
+        let parent = JsonPointer {
+            tokens: parent.to_vec(),
+        };
+        Some((parent, last))
+    }
+
+    /// Looks up the value the pointer refers to.
+    pub fn get<'v>(&self, value: &'v JsonValue) -> Result<&'v JsonValue, PointerError> {
+        self.tokens
+            .iter()
+            .try_fold(value, |value, token| match value {
+                JsonValue::Object(map) => map.get(token).ok_or(PointerError::NotFound),
+                JsonValue::Array(items) => items
+                    .get(index(token, items.len())?)
+                    .ok_or(PointerError::NotFound),
+                _ => Err(PointerError::NotFound),
+            })
+    }
+
+    /// Like [`JsonPointer::get`], for changing the value in place.
+    pub fn get_mut<'v>(&self, value: &'v mut JsonValue) -> Result<&'v mut JsonValue, PointerError> {
+        walk_mut(&self.tokens, value)
+    }
+
+    /// Puts `new` where the pointer refers to, and returns the value it
+    /// replaced, if any. A missing member is added at the end of its object,
+    /// and an index just past the end of an array, or `-`, appends to it.
+    /// The array or object itself has to exist.
+    pub fn set(
+        &self,
+        value: &mut JsonValue,
+        new: JsonValue,
+    ) -> Result<Option<JsonValue>, PointerError> {
+        let Some((last, parent)) = self.tokens.split_last() else {
+            return Ok(Some(std::mem::replace(value, new)));
+        };
+        match walk_mut(parent, value)? {
+            JsonValue::Object(map) => Ok(map.insert(last.clone(), new)),
+            JsonValue::Array(items) => {
+                let i = index(last, items.len())?;
+                match i.cmp(&items.len()) {
+                    Ordering::Less => Ok(Some(std::mem::replace(&mut items[i], new))),
+                    Ordering::Equal => {
+                        items.push(new);
+                        Ok(None)
+                    }
+                    Ordering::Greater => Err(PointerError::NotFound),
+                }
+            }
+            _ => Err(PointerError::NotFound),
+        }
+    }
+
+    /// Removes the value the pointer refers to and returns it. Later
+    /// members and elements keep their order.
+    pub fn remove(&self, value: &mut JsonValue) -> Result<JsonValue, PointerError> {
+        let Some((last, parent)) = self.tokens.split_last() else {
+            return Err(PointerError::RemoveRoot);
+        };
+        match walk_mut(parent, value)? {
+            JsonValue::Object(map) => map.shift_remove(last).ok_or(PointerError::NotFound),
+            JsonValue::Array(items) => {
+                let i = index(last, items.len())?;
+                match i < items.len() {
+                    true => Ok(items.remove(i)),
+                    false => Err(PointerError::NotFound),
+                }
+            }
+            _ => Err(PointerError::NotFound),
+        }
+    }
+}
+
+fn walk_mut<'v>(
+    tokens: &[String],
+    value: &'v mut JsonValue,
+) -> Result<&'v mut JsonValue, PointerError> {
+    tokens.iter().try_fold(value, |value, token| match value {
+        JsonValue::Object(map) => map.get_mut(token).ok_or(PointerError::NotFound),
+        JsonValue::Array(items) => {
+            let i = index(token, items.len())?;
+            items.get_mut(i).ok_or(PointerError::NotFound)
+        }
+        _ => Err(PointerError::NotFound),
+    })
+}
+
+/// Reads a reference token as an index into an array of `len` elements.
+/// `-` is the index just past the end.
+pub(crate) fn index(token: &str, len: usize) -> Result<usize, PointerError> {
+    if token == "-" {
+        return Ok(len);
+    }
+    let digits = !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit());
+    if !digits || (token.len() > 1 && token.starts_with('0')) {
+        return Err(PointerError::InvalidIndex);
+    }
+    // Too large to be in bounds.
+    Ok(token.parse().unwrap_or(usize::MAX))
+}
+
+/// Unescapes a reference token that starts at byte `offset` of the pointer.
+fn unescape(raw: &str, offset: usize) -> Result<String, PointerError> {
+    if !raw.contains('~') {
+        return Ok(raw.to_owned());
+    }
+    let mut token = String::with_capacity(raw.len());
+    let mut chars = raw.char_indices();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '~' => match chars.next() {
+                Some((_, '0')) => token.push('~'),
+                Some((_, '1')) => token.push('/'),
+                _ => return Err(PointerError::InvalidEscape(offset + i)),
+            },
+            ch => token.push(ch),
+        }
+    }
+    Ok(token)
+}
+
+impl std::str::FromStr for JsonPointer {
+    type Err = PointerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl std::fmt::Display for JsonPointer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for token in &self.tokens {
+            write!(f, "/{}", token.replace('~', "~0").replace('/', "~1"))?;
+        }
+        Ok(())
+    }
+}
+
+impl JsonValue {
+    /// Looks up a value by JSON Pointer, such as `/servers/0/name`.
+    ///
+    /// ```
+    /// use jimson::parser::Parser;
+    ///
+    /// let value = Parser::new(r#"{"servers": [{"name": "a"}]}"#).unwrap().parse_root().unwrap();
+    /// let name = value.pointer("/servers/0/name").unwrap();
+    /// assert_eq!(name, &jimson::parser::JsonValue::String("a".into()));
+    /// assert!(value.pointer("/servers/1").is_err());
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Result<&JsonValue, PointerError> {
+        JsonPointer::parse(pointer)?.get(self)
+    }
+
+    /// Like [`JsonValue::pointer`], for changing the value in place.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Result<&mut JsonValue, PointerError> {
+        JsonPointer::parse(pointer)?.get_mut(self)
+    }
+
+    /// Puts `value` at a JSON Pointer, as [`JsonPointer::set`] does, and
+    /// returns the value it replaced, if any.
+    pub fn set_pointer(
+        &mut self,
+        pointer: &str,
+        value: JsonValue,
+    ) -> Result<Option<JsonValue>, PointerError> {
+        JsonPointer::parse(pointer)?.set(self, value)
+    }
+
+    /// Removes the value at a JSON Pointer and returns it.
+    pub fn remove_pointer(&mut self, pointer: &str) -> Result<JsonValue, PointerError> {
+        JsonPointer::parse(pointer)?.remove(self)
+    }
+}
@@ -0,0 +1,93 @@
+/// A location in the input.
+///
+/// `line` and `column` are 1-based, and `column` counts characters rather
+/// than bytes. `offset` is the 0-based byte offset into the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+impl Position {
+    /// The position of the first character of the input.
+    pub const START: Position = Position {
+        line: 1,
+        column: 1,
+        offset: 0,
+    };
+
+    /// Translates a position within a piece of the input that starts at
+    /// `base` into a position within the whole input.
+    pub(crate) fn rebase(self, base: Position) -> Position {
+        Position {
+            line: base.line + self.line - 1,
+            column: match self.line {
+                1 => base.column + self.column - 1,
+                _ => self.column,
+            },
+            offset: base.offset + self.offset,
+        }
+    }
+
+    /// Returns the position just past `ch`, if `ch` starts at `self`.
+    pub(crate) fn after(self, ch: char) -> Position {
+        if ch == '\n' {
+            Position {
+                line: self.line + 1,
+                column: 1,
+                offset: self.offset + 1,
+            }
+        } else {
+            Position {
+                line: self.line,
+                column: self.column + 1,
+                offset: self.offset + ch.len_utf8(),
+            }
+        }
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::START
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A range of the input, from `start` up to but not including `end`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// Creates a span between two positions.
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
+    }
+
+    /// Creates an empty span at `position`.
+    pub fn empty(position: Position) -> Self {
+        Span {
+            start: position,
+            end: position,
+        }
+    }
+
+    /// Like [`Position::rebase`], for both ends of the span.
+    pub(crate) fn rebase(self, base: Position) -> Span {
+        Span::new(self.start.rebase(base), self.end.rebase(base))
+    }
+
+    /// Returns the byte range covered by the span.
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.start.offset..self.end.offset
+    }
+}
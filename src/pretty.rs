@@ -0,0 +1,296 @@
+use crate::errors::SerializeError;
+use crate::map::Map;
+use crate::parser::JsonValue;
+use crate::serializer::{write_number, write_string, IoAdapter};
+
+type Result<T> = std::result::Result<T, SerializeError>;
+
+/// The unit of indentation used by the pretty printer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    /// Indent with the given number of spaces per level.
+    Spaces(usize),
+    /// Indent with one tab per level.
+    Tab,
+}
+
+/// Layout options for the pretty printer.
+///
+/// The defaults indent with two spaces, put a space after colons, keep keys
+/// in their original order, expand every non-empty array and object, and end
+/// the output with a newline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrettyConfig {
+    indent: Indent,
+    space_after_colon: bool,
+    sort_keys: bool,
+    max_width: Option<usize>,
+    trailing_newline: bool,
+    ensure_ascii: bool,
+}
+
+impl Default for PrettyConfig {
+    fn default() -> Self {
+        PrettyConfig {
+            indent: Indent::Spaces(2),
+            space_after_colon: true,
+            sort_keys: false,
+            max_width: None,
+            trailing_newline: true,
+            ensure_ascii: false,
+        }
+    }
+}
+
+impl PrettyConfig {
+    /// Creates a config with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the indentation unit.
+    pub fn indent(mut self, indent: Indent) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Whether to put a space between a key's colon and its value.
+    pub fn space_after_colon(mut self, enabled: bool) -> Self {
+        self.space_after_colon = enabled;
+        self
+    }
+
+    /// Whether to write object members sorted by key instead of in their
+    /// original order.
+    pub fn sort_keys(mut self, enabled: bool) -> Self {
+        self.sort_keys = enabled;
+        self
+    }
+
+    /// Keeps an array or object on a single line when it fits within
+    /// `width` columns, including its indentation and key. `None` expands
+    /// every non-empty array and object.
+    pub fn max_width(mut self, width: Option<usize>) -> Self {
+        self.max_width = width;
+        self
+    }
+
+    /// Whether to end the output with a newline.
+    pub fn trailing_newline(mut self, enabled: bool) -> Self {
+        self.trailing_newline = enabled;
+        self
+    }
+
+    /// Whether to escape every non-ASCII character as `\uXXXX`.
+    pub fn ensure_ascii(mut self, enabled: bool) -> Self {
+        self.ensure_ascii = enabled;
+        self
+    }
+}
+
+/// Serializes a value as pretty-printed JSON text.
+pub fn to_string_pretty(value: &JsonValue, config: &PrettyConfig) -> Result<String> {
+    let mut out = String::new();
+    to_fmt_writer_pretty(&mut out, value, config)?;
+    Ok(out)
+}
+
+/// Writes a value as pretty-printed JSON text to a `std::fmt::Write`.
+pub fn to_fmt_writer_pretty<W: std::fmt::Write>(
+    writer: &mut W,
+    value: &JsonValue,
+    config: &PrettyConfig,
+) -> Result<()> {
+    let mut printer = PrettyPrinter { w: writer, config };
+    printer.write_value(value, 0, 0)?;
+    if config.trailing_newline {
+        printer.w.write_char('\n')?;
+    }
+    Ok(())
+}
+
+/// Writes a value as pretty-printed JSON text to a `std::io::Write`.
+pub fn to_writer_pretty<W: std::io::Write>(
+    writer: &mut W,
+    value: &JsonValue,
+    config: &PrettyConfig,
+) -> Result<()> {
+    let mut adapter = IoAdapter {
+        inner: writer,
+        error: None,
+    };
+    to_fmt_writer_pretty(&mut adapter, value, config).map_err(|e| adapter.take_error(e))
+}
+
+struct PrettyPrinter<'a, W> {
+    w: &'a mut W,
+    config: &'a PrettyConfig,
+}
+
+impl<W: std::fmt::Write> PrettyPrinter<'_, W> {
+    /// Writes `value` at nesting level `depth`. `column` is where the value
+    /// starts on the current line, used to decide whether it fits inline.
+    fn write_value(&mut self, value: &JsonValue, depth: usize, column: usize) -> Result<()> {
+        match value {
+            JsonValue::Array(array) if !array.is_empty() => {
+                if self.try_inline(value, column)? {
+                    return Ok(());
+                }
+                self.w.write_char('[')?;
+                for (i, element) in array.iter().enumerate() {
+                    if i > 0 {
+                        self.w.write_char(',')?;
+                    }
+                    let column = self.newline(depth + 1)?;
+                    self.write_value(element, depth + 1, column)?;
+                }
+                self.newline(depth)?;
+                self.w.write_char(']')?;
+            }
+            JsonValue::Object(object) if !object.is_empty() => {
+                if self.try_inline(value, column)? {
+                    return Ok(());
+                }
+                self.w.write_char('{')?;
+                for (i, (key, member)) in members(object, self.config).into_iter().enumerate() {
+                    if i > 0 {
+                        self.w.write_char(',')?;
+                    }
+                    let mut column = self.newline(depth + 1)?;
+                    let mut prefix = String::new();
+                    write_string(&mut prefix, key, self.config.ensure_ascii)?;
+                    prefix.push_str(colon(self.config));
+                    column += prefix.chars().count();
+                    self.w.write_str(&prefix)?;
+                    self.write_value(member, depth + 1, column)?;
+                }
+                self.newline(depth)?;
+                self.w.write_char('}')?;
+            }
+            _ => write_inline(self.w, value, self.config)?,
+        }
+        Ok(())
+    }
+
+    /// Writes `value` on one line if a width limit is set and the value,
+    /// plus a following comma, fits within it. Returns whether it did.
+    fn try_inline(&mut self, value: &JsonValue, column: usize) -> Result<bool> {
+        let Some(max_width) = self.config.max_width else {
+            return Ok(false);
+        };
+        let Some(budget) = max_width.checked_sub(column + 1) else {
+            return Ok(false);
+        };
+        let mut line = BoundedString {
+            buf: String::new(),
+            remaining: budget,
+        };
+        match write_inline(&mut line, value, self.config) {
+            Ok(()) => {
+                self.w.write_str(&line.buf)?;
+                Ok(true)
+            }
+            // The bounded buffer signals "too wide" with a formatting error.
+            Err(SerializeError::Fmt(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Starts a new line indented to `depth` and returns the column reached.
+    fn newline(&mut self, depth: usize) -> Result<usize> {
+        self.w.write_char('\n')?;
+        let width = match self.config.indent {
+            Indent::Spaces(n) => {
+                for _ in 0..n * depth {
+                    self.w.write_char(' ')?;
+                }
+                n * depth
+            }
+            Indent::Tab => {
+                for _ in 0..depth {
+                    self.w.write_char('\t')?;
+                }
+                // Count a tab as one column; editors vary too much to guess.
+                depth
+            }
+        };
+        Ok(width)
+    }
+}
+
+/// Returns the members of `object` in the order they should be written.
+fn members<'v>(
+    object: &'v Map<JsonValue>,
+    config: &PrettyConfig,
+) -> Vec<(&'v String, &'v JsonValue)> {
+    let mut members: Vec<_> = object.iter().collect();
+    if config.sort_keys {
+        members.sort_by(|a, b| a.0.cmp(b.0));
+    }
+    members
+}
+
+fn colon(config: &PrettyConfig) -> &'static str {
+    if config.space_after_colon {
+        ": "
+    } else {
+        ":"
+    }
+}
+
+/// Writes `value` on a single line, with a space after each comma.
+fn write_inline<W: std::fmt::Write>(
+    w: &mut W,
+    value: &JsonValue,
+    config: &PrettyConfig,
+) -> Result<()> {
+    match value {
+        JsonValue::Null => w.write_str("null")?,
+        JsonValue::Boolean(true) => w.write_str("true")?,
+        JsonValue::Boolean(false) => w.write_str("false")?,
+        JsonValue::Number(n) => write_number(w, n)?,
+        JsonValue::String(s) => write_string(w, s, config.ensure_ascii)?,
+        JsonValue::Array(array) => {
+            w.write_char('[')?;
+            for (i, element) in array.iter().enumerate() {
+                if i > 0 {
+                    w.write_str(", ")?;
+                }
+                write_inline(w, element, config)?;
+            }
+            w.write_char(']')?;
+        }
+        JsonValue::Object(object) => {
+            w.write_char('{')?;
+            for (i, (key, member)) in members(object, config).into_iter().enumerate() {
+                if i > 0 {
+                    w.write_str(", ")?;
+                }
+                write_string(w, key, config.ensure_ascii)?;
+                w.write_str(colon(config))?;
+                write_inline(w, member, config)?;
+            }
+            w.write_char('}')?;
+        }
+    }
+    Ok(())
+}
+
+/// A string buffer that refuses to grow past a number of characters, so
+/// that measuring whether a value fits on a line stops early.
+struct BoundedString {
+    buf: String,
+    remaining: usize,
+}
+
+impl std::fmt::Write for BoundedString {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let len = s.chars().count();
+        if len > self.remaining {
+            return Err(std::fmt::Error);
+        }
+        self.remaining -= len;
+        self.buf.push_str(s);
+        Ok(())
+    }
+}
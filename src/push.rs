@@ -0,0 +1,116 @@
+use crate::comment::Comment;
+use crate::errors::JsonError;
+use crate::event::Event;
+use crate::options::ParserOptions;
+use crate::parser::{Builder, JsonValue, Parser};
+
+/// A parser that is handed its input a piece at a time, as it arrives,
+/// rather than reading it from a string or a reader.
+///
+/// Feed each piece with [`PushParser::feed`] and mark the end of the input
+/// with [`PushParser::finish`]. Pieces may split the input anywhere, even
+/// inside a token, a UTF-8 sequence or a `\u` escape. Positions count from
+/// the start of the whole input, so values and errors come out exactly as
+/// from [`Parser`] given the whole input as one string.
+///
+/// Events are handed out as soon as the input says enough about them,
+/// which is usually once the token after them has arrived too. Pull either
+/// events or the value from one parser, not both.
+///
+/// ```
+/// use jimson::event::EventKind;
+/// use jimson::parser::JsonValue;
+/// use jimson::push::{PushParser, Pushed};
+///
+/// let mut parser = PushParser::new();
+/// parser.feed(b"[1, \"caf\xc3").unwrap();
+/// assert!(matches!(parser.next_event().unwrap(), Pushed::Event(e) if e.kind == EventKind::StartArray));
+/// assert!(matches!(parser.next_event().unwrap(), Pushed::Event(e) if e.kind == EventKind::Number(1u64.into())));
+/// assert_eq!(parser.next_event().unwrap(), Pushed::NeedMore);
+///
+/// parser.feed(b"\xa9\"]").unwrap();
+/// parser.finish().unwrap();
+/// assert!(matches!(parser.next_event().unwrap(), Pushed::Event(e) if e.kind == EventKind::String("café")));
+/// assert!(matches!(parser.next_event().unwrap(), Pushed::Event(e) if e.kind == EventKind::EndArray));
+/// assert_eq!(parser.next_event().unwrap(), Pushed::Done);
+///
+/// let mut parser = PushParser::new();
+/// for byte in b"{\"a\": 1}" {
+///     parser.feed(&[*byte]).unwrap();
+///     assert_eq!(parser.value().unwrap(), None);
+/// }
+/// parser.finish().unwrap();
+/// assert!(matches!(parser.value().unwrap(), Some(JsonValue::Object(_))));
+/// ```
+#[derive(Debug)]
+pub struct PushParser {
+    parser: Parser<'static>,
+    builder: Builder,
+}
+
+/// What [`PushParser::next_event`] found.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pushed<'a> {
+    /// The next event.
+    Event(Event<'a>),
+    /// The input so far is not enough to tell what comes next. Feed more
+    /// of it, or mark its end with [`PushParser::finish`].
+    NeedMore,
+    /// The document is done, or failed; there are no more events.
+    Done,
+}
+
+impl PushParser {
+    /// Creates a push parser for strict JSON with the default limits.
+    pub fn new() -> Self {
+        Self::with_options(ParserOptions::default())
+    }
+
+    /// Create a new push parser with the given limits and strictness
+    /// settings. Recovery mode is not available.
+    pub fn with_options(options: ParserOptions) -> Self {
+        PushParser {
+            builder: Builder::new(options.duplicate_keys),
+            parser: Parser::pushed(options),
+        }
+    }
+
+    /// Adds the next piece of the input. Fails with `ErrorKind::Io` if the
+    /// input is not valid UTF-8, and with `ErrorKind::InputTooLarge` once
+    /// there is more of it than the options allow.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<(), JsonError> {
+        self.parser.push_lexer().feed(bytes)
+    }
+
+    /// Marks the end of the input. Fails with `ErrorKind::EmptyInput` if
+    /// nothing was fed.
+    pub fn finish(&mut self) -> Result<(), JsonError> {
+        self.parser.push_lexer().finish()
+    }
+
+    /// Produces the next event, like [`Parser::next_event`], or tells
+    /// whether more input is needed for it.
+    pub fn next_event(&mut self) -> Result<Pushed<'_>, JsonError> {
+        self.parser.next_pushed_event()
+    }
+
+    /// Builds as much of the root value as the input so far allows.
+    /// Returns the value once the document is done, which takes
+    /// [`PushParser::finish`] unless the document fails, and `None` until
+    /// then.
+    pub fn value(&mut self) -> Result<Option<JsonValue>, JsonError> {
+        self.parser.build_pushed(&mut self.builder)
+    }
+
+    /// The comments found so far, each attached to the nearest value, like
+    /// [`Parser::comments`].
+    pub fn comments(&self) -> &[Comment] {
+        self.parser.comments()
+    }
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,477 @@
+//! A matcher for I-Regexp (RFC 9485), the regular expressions used by the
+//! JSONPath `match` and `search` functions.
+//!
+//! Patterns are compiled to a small NFA and run without backtracking, so
+//! matching takes time linear in the length of the text whatever the
+//! pattern.
+
+/// Patterns that would compile to more instructions than this, usually
+/// through large counted repetitions, are refused.
+const MAX_PROGRAM: usize = 10_000;
+
+/// How deeply groups may nest. Parsing and compiling recurse into them.
+const MAX_GROUP_DEPTH: usize = 64;
+
+/// Why a pattern could not be compiled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RegexError {
+    /// The pattern is not a valid I-Regexp.
+    Invalid,
+    /// The pattern is valid, but uses a category other than `L`, `Lu`, `Ll`,
+    /// `N` and `Cc`, is too large, or nests groups more than 64 deep.
+    Unsupported,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Regex {
+    program: Vec<Inst>,
+}
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(Class),
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+#[derive(Debug, Clone)]
+struct Class {
+    negated: bool,
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Range(char, char),
+    /// A `\p{..}` category, or with `true` a `\P{..}` one.
+    Category(Category, bool),
+}
+
+/// The Unicode categories that can be told apart without tables of our
+/// own. `L`, `Lu` and `Ll` follow the Alphabetic, Uppercase and Lowercase
+/// properties, which are slightly wider than the general categories.
+#[derive(Debug, Clone, Copy)]
+enum Category {
+    Letter,
+    Uppercase,
+    Lowercase,
+    Number,
+    Control,
+}
+
+#[derive(Debug)]
+enum Node {
+    Class(Class),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat(Box<Node>, u32, Option<u32>),
+}
+
+impl Regex {
+    /// Compiles a pattern.
+    pub(crate) fn new(pattern: &str) -> Result<Regex, RegexError> {
+        let mut parser = PatternParser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            depth: 0,
+            unknown_category: false,
+            too_deep: false,
+        };
+        let node = parser.alternation();
+        if parser.too_deep {
+            return Err(RegexError::Unsupported);
+        }
+        let node = node
+            .filter(|_| parser.pos == parser.chars.len())
+            .ok_or(RegexError::Invalid)?;
+        if parser.unknown_category {
+            return Err(RegexError::Unsupported);
+        }
+        let mut program = Vec::new();
+        compile(&node, &mut program).ok_or(RegexError::Unsupported)?;
+        program.push(Inst::Match);
+        Ok(Regex { program })
+    }
+
+    /// Whether the pattern matches the whole of `text`.
+    pub(crate) fn is_match(&self, text: &str) -> bool {
+        self.run(text, false)
+    }
+
+    /// Whether the pattern matches somewhere in `text`.
+    pub(crate) fn is_found(&self, text: &str) -> bool {
+        self.run(text, true)
+    }
+
+    fn run(&self, text: &str, anywhere: bool) -> bool {
+        let mut threads = Vec::new();
+        let mut next = Vec::new();
+        // The step at which each instruction was last added, so that no
+        // thread is added twice in one step.
+        let mut seen = vec![usize::MAX; self.program.len()];
+        self.add(&mut threads, &mut seen, 0, 0);
+        for (step, ch) in text.chars().enumerate() {
+            if anywhere && self.has_match(&threads) {
+                return true;
+            }
+            for &pc in &threads {
+                if let Inst::Char(class) = &self.program[pc] {
+                    if class.contains(ch) {
+                        self.add(&mut next, &mut seen, pc + 1, step + 1);
+                    }
+                }
+            }
+            if anywhere {
+                self.add(&mut next, &mut seen, 0, step + 1);
+            }
+            std::mem::swap(&mut threads, &mut next);
+            next.clear();
+            if threads.is_empty() {
+                return false;
+            }
+        }
+        self.has_match(&threads)
+    }
+
+    fn has_match(&self, threads: &[usize]) -> bool {
+        threads
+            .iter()
+            .any(|&pc| matches!(self.program[pc], Inst::Match))
+    }
+
+    /// Adds a thread at `pc`, following jumps and splits.
+    fn add(&self, threads: &mut Vec<usize>, seen: &mut [usize], pc: usize, step: usize) {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if seen[pc] == step {
+                continue;
+            }
+            seen[pc] = step;
+            match self.program[pc] {
+                Inst::Jump(to) => stack.push(to),
+                Inst::Split(a, b) => stack.extend([b, a]),
+                Inst::Char(_) | Inst::Match => threads.push(pc),
+            }
+        }
+    }
+}
+
+impl Class {
+    fn one(ch: char) -> Class {
+        Class {
+            negated: false,
+            items: vec![Item::Range(ch, ch)],
+        }
+    }
+
+    fn contains(&self, ch: char) -> bool {
+        let found = self.items.iter().any(|item| match *item {
+            Item::Range(lo, hi) => lo <= ch && ch <= hi,
+            Item::Category(category, negated) => category.contains(ch) != negated,
+        });
+        found != self.negated
+    }
+}
+
+impl Category {
+    fn contains(self, ch: char) -> bool {
+        match self {
+            Category::Letter => ch.is_alphabetic(),
+            Category::Uppercase => ch.is_uppercase(),
+            Category::Lowercase => ch.is_lowercase(),
+            Category::Number => ch.is_numeric(),
+            Category::Control => ch.is_control(),
+        }
+    }
+}
+
+fn compile(node: &Node, program: &mut Vec<Inst>) -> Option<()> {
+    if program.len() > MAX_PROGRAM {
+        return None;
+    }
+    match node {
+        Node::Class(class) => program.push(Inst::Char(class.clone())),
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, program)?;
+            }
+        }
+        Node::Alternate(branches) => {
+            let mut jumps = Vec::new();
+            let (last, rest) = branches.split_last().expect("at least one branch");
+            for branch in rest {
+                let split = program.len();
+                program.push(Inst::Split(split + 1, 0));
+                compile(branch, program)?;
+                jumps.push(program.len());
+                program.push(Inst::Jump(0));
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+            compile(last, program)?;
+            let end = program.len();
+            for jump in jumps {
+                program[jump] = Inst::Jump(end);
+            }
+        }
+        Node::Repeat(node, min, max) => {
+            // Checked up front, as an empty group adds no instructions.
+            if (*min).max(max.unwrap_or(0)) as usize > MAX_PROGRAM {
+                return None;
+            }
+            for _ in 0..*min {
+                compile(node, program)?;
+            }
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(node, program)?;
+                    program.push(Inst::Jump(split));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(0, 0));
+                        compile(node, program)?;
+                    }
+                    let end = program.len();
+                    for split in splits {
+                        program[split] = Inst::Split(split + 1, end);
+                    }
+                }
+            }
+        }
+    }
+    (program.len() <= MAX_PROGRAM).then_some(())
+}
+
+struct PatternParser {
+    chars: Vec<char>,
+    pos: usize,
+    /// How many groups the parser is inside.
+    depth: usize,
+    /// Set on a category that [`Category`] cannot tell apart. Parsing goes
+    /// on, to tell whether the rest of the pattern is valid.
+    unknown_category: bool,
+    /// Set when groups nest more than [`MAX_GROUP_DEPTH`] deep, which stops
+    /// parsing.
+    too_deep: bool,
+}
+
+impl PatternParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += 1;
+        Some(ch)
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        let found = self.peek() == Some(ch);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn alternation(&mut self) -> Option<Node> {
+        let mut branches = vec![self.branch()?];
+        while self.eat('|') {
+            branches.push(self.branch()?);
+        }
+        Some(match branches.len() {
+            1 => branches.pop().expect("one branch"),
+            _ => Node::Alternate(branches),
+        })
+    }
+
+    fn branch(&mut self) -> Option<Node> {
+        let mut pieces = Vec::new();
+        while let Some(ch) = self.peek() {
+            let atom = match ch {
+                '|' | ')' => break,
+                '(' => {
+                    if self.depth == MAX_GROUP_DEPTH {
+                        self.too_deep = true;
+                        return None;
+                    }
+                    self.pos += 1;
+                    self.depth += 1;
+                    let group = self.alternation()?;
+                    self.depth -= 1;
+                    if !self.eat(')') {
+                        return None;
+                    }
+                    group
+                }
+                '.' => {
+                    self.pos += 1;
+                    Node::Class(Class {
+                        negated: true,
+                        items: vec![Item::Range('\n', '\n'), Item::Range('\r', '\r')],
+                    })
+                }
+                '[' => {
+                    self.pos += 1;
+                    Node::Class(self.class_expr()?)
+                }
+                '\\' => {
+                    self.pos += 1;
+                    Node::Class(match self.escape()? {
+                        Escaped::Char(ch) => Class::one(ch),
+                        Escaped::Category(item) => Class {
+                            negated: false,
+                            items: vec![item],
+                        },
+                    })
+                }
+                '*' | '+' | '?' | '{' | '}' | ']' => return None,
+                ch => {
+                    self.pos += 1;
+                    Node::Class(Class::one(ch))
+                }
+            };
+            pieces.push(self.quantified(atom)?);
+        }
+        Some(Node::Concat(pieces))
+    }
+
+    fn quantified(&mut self, atom: Node) -> Option<Node> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.pos += 1;
+                let min = self.count()?;
+                let max = match self.eat(',') {
+                    true if self.peek() == Some('}') => None,
+                    true => Some(self.count()?),
+                    false => Some(min),
+                };
+                if self.peek() != Some('}') || max.is_some_and(|max| max < min) {
+                    return None;
+                }
+                (min, max)
+            }
+            _ => return Some(atom),
+        };
+        self.pos += 1;
+        Some(Node::Repeat(Box::new(atom), min, max))
+    }
+
+    fn count(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return None;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        // Anything past `u32::MAX` is too large to compile anyway.
+        Some(digits.parse().unwrap_or(u32::MAX))
+    }
+
+    /// Reads a character class after its `[`.
+    fn class_expr(&mut self) -> Option<Class> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        // A `-` is literal at the start and the end.
+        if self.eat('-') {
+            items.push(Item::Range('-', '-'));
+        }
+        loop {
+            match self.peek()? {
+                ']' if !items.is_empty() => break,
+                '-' => {
+                    self.pos += 1;
+                    if self.peek() != Some(']') {
+                        return None;
+                    }
+                    items.push(Item::Range('-', '-'));
+                    break;
+                }
+                _ => {}
+            }
+            let lo = match self.class_char()? {
+                Escaped::Char(ch) => ch,
+                Escaped::Category(item) => {
+                    items.push(item);
+                    continue;
+                }
+            };
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']') {
+                self.pos += 1;
+                match self.class_char()? {
+                    Escaped::Char(hi) if lo <= hi => items.push(Item::Range(lo, hi)),
+                    _ => return None,
+                }
+            } else {
+                items.push(Item::Range(lo, lo));
+            }
+        }
+        self.pos += 1;
+        Some(Class { negated, items })
+    }
+
+    fn class_char(&mut self) -> Option<Escaped> {
+        match self.next()? {
+            '\\' => self.escape(),
+            '[' | ']' | '-' => None,
+            ch => Some(Escaped::Char(ch)),
+        }
+    }
+
+    /// Reads an escape after its `\`.
+    fn escape(&mut self) -> Option<Escaped> {
+        let ch = self.next()?;
+        let ch = match ch {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '(' | ')' | '*' | '+' | '-' | '.' | '?' | '[' | '\\' | ']' | '^' | '{' | '|' | '}' => {
+                ch
+            }
+            'p' | 'P' => {
+                if !self.eat('{') {
+                    return None;
+                }
+                let start = self.pos;
+                while self.peek().is_some_and(|ch| ch != '}') {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                self.next()?;
+                let category = match name.as_str() {
+                    "L" => Category::Letter,
+                    "Lu" => Category::Uppercase,
+                    "Ll" => Category::Lowercase,
+                    "N" => Category::Number,
+                    "Cc" => Category::Control,
+                    "Lt" | "Lm" | "Lo" | "M" | "Mn" | "Mc" | "Me" | "Nd" | "Nl" | "No" | "P"
+                    | "Pc" | "Pd" | "Ps" | "Pe" | "Pi" | "Pf" | "Po" | "Z" | "Zs" | "Zl" | "Zp"
+                    | "S" | "Sm" | "Sc" | "Sk" | "So" | "C" | "Cf" | "Co" | "Cn" => {
+                        self.unknown_category = true;
+                        Category::Letter
+                    }
+                    _ => return None,
+                };
+                return Some(Escaped::Category(Item::Category(category, ch == 'P')));
+            }
+            _ => return None,
+        };
+        Some(Escaped::Char(ch))
+    }
+}
+
+enum Escaped {
+    Char(char),
+    Category(Item),
+}
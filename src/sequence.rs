@@ -0,0 +1,173 @@
+use crate::errors::{ErrorKind, JsonError};
+use crate::options::ParserOptions;
+use crate::parser::{JsonValue, Parser};
+use crate::position::{Position, Span};
+
+/// The RFC 7464 record separator.
+const RS: char = '\u{1E}';
+
+/// Iterates over the root values of an input that holds more than one.
+///
+/// By default the values simply follow one another, with or without
+/// whitespace between them, as in `{"a": 1}{"b": 2} [3]`. The first error
+/// ends the iteration, since there is no telling where the next value
+/// starts.
+///
+/// With [`StreamDeserializer::json_seq`] the input is an RFC 7464 JSON text
+/// sequence instead, where each value is a record that starts with the
+/// record separator `0x1E` and ends with a line feed. A record without the
+/// line feed may have been cut short, and is reported as
+/// `ErrorKind::TruncatedRecord`. Bad records are skipped after their error,
+/// and empty ones are ignored.
+///
+/// Each item is a value along with the byte offset where it ends.
+///
+/// ```
+/// use jimson::errors::ErrorKind;
+/// use jimson::parser::Parser;
+/// use jimson::sequence::StreamDeserializer;
+///
+/// let parser = Parser::new(r#"{"a": 1}{"b": 2} [3]"#).unwrap();
+/// let ends: Vec<_> = StreamDeserializer::new(parser)
+///     .map(|item| item.unwrap().1)
+///     .collect();
+/// assert_eq!(ends, [8, 16, 20]);
+///
+/// let mut values = StreamDeserializer::json_seq("\x1e[1]\n\x1e{\"a\":\x1e2\n");
+/// assert_eq!(values.next().unwrap().unwrap().1, 4);
+/// assert_eq!(values.next().unwrap().unwrap_err().kind, ErrorKind::TruncatedRecord);
+/// assert_eq!(values.next().unwrap().unwrap().1, 13);
+/// assert!(values.next().is_none());
+/// ```
+#[derive(Debug)]
+pub struct StreamDeserializer<'l> {
+    source: Source<'l>,
+    /// Where the last value ended.
+    offset: usize,
+    done: bool,
+}
+
+#[derive(Debug)]
+enum Source<'l> {
+    Concatenated(Box<Parser<'l>>),
+    Sequence {
+        input: &'l str,
+        options: ParserOptions,
+        /// Where the next record starts.
+        next: Position,
+    },
+}
+
+impl<'l> StreamDeserializer<'l> {
+    /// Iterates over values that follow one another in the input of
+    /// `parser`.
+    pub fn new(parser: Parser<'l>) -> Self {
+        StreamDeserializer {
+            source: Source::Concatenated(Box::new(parser)),
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Iterates over the records of an RFC 7464 JSON text sequence.
+    pub fn json_seq(input: &'l str) -> Self {
+        Self::json_seq_with_options(input, ParserOptions::default())
+    }
+
+    /// Like [`StreamDeserializer::json_seq`], parsing each record with the
+    /// given limits and strictness settings.
+    pub fn json_seq_with_options(input: &'l str, options: ParserOptions) -> Self {
+        StreamDeserializer {
+            source: Source::Sequence {
+                input,
+                options,
+                next: Position::START,
+            },
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// The byte offset where the last value ended, or 0 before the first.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl Iterator for StreamDeserializer<'_> {
+    type Item = Result<(JsonValue, usize), JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = match &mut self.source {
+            Source::Concatenated(parser) => {
+                let item = parser.parse_next().transpose();
+                // Nothing after an error can be trusted to start a value.
+                self.done = !matches!(item, Some(Ok(_)));
+                item
+            }
+            Source::Sequence {
+                input,
+                options,
+                next,
+            } => {
+                let item = next_record(input, options, next);
+                self.done = item.is_none();
+                item
+            }
+        };
+        if let Some(Ok((_, end))) = &item {
+            self.offset = *end;
+        }
+        item
+    }
+}
+
+/// Parses the record at `next`, skipping empty ones, and moves `next` past
+/// it.
+fn next_record(
+    input: &str,
+    options: &ParserOptions,
+    next: &mut Position,
+) -> Option<Result<(JsonValue, usize), JsonError>> {
+    loop {
+        let rest = &input[next.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        // Only text before the first separator can lack one.
+        let body = rest.strip_prefix(RS).unwrap_or(rest);
+        let base = match body.len() < rest.len() {
+            true => next.after(RS),
+            false => *next,
+        };
+        let record = &body[..body.find(RS).unwrap_or(body.len())];
+        *next = record
+            .chars()
+            .fold(Position::START, Position::after)
+            .rebase(base);
+
+        if record.trim_matches([' ', '\t', '\n', '\r']).is_empty() {
+            continue;
+        }
+        if !record.ends_with('\n') {
+            let span = Span::new(base, *next);
+            return Some(Err(JsonError::compose(ErrorKind::TruncatedRecord, span)));
+        }
+        let parsed = Parser::with_options(record, options.clone()).and_then(|mut parser| {
+            let parsed = parser.parse_next()?;
+            if parsed.is_some() {
+                parser.finish_root()?;
+            }
+            Ok(parsed)
+        });
+        match parsed {
+            Ok(Some((value, end))) => return Some(Ok((value, base.offset + end))),
+            // Only comments.
+            Ok(None) => continue,
+            Err(e) => return Some(Err(e.rebase(base))),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::errors::SerializeError;
+use crate::number::Number;
+use crate::parser::JsonValue;
+
+type Result<T> = std::result::Result<T, SerializeError>;
+
+/// Serializes a value as compact JSON text.
+pub fn to_string(value: &JsonValue) -> Result<String> {
+    let mut out = String::new();
+    to_fmt_writer(&mut out, value)?;
+    Ok(out)
+}
+
+/// Writes a value as compact JSON text to a `std::fmt::Write`.
+pub fn to_fmt_writer<W: std::fmt::Write>(writer: &mut W, value: &JsonValue) -> Result<()> {
+    write_value(writer, value)
+}
+
+/// Writes a value as compact JSON text to a `std::io::Write`.
+pub fn to_writer<W: std::io::Write>(writer: &mut W, value: &JsonValue) -> Result<()> {
+    let mut adapter = IoAdapter {
+        inner: writer,
+        error: None,
+    };
+    write_value(&mut adapter, value).map_err(|e| adapter.take_error(e))
+}
+
+fn write_value<W: std::fmt::Write>(w: &mut W, value: &JsonValue) -> Result<()> {
+    match value {
+        JsonValue::Null => w.write_str("null")?,
+        JsonValue::Boolean(true) => w.write_str("true")?,
+        JsonValue::Boolean(false) => w.write_str("false")?,
+        JsonValue::Number(n) => write_number(w, n)?,
+        JsonValue::String(s) => write_string(w, s, false)?,
+        JsonValue::Array(array) => {
+            w.write_char('[')?;
+            for (i, element) in array.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_value(w, element)?;
+            }
+            w.write_char(']')?;
+        }
+        JsonValue::Object(object) => {
+            w.write_char('{')?;
+            for (i, (key, member)) in object.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_string(w, key, false)?;
+                w.write_char(':')?;
+                write_value(w, member)?;
+            }
+            w.write_char('}')?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a number. Integers are written without a fraction; floats always
+/// keep one (or an exponent) so that they read back as floats.
+pub(crate) fn write_number<W: std::fmt::Write>(w: &mut W, n: &Number) -> Result<()> {
+    if let Some(f) = n.as_f64().filter(|f| !f.is_finite()) {
+        return Err(SerializeError::NonFiniteFloat(f));
+    }
+    write!(w, "{}", n)?;
+    Ok(())
+}
+
+/// Writes a string in double quotes, escaping what RFC 8259 requires.
+///
+/// With `ensure_ascii` every non-ASCII character is written as a `\uXXXX`
+/// escape, using a surrogate pair outside the Basic Multilingual Plane.
+pub(crate) fn write_string<W: std::fmt::Write>(
+    w: &mut W,
+    s: &str,
+    ensure_ascii: bool,
+) -> std::fmt::Result {
+    w.write_char('"')?;
+    let mut start = 0;
+    for (i, ch) in s.char_indices() {
+        let escape = match ch {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\u{0008}' => "\\b",
+            '\u{000C}' => "\\f",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{0000}'..='\u{001F}' => "",
+            _ if ensure_ascii && !ch.is_ascii() => "",
+            _ => continue,
+        };
+        w.write_str(&s[start..i])?;
+        start = i + ch.len_utf8();
+        if !escape.is_empty() {
+            w.write_str(escape)?;
+            continue;
+        }
+        let mut units = [0; 2];
+        for unit in ch.encode_utf16(&mut units) {
+            write!(w, "\\u{:04x}", unit)?;
+        }
+    }
+    w.write_str(&s[start..])?;
+    w.write_char('"')
+}
+
+/// Lets the serializer, which works on `std::fmt::Write`, write to a
+/// `std::io::Write`, holding on to the I/O error that `fmt::Error` cannot
+/// carry.
+pub(crate) struct IoAdapter<'a, W> {
+    pub(crate) inner: &'a mut W,
+    pub(crate) error: Option<std::io::Error>,
+}
+
+impl<W> IoAdapter<'_, W> {
+    /// Replaces a formatting error with the I/O error behind it, if any.
+    pub(crate) fn take_error(&mut self, e: SerializeError) -> SerializeError {
+        match (e, self.error.take()) {
+            (SerializeError::Fmt(_), Some(io)) => SerializeError::Io(io),
+            (e, _) => e,
+        }
+    }
+}
+
+impl<W: std::io::Write> std::fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            std::fmt::Error
+        })
+    }
+}
+
+impl std::fmt::Display for JsonValue {
+    /// Formats the value as compact JSON. Fails if it contains a non-finite
+    /// float.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_value(f, self).map_err(|_| std::fmt::Error)
+    }
+}
@@ -0,0 +1,487 @@
+use crate::errors::{ErrorKind, IoError, JsonError};
+use crate::lexer::{is_line_terminator, is_whitespace, Lexer};
+use crate::options::{Dialect, ParserOptions};
+use crate::position::{Position, Span};
+use crate::token::{CowToken, Token, TokenType};
+
+use std::collections::VecDeque;
+use std::io::Read;
+
+/// How many bytes to read at a time, at least.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// What [`ChunkLexer::next_token`] found.
+pub(crate) enum Lexed {
+    /// The next token, or `None` once the input is finished.
+    Token(Option<Result<CowToken<'static>, JsonError>>),
+    /// The buffered input ends inside a token, or before any token.
+    NeedMore,
+}
+
+/// Tokenizes input that arrives a piece at a time.
+///
+/// The buffered text is run through the ordinary [`Lexer`]. A token that
+/// ends right at the end of the buffer might go on in the next piece, so
+/// it is only handed out once more input has arrived or the input is
+/// finished. Positions are kept relative to the whole input, so tokens and
+/// errors come out exactly as if the input had been one string.
+///
+/// Whitespace and comments are always lexed as tokens, and dropped here
+/// unless asked for, so that a token left open at the end of the buffer
+/// is always the first one in it.
+#[derive(Debug)]
+pub(crate) struct ChunkLexer {
+    buffer: String,
+    /// How much of `buffer` has been turned into tokens.
+    consumed: usize,
+    /// The first bytes of a character that is split between two pieces.
+    partial: Vec<u8>,
+    /// Where `buffer[consumed..]` starts in the whole input.
+    base: Position,
+    /// The token at the start of `buffer[consumed..]`, if it runs to the
+    /// end of the buffer.
+    open: Option<Open>,
+    finished: bool,
+    dialect: Dialect,
+    trivia: bool,
+}
+
+impl ChunkLexer {
+    pub(crate) fn new(dialect: Dialect, trivia: bool) -> Self {
+        ChunkLexer {
+            buffer: String::new(),
+            consumed: 0,
+            partial: Vec::new(),
+            base: Position::START,
+            open: None,
+            finished: false,
+            dialect,
+            trivia,
+        }
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Where the unconsumed input starts.
+    pub(crate) fn position(&self) -> Position {
+        self.base
+    }
+
+    /// The number of bytes buffered but not yet turned into tokens.
+    pub(crate) fn buffered(&self) -> usize {
+        self.buffer.len() - self.consumed
+    }
+
+    /// Adds the next piece of the input. Fails if the input is not valid
+    /// UTF-8.
+    pub(crate) fn feed(&mut self, bytes: &[u8]) -> Result<(), JsonError> {
+        self.buffer.drain(..self.consumed);
+        self.consumed = 0;
+
+        let mut bytes = bytes;
+        let joined;
+        if !self.partial.is_empty() {
+            self.partial.extend_from_slice(bytes);
+            joined = std::mem::take(&mut self.partial);
+            bytes = &joined;
+        }
+        match std::str::from_utf8(bytes) {
+            Ok(text) => self.buffer.push_str(text),
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                // Checked by `from_utf8` above.
+                self.buffer
+                    .push_str(std::str::from_utf8(valid).expect("valid UTF-8"));
+                match e.error_len() {
+                    // A character cut off at the end; the rest comes next.
+                    None => self.partial = rest.to_vec(),
+                    Some(_) => return Err(self.invalid_utf8()),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks the end of the input. Fails if it ends in the middle of a
+    /// character.
+    pub(crate) fn finish(&mut self) -> Result<(), JsonError> {
+        self.finished = true;
+        match self.partial.is_empty() {
+            true => Ok(()),
+            false => Err(self.invalid_utf8()),
+        }
+    }
+
+    /// Where the buffered input ends.
+    pub(crate) fn end(&self) -> Position {
+        self.buffer[self.consumed..]
+            .chars()
+            .fold(Position::START, Position::after)
+            .rebase(self.base)
+    }
+
+    fn invalid_utf8(&self) -> JsonError {
+        JsonError::compose(
+            ErrorKind::Io(IoError::invalid_utf8()),
+            Span::empty(self.end()),
+        )
+    }
+
+    /// Produces the next token, if the buffered input is enough to tell
+    /// where it ends.
+    pub(crate) fn next_token(&mut self) -> Lexed {
+        loop {
+            match self.lex() {
+                Lexed::Token(Some(Ok(tok)))
+                    if !self.trivia
+                        && matches!(tok.token_type, TokenType::Whitespace | TokenType::Comment) => {
+                }
+                lexed => return lexed,
+            }
+        }
+    }
+
+    /// Does the work of `next_token`, with whitespace and comments.
+    fn lex(&mut self) -> Lexed {
+        let rest = &self.buffer[self.consumed..];
+        if rest.is_empty() {
+            return match self.finished {
+                true => Lexed::Token(None),
+                false => Lexed::NeedMore,
+            };
+        }
+        if let Some(open) = self.open.as_mut().filter(|_| !self.finished) {
+            if !open.may_end(rest, self.dialect) {
+                return Lexed::NeedMore;
+            }
+        }
+        let mut lexer = Lexer::new(rest)
+            .expect("input is not empty")
+            .with_dialect(self.dialect)
+            .with_trivia(true);
+        let tok = lexer.next_token();
+        let end = lexer.position();
+        if !self.finished && end.offset == rest.len() && !is_closed(&tok) {
+            self.open = Some(Open::new(rest, self.dialect));
+            return Lexed::NeedMore;
+        }
+
+        self.open = None;
+        let base = self.base;
+        self.consumed += end.offset;
+        self.base = end.rebase(base);
+        Lexed::Token(tok.map(|tok| match tok {
+            Ok(tok) => Ok(CowToken::rebased(tok, base)),
+            Err(e) => Err(e.rebase(base)),
+        }))
+    }
+}
+
+/// Whether `tok` is done whatever follows it: a string, or a punctuation
+/// token. Anything else might go on in the next piece of the input.
+fn is_closed(tok: &Option<Result<Token, JsonError>>) -> bool {
+    matches!(
+        tok,
+        Some(Ok(Token {
+            token_type: TokenType::Str
+                | TokenType::Lbrace
+                | TokenType::Rbrace
+                | TokenType::LSqBracket
+                | TokenType::RSqbracket
+                | TokenType::Colon
+                | TokenType::Comma,
+            ..
+        }))
+    )
+}
+
+/// A token that runs to the end of the buffered input, so that more input
+/// might make it longer.
+///
+/// Lexing it again from its start each time a piece arrives would take
+/// time quadratic in its length. Instead only the new input is checked for
+/// a character that might end the token, and the token is lexed again once
+/// one turns up, or once the buffered input has doubled in case the check
+/// missed something.
+#[derive(Debug)]
+struct Open {
+    kind: OpenKind,
+    /// How many bytes of the unconsumed input are known not to end the
+    /// token.
+    checked: usize,
+    /// Whether the last character checked was a backslash in a string, or
+    /// a `*` in a block comment.
+    pending: bool,
+    /// The number of unconsumed bytes at which to lex the token again
+    /// anyway.
+    relex_at: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum OpenKind {
+    /// A string, with its opening quote.
+    Str(char),
+    LineComment,
+    BlockComment,
+    Whitespace,
+    /// A number, a literal or an identifier.
+    Word,
+    /// A string with an error in it, which ends wherever the lexer gives up
+    /// on it.
+    Other,
+}
+
+impl Open {
+    /// Sizes up the token at the start of `rest`.
+    fn new(rest: &str, dialect: Dialect) -> Self {
+        let comments = dialect.allows_comments();
+        let kind = match rest.chars().next() {
+            Some('"') => OpenKind::Str('"'),
+            Some('\'') if dialect == Dialect::Json5 => OpenKind::Str('\''),
+            _ if comments && rest.starts_with("//") => OpenKind::LineComment,
+            _ if comments && rest.starts_with("/*") => OpenKind::BlockComment,
+            Some(ch) if is_whitespace(ch, dialect) => OpenKind::Whitespace,
+            _ => OpenKind::Word,
+        };
+        let mut open = Open {
+            kind,
+            checked: rest.len(),
+            pending: false,
+            relex_at: 2 * rest.len(),
+        };
+        match kind {
+            // Scan the string for its escapes, and for errors.
+            OpenKind::Str(_) => {
+                open.checked = 1;
+                if open.scan(rest, dialect) {
+                    open.kind = OpenKind::Other;
+                    open.checked = rest.len();
+                }
+            }
+            // The `*` of the opening `/*` does not start a `*/`.
+            OpenKind::BlockComment => open.pending = rest.len() > 2 && rest.ends_with('*'),
+            _ => {}
+        }
+        open
+    }
+
+    /// Whether the token might end now that `rest` is buffered.
+    fn may_end(&mut self, rest: &str, dialect: Dialect) -> bool {
+        self.scan(rest, dialect) || rest.len() >= self.relex_at
+    }
+
+    /// Checks the input after `checked` for a character that might end the
+    /// token, and stops at the first one.
+    fn scan(&mut self, rest: &str, dialect: Dialect) -> bool {
+        for (i, ch) in rest[self.checked..].char_indices() {
+            let ends = match self.kind {
+                OpenKind::Str(quote) => (ch == quote && !self.pending) || ch < ' ',
+                OpenKind::LineComment => is_line_terminator(ch),
+                OpenKind::BlockComment => ch == '/' && self.pending,
+                OpenKind::Whitespace => !is_whitespace(ch, dialect),
+                OpenKind::Word => !(ch.is_alphanumeric() || matches!(ch, '_' | '$' | '.')),
+                OpenKind::Other => false,
+            };
+            if ends {
+                self.checked += i;
+                return true;
+            }
+            self.pending = match self.kind {
+                OpenKind::Str(_) => ch == '\\' && !self.pending,
+                OpenKind::BlockComment => ch == '*',
+                _ => false,
+            };
+        }
+        self.checked = rest.len();
+        false
+    }
+}
+
+/// Tokenizes input from a `std::io::Read`, through an internal buffer.
+pub(crate) struct ReaderLexer<'r> {
+    reader: Box<dyn Read + 'r>,
+    lexer: ChunkLexer,
+    /// Bytes read so far.
+    read: usize,
+    max_input_size: Option<usize>,
+    scratch: Vec<u8>,
+}
+
+impl std::fmt::Debug for ReaderLexer<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReaderLexer")
+            .field("lexer", &self.lexer)
+            .field("read", &self.read)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'r> ReaderLexer<'r> {
+    /// Starts reading. Fails with `ErrorKind::EmptyInput` if there is
+    /// nothing to read, like [`Lexer::new`].
+    pub(crate) fn new(reader: impl Read + 'r, options: &ParserOptions) -> Result<Self, JsonError> {
+        let mut lexer = ReaderLexer {
+            reader: Box::new(reader),
+            lexer: ChunkLexer::new(options.dialect, options.attach_comments),
+            read: 0,
+            max_input_size: options.max_input_size,
+            scratch: Vec::new(),
+        };
+        lexer.fill()?;
+        if lexer.read == 0 {
+            return Err(JsonError::compose(
+                ErrorKind::EmptyInput,
+                Span::empty(Position::START),
+            ));
+        }
+        Ok(lexer)
+    }
+
+    pub(crate) fn position(&self) -> Position {
+        self.lexer.position()
+    }
+
+    pub(crate) fn next_token(&mut self) -> Option<Result<CowToken<'static>, JsonError>> {
+        loop {
+            match self.lexer.next_token() {
+                Lexed::Token(tok) => return tok,
+                Lexed::NeedMore => {
+                    if let Err(e) = self.fill() {
+                        // Give up on the rest of the input, so that recovery
+                        // does not keep trying to read it.
+                        let _ = self.lexer.finish();
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads the next piece of the input. Reads at least as much as is
+    /// already buffered, so that a long token is lexed a bounded number of
+    /// times.
+    fn fill(&mut self) -> Result<(), JsonError> {
+        let want = CHUNK_SIZE.max(self.lexer.buffered());
+        self.scratch.resize(want, 0);
+        let n = loop {
+            match self.reader.read(&mut self.scratch) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    return Err(JsonError::compose(
+                        ErrorKind::Io(e.into()),
+                        Span::empty(self.lexer.end()),
+                    ))
+                }
+            }
+        };
+        self.read += n;
+        if self.max_input_size.is_some_and(|max| self.read > max) {
+            return Err(JsonError::compose(
+                ErrorKind::InputTooLarge,
+                Span::empty(Position::START),
+            ));
+        }
+        match n {
+            0 => self.lexer.finish(),
+            n => self.lexer.feed(&self.scratch[..n]),
+        }
+    }
+}
+
+/// Tokenizes input pushed in by a [`PushParser`](crate::push::PushParser),
+/// lexing ahead of the parser so that it can tell whether the input so far
+/// is enough for its next step.
+#[derive(Debug)]
+pub(crate) struct PushLexer {
+    lexer: ChunkLexer,
+    tokens: VecDeque<Result<CowToken<'static>, JsonError>>,
+    /// How many of `tokens` are neither whitespace nor comments.
+    significant: usize,
+    /// Bytes fed so far.
+    fed: usize,
+    max_input_size: Option<usize>,
+}
+
+impl PushLexer {
+    pub(crate) fn new(options: &ParserOptions) -> Self {
+        PushLexer {
+            lexer: ChunkLexer::new(options.dialect, options.attach_comments),
+            tokens: VecDeque::new(),
+            significant: 0,
+            fed: 0,
+            max_input_size: options.max_input_size,
+        }
+    }
+
+    pub(crate) fn feed(&mut self, bytes: &[u8]) -> Result<(), JsonError> {
+        self.fed += bytes.len();
+        if self.max_input_size.is_some_and(|max| self.fed > max) {
+            return Err(JsonError::compose(
+                ErrorKind::InputTooLarge,
+                Span::empty(Position::START),
+            ));
+        }
+        self.lexer.feed(bytes)?;
+        self.lex();
+        Ok(())
+    }
+
+    /// Marks the end of the input. Fails with `ErrorKind::EmptyInput` if
+    /// nothing was fed, like [`Lexer::new`].
+    pub(crate) fn finish(&mut self) -> Result<(), JsonError> {
+        if self.fed == 0 {
+            return Err(JsonError::compose(
+                ErrorKind::EmptyInput,
+                Span::empty(Position::START),
+            ));
+        }
+        self.lexer.finish()?;
+        self.lex();
+        Ok(())
+    }
+
+    /// Whether the end of the input has been marked.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.lexer.is_finished()
+    }
+
+    /// The number of tokens ready, besides whitespace and comments.
+    pub(crate) fn significant(&self) -> usize {
+        self.significant
+    }
+
+    pub(crate) fn position(&self) -> Position {
+        self.lexer.position()
+    }
+
+    pub(crate) fn next_token(&mut self) -> Option<Result<CowToken<'static>, JsonError>> {
+        let tok = self.tokens.pop_front()?;
+        if is_significant(&tok) {
+            self.significant -= 1;
+        }
+        Some(tok)
+    }
+
+    /// Lexes as far as the buffered input allows.
+    fn lex(&mut self) {
+        while let Lexed::Token(Some(tok)) = self.lexer.next_token() {
+            if is_significant(&tok) {
+                self.significant += 1;
+            }
+            self.tokens.push_back(tok);
+        }
+    }
+}
+
+fn is_significant(tok: &Result<CowToken, JsonError>) -> bool {
+    !matches!(
+        tok,
+        Ok(CowToken {
+            token_type: TokenType::Whitespace | TokenType::Comment,
+            ..
+        })
+    )
+}
@@ -1,3 +1,7 @@
+use crate::position::{Position, Span};
+
+use std::borrow::Cow;
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 /// The JSON tokens.
 pub enum TokenType {
@@ -6,16 +10,71 @@ pub enum TokenType {
     LSqBracket,
     RSqbracket,
     Comma,
-    Str,
     Colon,
+    /// A string in double quotes.
+    Str,
+    /// A number, validated against the number grammar of the dialect.
+    Number,
+    True,
+    False,
+    Null,
+    /// A bare word that is not one of the keywords, such as an unquoted key.
+    Identifier,
+    /// A run of whitespace. Only produced when the lexer is asked to keep
+    /// trivia.
+    Whitespace,
+    /// A `//` or `/* */` comment. Only produced when the dialect allows
+    /// comments and the lexer is asked to keep trivia.
+    Comment,
+    /// A character that cannot start any token.
     InvalidChar(char),
-    Character(char),
-    Digit,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 /// Represents a distinct token in JSON.
 /// The token is identified by its `TokenType` and its string form.
-pub struct Token {
+pub struct Token<'a> {
     pub token_type: TokenType,
+    /// The text of the token exactly as it appears in the input, including
+    /// any quotes and escape sequences.
+    pub lexeme: &'a str,
+    /// Where the token appears in the input.
+    pub span: Span,
+    /// The contents of a `Str` token with the quotes removed and the escape
+    /// sequences decoded. `None` for every other token.
+    pub value: Option<String>,
+}
+
+/// A token that owns its lexeme when it cannot borrow it from the input, as
+/// when the input arrives a piece at a time.
+#[derive(Debug, Clone)]
+pub(crate) struct CowToken<'a> {
+    pub(crate) token_type: TokenType,
+    pub(crate) lexeme: Cow<'a, str>,
+    pub(crate) span: Span,
+    pub(crate) value: Option<String>,
+}
+
+impl<'a> From<Token<'a>> for CowToken<'a> {
+    fn from(tok: Token<'a>) -> Self {
+        CowToken {
+            token_type: tok.token_type,
+            lexeme: Cow::Borrowed(tok.lexeme),
+            span: tok.span,
+            value: tok.value,
+        }
+    }
+}
+
+impl CowToken<'static> {
+    /// Copies a token read from a piece of the input that starts at `base`,
+    /// moving its span to the same place in the whole input.
+    pub(crate) fn rebased(tok: Token<'_>, base: Position) -> Self {
+        CowToken {
+            token_type: tok.token_type,
+            lexeme: Cow::Owned(tok.lexeme.to_owned()),
+            span: tok.span.rebase(base),
+            value: tok.value,
+        }
+    }
 }
@@ -0,0 +1,6 @@
+use jimson::parser::{JsonValue, Parser};
+
+/// Parses `input`, which the test knows to be valid JSON.
+pub fn parse(input: &str) -> JsonValue {
+    Parser::new(input).unwrap().parse_root().unwrap()
+}
@@ -0,0 +1,179 @@
+use jimson::{
+    comment::PathSegment,
+    cst::{Document, Element, Node, NodeKind},
+    errors::{EditError, ErrorKind},
+    options::{Dialect, ParserOptions},
+    parser::JsonValue,
+};
+
+mod common;
+use common::parse;
+
+fn key(k: &str) -> PathSegment {
+    PathSegment::Key(k.into())
+}
+
+fn jsonc(text: &str) -> Document {
+    Document::parse_with_options(text, ParserOptions::new().dialect(Dialect::Jsonc)).unwrap()
+}
+
+/// Concatenates the text of every token under `node`.
+fn token_text(doc: &Document, node: &Node, out: &mut String) {
+    for child in node.children() {
+        match child {
+            Element::Node(node) => token_text(doc, node, out),
+            Element::Token(tok) => out.push_str(&doc.text()[tok.span.range()]),
+        }
+    }
+}
+
+const PACKAGE: &str = r#"
+// Hand formatted, keep it that way.
+{
+    "name":    "jimson",
+    "version": "0.1.0",   /* bumped by CI */
+    "keywords": [ "json", "parser",
+                  "no-deps" ],
+    "nested": {"a": 1}
+}
+"#;
+
+#[test]
+fn tree_is_lossless() {
+    let doc = jsonc(PACKAGE);
+    let mut text = String::new();
+    token_text(&doc, doc.root(), &mut text);
+    assert_eq!(text, PACKAGE);
+    assert_eq!(doc.to_string(), PACKAGE);
+
+    let root = doc.root().value().unwrap();
+    assert_eq!(root.kind(), NodeKind::Object);
+    let keys: Vec<_> = root.items().map(|m| m.key().unwrap()).collect();
+    assert_eq!(keys, ["name", "version", "keywords", "nested"]);
+    let keywords = doc.get(&[key("keywords")]).unwrap();
+    assert_eq!(keywords.kind(), NodeKind::Array);
+    assert_eq!(
+        doc.node_text(doc.get(&[key("keywords"), PathSegment::Index(2)]).unwrap()),
+        "\"no-deps\""
+    );
+    assert!(doc.get(&[key("keywords"), PathSegment::Index(3)]).is_none());
+    assert!(doc.get(&[key("name"), key("x")]).is_none());
+}
+
+#[test]
+fn set_touches_only_the_value() {
+    let mut doc = jsonc(PACKAGE);
+    doc.set(&[key("version")], &JsonValue::String("0.2.0".into()))
+        .unwrap();
+    doc.set(&[key("nested"), key("a")], &parse(r#"{"b": [true]}"#))
+        .unwrap();
+    assert_eq!(
+        doc.text(),
+        PACKAGE
+            .replace("0.1.0", "0.2.0")
+            .replace(r#"{"a": 1}"#, r#"{"a": {"b":[true]}}"#)
+    );
+    assert_eq!(
+        doc.set(&[key("missing")], &JsonValue::Null)
+            .unwrap_err()
+            .to_string(),
+        EditError::PathNotFound.to_string()
+    );
+}
+
+#[test]
+fn insert_member_copies_the_formatting() {
+    let mut doc = Document::parse("{\n  \"a\": 1,\n  \"b\" : 2\n}").unwrap();
+    doc.insert_member(&[], "c", &parse("[3]")).unwrap();
+    assert_eq!(doc.text(), "{\n  \"a\": 1,\n  \"b\" : 2,\n  \"c\" : [3]\n}");
+
+    let mut doc = Document::parse(r#"{"a": {}, "b": {"x": 1}}"#).unwrap();
+    doc.insert_member(&[key("a")], "k", &JsonValue::Null)
+        .unwrap();
+    doc.insert_member(&[key("b")], "y", &JsonValue::Boolean(false))
+        .unwrap();
+    assert_eq!(
+        doc.text(),
+        r#"{"a": {"k": null}, "b": {"x": 1, "y": false}}"#
+    );
+
+    // A trailing comma stays trailing.
+    let mut doc = jsonc("{\r\n\t\"a\": 1,\r\n}");
+    doc.insert_member(&[], "b", &parse("2")).unwrap();
+    assert_eq!(doc.text(), "{\r\n\t\"a\": 1,\r\n\t\"b\": 2,\r\n}");
+
+    assert!(matches!(
+        doc.insert_member(&[], "a", &JsonValue::Null),
+        Err(EditError::KeyExists)
+    ));
+    assert!(matches!(
+        doc.insert_member(&[key("a")], "z", &JsonValue::Null),
+        Err(EditError::NotAnObject)
+    ));
+}
+
+#[test]
+fn remove_elements_and_members() {
+    let mut doc = Document::parse("[1, 2, 3]").unwrap();
+    doc.remove_element(&[], 0).unwrap();
+    assert_eq!(doc.text(), "[2, 3]");
+    doc.remove_element(&[], 1).unwrap();
+    assert_eq!(doc.text(), "[2]");
+    doc.remove_element(&[], 0).unwrap();
+    assert_eq!(doc.text(), "[]");
+    assert!(matches!(
+        doc.remove_element(&[], 0),
+        Err(EditError::PathNotFound)
+    ));
+
+    let mut doc = jsonc("[\n  1, // one\n  // about two\n  2,\n  3 // three\n]");
+    doc.remove_element(&[], 0).unwrap();
+    assert_eq!(doc.text(), "[\n  // about two\n  2,\n  3 // three\n]");
+    doc.remove_element(&[], 1).unwrap();
+    assert_eq!(doc.text(), "[\n  // about two\n  2\n]");
+
+    let mut doc = jsonc(PACKAGE);
+    doc.remove_member(&[], "version").unwrap();
+    doc.remove_element(&[key("keywords")], 1).unwrap();
+    doc.remove_member(&[], "nested").unwrap();
+    assert_eq!(
+        doc.text(),
+        r#"
+// Hand formatted, keep it that way.
+{
+    "name":    "jimson",
+    "keywords": [ "json", "no-deps" ]
+}
+"#
+    );
+    assert!(matches!(
+        doc.remove_member(&[key("keywords")], "a"),
+        Err(EditError::NotAnObject)
+    ));
+}
+
+#[test]
+fn invalid_input_is_rejected() {
+    assert!(Document::parse("[1,]").is_err());
+    assert!(Document::parse("// hi\n[1]").is_err());
+    assert!(jsonc("// hi\n[1,]").to_value().unwrap() == parse("[1]"));
+}
+
+#[test]
+fn edits_that_break_a_limit_are_rejected() {
+    let options = ParserOptions::new().max_depth(3).max_string_length(Some(4));
+    let text = "{\"a\": [1]}";
+    let mut doc = Document::parse_with_options(text, options).unwrap();
+
+    let deep = parse("[[[1]]]");
+    let err = doc.set(&[key("a")], &deep).unwrap_err();
+    assert!(matches!(&err, EditError::Limit(e) if e.kind == ErrorKind::NestingTooDeep));
+    let err = doc.insert_member(&[], "long key", &deep).unwrap_err();
+    assert!(matches!(&err, EditError::Limit(e) if e.kind == ErrorKind::StringTooLong));
+
+    assert_eq!(doc.text(), text);
+    assert_eq!(doc.root().span().end.offset, text.len());
+    assert!(doc.to_value().unwrap() == parse(text));
+    doc.set(&[key("a")], &parse("[2]")).unwrap();
+    assert_eq!(doc.text(), "{\"a\": [2]}");
+}
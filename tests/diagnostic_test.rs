@@ -0,0 +1,64 @@
+use jimson::{diagnostic::Diagnostic, errors::ErrorKind, parser::Parser, token::TokenType};
+
+fn render(source: &str, color: bool) -> String {
+    let error = Parser::new(source).unwrap().parse_root().unwrap_err();
+    Diagnostic::new(&error, source)
+        .with_color(color)
+        .to_string()
+}
+
+#[test]
+fn render_trailing_comma() {
+    let source = "{\n  \"a\": 1,\n    \"b\": [1, 2,],\n  \"c\": 3\n}";
+    let expected = r#"error: trailing comma
+ --> line 3, column 15
+  |
+3 |     "b": [1, 2,],
+  |               ^ remove this trailing comma
+"#;
+    assert_eq!(render(source, false), expected);
+}
+
+#[test]
+fn render_underlines_the_span() {
+    let source = "[\"bad \\uZZZZ\"]";
+    let expected = r#"error: expected four hexadecimal digits after \u
+ --> line 1, column 7
+  |
+1 | ["bad \uZZZZ"]
+  |       ^^^ write exactly four hex digits, as in `\u00e9`
+"#;
+    assert_eq!(render(source, false), expected);
+}
+
+#[test]
+fn render_keeps_tabs_aligned() {
+    let source = "[\t'x']";
+    let rendered = render(source, false);
+    assert!(rendered.contains("1 | [\t'x']\n  |  \t^ use double quotes\n"));
+}
+
+#[test]
+fn render_with_color() {
+    let rendered = render("[1,]", true);
+    assert!(rendered.starts_with("\x1b[1;31merror\x1b[0m"));
+    assert!(rendered.contains("\x1b[1;31m^\x1b[0m"));
+    assert!(!render("[1,]", false).contains('\x1b'));
+}
+
+#[test]
+fn hints_for_error_kinds() {
+    assert_eq!(
+        ErrorKind::TrailingComma.hint(),
+        "remove this trailing comma"
+    );
+    assert_eq!(ErrorKind::SingleQuote.hint(), "use double quotes");
+    assert_eq!(
+        ErrorKind::StringTooLong.hint(),
+        "raise `max_string_length` or shorten the string"
+    );
+    assert_eq!(
+        ErrorKind::UnclosedDelimiter(TokenType::Comma).hint(),
+        "close every `{` with `}` and every `[` with `]`"
+    );
+}
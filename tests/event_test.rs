@@ -0,0 +1,107 @@
+use jimson::{
+    errors::ErrorKind,
+    event::EventKind,
+    options::ParserOptions,
+    parser::{JsonValue, Parser},
+};
+
+/// Collects the events of `input`, each with the offsets it spans.
+fn events(input: &str) -> Vec<(String, usize, usize)> {
+    let mut parser = Parser::new(input).unwrap();
+    let mut events = Vec::new();
+    while let Some(event) = parser.next_event().unwrap() {
+        let kind = match event.kind {
+            EventKind::Number(n) => format!("Number({n})"),
+            kind => format!("{kind:?}"),
+        };
+        events.push((kind, event.span.start.offset, event.span.end.offset));
+    }
+    events
+}
+
+#[test]
+fn events_in_document_order_with_spans() {
+    let events = events(r#"{"a": [1, "x"], "b": null}"#);
+    let expected = [
+        ("StartObject", 0, 1),
+        ("Key(\"a\")", 1, 4),
+        ("StartArray", 6, 7),
+        ("Number(1)", 7, 8),
+        ("String(\"x\")", 10, 13),
+        ("EndArray", 13, 14),
+        ("Key(\"b\")", 16, 19),
+        ("Null", 21, 25),
+        ("EndObject", 25, 26),
+    ];
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|(kind, start, end)| (kind.to_string(), *start, *end))
+        .collect();
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn skip_value_skips_members_and_elements() {
+    let mut parser = Parser::new(r#"{"skip": {"deep": [1, [2]]}, "keep": true}"#).unwrap();
+    assert_eq!(
+        parser.next_event().unwrap().unwrap().kind,
+        EventKind::StartObject
+    );
+    // Before a key, the whole member goes.
+    parser.skip_value().unwrap();
+    assert_eq!(
+        parser.next_event().unwrap().unwrap().kind,
+        EventKind::Key("keep")
+    );
+    parser.skip_value().unwrap();
+    // At the end of the object, nothing is skipped.
+    parser.skip_value().unwrap();
+    assert_eq!(
+        parser.next_event().unwrap().unwrap().kind,
+        EventKind::EndObject
+    );
+    assert_eq!(parser.next_event().unwrap(), None);
+}
+
+#[test]
+fn events_report_errors_and_trailing_content() {
+    let mut parser = Parser::new("[1 2]").unwrap();
+    parser.next_event().unwrap();
+    parser.next_event().unwrap();
+    let e = parser.next_event().unwrap_err();
+    assert_eq!(e.kind, ErrorKind::InvalidSyntax);
+    assert_eq!(parser.next_event().unwrap(), None);
+
+    let mut parser = Parser::new("1 2").unwrap();
+    parser.next_event().unwrap();
+    let e = parser.next_event().unwrap_err();
+    assert_eq!(e.kind, ErrorKind::InvalidSyntax);
+}
+
+#[test]
+fn nesting_is_not_bound_to_the_call_stack() {
+    let depth = 100_000;
+    let input = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    let options = ParserOptions::new().max_depth(depth);
+    let mut parser = Parser::with_options(&input, options).unwrap();
+    let mut count = 0;
+    while parser.next_event().unwrap().is_some() {
+        count += 1;
+    }
+    assert_eq!(count, 2 * depth);
+
+    let depth = 5_000;
+    let input = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    let options = ParserOptions::new().max_depth(depth);
+    let mut value = Parser::with_options(&input, options)
+        .unwrap()
+        .parse_root()
+        .unwrap();
+    for _ in 1..depth {
+        value = match value {
+            JsonValue::Array(mut items) => items.pop().unwrap(),
+            _ => panic!("expected an array"),
+        };
+    }
+    assert_eq!(value, JsonValue::Array(Vec::new()));
+}
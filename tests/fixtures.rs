@@ -2,7 +2,9 @@ use std::{collections::HashMap, fs, path::Path};
 
 use jimson::{
     errors::{ErrorKind, JsonError},
+    options::ParserOptions,
     parser::{JsonValue, Parser},
+    push::PushParser,
 };
 
 fn collect_test_files(path: &Path, tf: &mut Vec<std::path::PathBuf>) {
@@ -17,13 +19,8 @@ fn collect_test_files(path: &Path, tf: &mut Vec<std::path::PathBuf>) {
     tf.sort();
 }
 
-#[test]
-fn prepare_test_data_invalids() {
-    let mut test_files = Vec::new();
-    let p = Path::new("tests/fixtures/invalid");
-    collect_test_files(p, &mut test_files);
-
-    let expected: HashMap<&str, ErrorKind> = HashMap::from([
+fn expected_invalid_kinds() -> HashMap<&'static str, ErrorKind> {
+    HashMap::from([
         (
             "fail1.json",
             ErrorKind::UnclosedDelimiter(jimson::token::TokenType::RSqbracket),
@@ -39,18 +36,148 @@ fn prepare_test_data_invalids() {
         ("fail10.json", ErrorKind::InvalidSyntax),
         ("fail11.json", ErrorKind::InvalidSyntax),
         ("fail12.json", ErrorKind::NumberWithLeadingZero),
-        ("fail13.json", ErrorKind::NumberWithLeadingZero),
+        ("fail13.json", ErrorKind::InvalidNumber),
         ("fail14.json", ErrorKind::InvalidBackslashEscape),
         ("fail15.json", ErrorKind::InvalidSyntax),
         ("fail16.json", ErrorKind::InvalidBackslashEscape),
         ("fail17.json", ErrorKind::NestingTooDeep),
         ("fail18.json", ErrorKind::MissingColon),
-    ]);
+        ("fail19.json", ErrorKind::NumberWithLeadingZero),
+        ("fail20.json", ErrorKind::InvalidBackslashEscape),
+    ])
+}
+
+/// What recovery makes of each invalid fixture: the value, as JSON text,
+/// and the kind of every error reported. A `null` that is not in the input
+/// is a placeholder for a value that could not be parsed.
+fn expected_recoveries() -> HashMap<&'static str, (&'static str, Vec<ErrorKind>)> {
+    use jimson::token::TokenType;
+
+    HashMap::from([
+        (
+            "fail1.json",
+            (
+                r#"["Unclosed array"]"#,
+                vec![ErrorKind::UnclosedDelimiter(TokenType::RSqbracket)],
+            ),
+        ),
+        ("fail2.json", ("{}", vec![ErrorKind::ObjectKeyNotString])),
+        (
+            "fail3.json",
+            (r#"["extra comma"]"#, vec![ErrorKind::TrailingComma]),
+        ),
+        (
+            "fail4.json",
+            (r#"["double extra comma"]"#, vec![ErrorKind::TrailingComma]),
+        ),
+        (
+            "fail5.json",
+            (r#"["<-- missing value"]"#, vec![ErrorKind::MissingValue]),
+        ),
+        (
+            "fail6.json",
+            (
+                r#"["Comma after the close"]"#,
+                vec![ErrorKind::InvalidSyntax],
+            ),
+        ),
+        (
+            "fail7.json",
+            (r#"["Extra close"]"#, vec![ErrorKind::InvalidSyntax]),
+        ),
+        (
+            "fail8.json",
+            (r#"{"Extra comma": true}"#, vec![ErrorKind::TrailingComma]),
+        ),
+        (
+            "fail9.json",
+            (
+                r#"{"Extra value after close": true}"#,
+                vec![ErrorKind::InvalidSyntax],
+            ),
+        ),
+        (
+            "fail10.json",
+            (
+                r#"{"Illegal expression": 1}"#,
+                vec![ErrorKind::InvalidSyntax],
+            ),
+        ),
+        (
+            "fail11.json",
+            (
+                r#"{"Illegal invocation": null}"#,
+                vec![ErrorKind::InvalidSyntax],
+            ),
+        ),
+        (
+            "fail12.json",
+            (
+                r#"{"Numbers cannot have leading zeroes": null}"#,
+                vec![ErrorKind::NumberWithLeadingZero],
+            ),
+        ),
+        (
+            "fail13.json",
+            (
+                r#"{"Numbers cannot be hex": null}"#,
+                vec![ErrorKind::InvalidNumber],
+            ),
+        ),
+        (
+            "fail14.json",
+            ("[null]", vec![ErrorKind::InvalidBackslashEscape]),
+        ),
+        ("fail15.json", ("[null]", vec![ErrorKind::InvalidSyntax])),
+        (
+            "fail16.json",
+            ("[null]", vec![ErrorKind::InvalidBackslashEscape]),
+        ),
+        // Nesting too deeply gives up on the whole document.
+        ("fail17.json", ("null", vec![ErrorKind::NestingTooDeep])),
+        (
+            "fail18.json",
+            (r#"{"Missing colon": null}"#, vec![ErrorKind::MissingColon]),
+        ),
+        (
+            "fail19.json",
+            (
+                r#"{"a": null, "b": null, "c": null, "d": [1, 2]}"#,
+                vec![
+                    ErrorKind::NumberWithLeadingZero,
+                    ErrorKind::MissingColon,
+                    ErrorKind::InvalidSyntax,
+                    ErrorKind::TrailingComma,
+                ],
+            ),
+        ),
+        (
+            "fail20.json",
+            (
+                r#"[1, null, {"x": null}, [true], 2]"#,
+                vec![
+                    ErrorKind::InvalidBackslashEscape,
+                    ErrorKind::InvalidSyntax,
+                    ErrorKind::InvalidSyntax,
+                    ErrorKind::UnclosedDelimiter(TokenType::RSqbracket),
+                ],
+            ),
+        ),
+    ])
+}
+
+#[test]
+fn prepare_test_data_invalids() {
+    let mut test_files = Vec::new();
+    let p = Path::new("tests/fixtures/invalid");
+    collect_test_files(p, &mut test_files);
+
+    let expected = expected_invalid_kinds();
 
     for path in test_files.iter() {
         let fname = path.file_name().unwrap().to_str().unwrap();
-        let test_data = fs::read_to_string(&path)
-            .unwrap_or_else(|e| panic!("failed reading {:?}: {}", path, e));
+        let test_data =
+            fs::read_to_string(path).unwrap_or_else(|e| panic!("failed reading {:?}: {}", path, e));
         let parser = parse_test_json(test_data);
         assert!(&parser.unwrap_err().kind == expected.get(fname).unwrap());
     }
@@ -60,3 +187,130 @@ fn parse_test_json(j: String) -> Result<JsonValue, JsonError> {
     let mut parser = Parser::new(&j).unwrap();
     parser.parse_root()
 }
+
+#[test]
+fn round_trip_valids() {
+    let mut test_files = Vec::new();
+    collect_test_files(Path::new("tests/fixtures/valid"), &mut test_files);
+    assert!(!test_files.is_empty());
+
+    // pass2.json nests 19 levels deep, past the default limit.
+    let parse = |text: &str| {
+        let options = ParserOptions::new().max_depth(19);
+        Parser::with_options(text, options)?.parse_root()
+    };
+    for path in test_files.iter() {
+        let test_data =
+            fs::read_to_string(path).unwrap_or_else(|e| panic!("failed reading {:?}: {}", path, e));
+        let parsed =
+            parse(&test_data).unwrap_or_else(|e| panic!("failed parsing {:?}: {}", path, e));
+        let serialized = jimson::serializer::to_string(&parsed).unwrap();
+        let reparsed =
+            parse(&serialized).unwrap_or_else(|e| panic!("failed reparsing {:?}: {}", path, e));
+        assert_eq!(parsed, reparsed, "{:?}", path);
+    }
+}
+
+#[test]
+fn recover_from_invalids() {
+    let mut test_files = Vec::new();
+    collect_test_files(Path::new("tests/fixtures/invalid"), &mut test_files);
+    let expected = expected_recoveries();
+    assert_eq!(test_files.len(), expected.len());
+
+    for path in test_files.iter() {
+        let fname = path.file_name().unwrap().to_str().unwrap();
+        let test_data =
+            fs::read_to_string(path).unwrap_or_else(|e| panic!("failed reading {:?}: {}", path, e));
+        let strict = parse_test_json(test_data.clone()).unwrap_err();
+        let (value, errors) = Parser::new(&test_data).unwrap().parse_root_with_recovery();
+        let (recovered, kinds) = expected.get(fname).unwrap();
+        assert_eq!(
+            errors.iter().map(|e| e.kind.clone()).collect::<Vec<_>>(),
+            *kinds,
+            "{}",
+            fname
+        );
+        assert_eq!(
+            value,
+            parse_test_json(recovered.to_string()).unwrap(),
+            "{}",
+            fname
+        );
+        assert_eq!(errors[0].span, strict.span, "{}", fname);
+    }
+}
+
+/// Hands out its input `step` bytes at a time.
+struct Trickle<'a> {
+    input: &'a [u8],
+    step: usize,
+}
+
+impl std::io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.step.min(buf.len()).min(self.input.len());
+        buf[..n].copy_from_slice(&self.input[..n]);
+        self.input = &self.input[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn read_fixtures_in_pieces() {
+    let mut test_files = Vec::new();
+    collect_test_files(Path::new("tests/fixtures/valid"), &mut test_files);
+    collect_test_files(Path::new("tests/fixtures/invalid"), &mut test_files);
+
+    let summary = |result: Result<JsonValue, JsonError>| result.map_err(|e| (e.kind, e.span));
+    for path in test_files.iter() {
+        let test_data =
+            fs::read_to_string(path).unwrap_or_else(|e| panic!("failed reading {:?}: {}", path, e));
+        let from_str = summary(parse_test_json(test_data.clone()));
+        let (recovered, errors) = Parser::new(&test_data).unwrap().parse_root_with_recovery();
+        for step in [1, 3, 1 << 20] {
+            let reader = || Trickle {
+                input: test_data.as_bytes(),
+                step,
+            };
+            let mut parser = Parser::from_reader(reader()).unwrap();
+            assert_eq!(summary(parser.parse_root()), from_str, "{:?}", path);
+
+            let (value, errs) = Parser::from_reader(reader())
+                .unwrap()
+                .parse_root_with_recovery();
+            assert_eq!(value, recovered, "{:?}", path);
+            let spans = |errs: &[JsonError]| errs.iter().map(|e| e.span).collect::<Vec<_>>();
+            assert_eq!(spans(&errs), spans(&errors), "{:?}", path);
+        }
+    }
+}
+
+#[test]
+fn push_fixtures_byte_by_byte() {
+    let mut test_files = Vec::new();
+    collect_test_files(Path::new("tests/fixtures/valid"), &mut test_files);
+    collect_test_files(Path::new("tests/fixtures/invalid"), &mut test_files);
+
+    for path in test_files.iter() {
+        let test_data =
+            fs::read_to_string(path).unwrap_or_else(|e| panic!("failed reading {:?}: {}", path, e));
+        let from_str = parse_test_json(test_data.clone()).map_err(|e| (e.kind, e.span));
+
+        let mut parser = PushParser::new();
+        let mut pushed = Ok(None);
+        for byte in test_data.as_bytes() {
+            parser.feed(&[*byte]).unwrap();
+            pushed = parser.value();
+            if !matches!(pushed, Ok(None)) {
+                break;
+            }
+        }
+        if let Ok(None) = pushed {
+            parser.finish().unwrap();
+            pushed = parser.value();
+        }
+        let pushed = pushed.map(Option::unwrap).map_err(|e| (e.kind, e.span));
+        assert_eq!(pushed, from_str, "{:?}", path);
+    }
+}
@@ -0,0 +1,358 @@
+use jimson::{
+    errors::{EvalError, FilterErrorKind},
+    jq::Filter,
+    parser::{JsonValue, Parser},
+    serializer::to_string,
+};
+
+/// Runs `filter` on `input` and returns each output as compact JSON.
+fn jq(filter: &str, input: &str) -> Vec<String> {
+    let input = Parser::new(input).unwrap().parse_root().unwrap();
+    let outputs = Filter::parse(filter).unwrap().run(&input).unwrap();
+    outputs
+        .iter()
+        .map(|value| to_string(value).unwrap())
+        .collect()
+}
+
+fn eval_error(filter: &str, input: &str) -> EvalError {
+    let input = Parser::new(input).unwrap().parse_root().unwrap();
+    Filter::parse(filter).unwrap().run(&input).unwrap_err()
+}
+
+fn parse_error(filter: &str) -> (FilterErrorKind, usize) {
+    let e = Filter::parse(filter).unwrap_err();
+    (e.kind, e.offset)
+}
+
+#[test]
+fn paths_pipes_and_construction() {
+    let input = r#"{"a": {"b": [1, 2, 3]}, "c": "x", "d k": null}"#;
+    assert_eq!(jq(".a.b[1]", input), ["2"]);
+    assert_eq!(
+        jq(".a.b[-1], .[\"c\"], .\"d k\"", input),
+        ["3", "\"x\"", "null"]
+    );
+    assert_eq!(jq(".a.b[]", input), ["1", "2", "3"]);
+    assert_eq!(
+        jq(".a.b[1:], .c[:0], .missing.deep", input),
+        ["[2,3]", "\"\"", "null"]
+    );
+    assert_eq!(jq(".a | .b | length", input), ["3"]);
+    assert_eq!(jq("[.a.b[] * 10]", input), ["[10,20,30]"]);
+    assert_eq!(
+        jq("{c, \"n\": .a.b[0], (.c): true}", input),
+        [r#"{"c":"x","n":1,"x":true}"#]
+    );
+}
+
+#[test]
+fn object_construction() {
+    let input = r#"{"user": "ann", "titles": ["a", "b"]}"#;
+    assert_eq!(
+        jq("{user, title: .titles[]}", input),
+        [
+            r#"{"user":"ann","title":"a"}"#,
+            r#"{"user":"ann","title":"b"}"#
+        ]
+    );
+    assert_eq!(
+        jq(
+            r#".user as $u | {$u, (.user): 1, "x": .titles | length}"#,
+            input
+        ),
+        [r#"{"u":"ann","ann":1,"x":2}"#]
+    );
+    assert_eq!(jq("{}", input), ["{}"]);
+}
+
+#[test]
+fn select_map_and_builtins() {
+    let input = r#"[{"n": "b", "v": 3}, {"n": "a", "v": 1}, {"n": "c", "v": null}]"#;
+    assert_eq!(jq("map(select(.v != null) | .n)", input), [r#"["b","a"]"#]);
+    assert_eq!(jq("map(.v) | add", input), ["4"]);
+    assert_eq!(
+        jq("sort_by(.n) | map(.n) | join(\",\")", input),
+        ["\"a,b,c\""]
+    );
+    assert_eq!(
+        jq(".[0] | keys, to_entries", input),
+        [
+            r#"["n","v"]"#,
+            r#"[{"key":"n","value":"b"},{"key":"v","value":3}]"#,
+        ]
+    );
+    assert_eq!(
+        jq(".[0] | with_entries({key: (.key + \"!\"), value})", input),
+        [r#"{"n!":"b","v!":3}"#]
+    );
+    assert_eq!(
+        jq(
+            r#"[{"name": "a", "v": 1}, {"k": 2, "value": true}] | from_entries"#,
+            "null"
+        ),
+        [r#"{"a":1,"2":true}"#]
+    );
+    assert_eq!(
+        jq("to_entries", "[10, 20]"),
+        [r#"[{"key":0,"value":10},{"key":1,"value":20}]"#]
+    );
+    assert_eq!(
+        jq(
+            r#"[{"key": false, "value": 1}, {"key": null, "k": "b"}] | from_entries"#,
+            "null"
+        ),
+        [r#"{"false":1,"b":null}"#]
+    );
+    assert_eq!(
+        jq("[.[] | .v | type]", input),
+        [r#"["number","number","null"]"#]
+    );
+    assert_eq!(
+        jq("[range(3)], [range(2; 4)]", "null"),
+        ["[0,1,2]", "[2,3]"]
+    );
+    assert_eq!(jq("[..]", "[[1]]"), ["[[[1]],[1],1]"]);
+}
+
+#[test]
+fn arithmetic_and_comparison() {
+    assert_eq!(
+        jq("1 + 2 * 3, 7 / 2, 7 % 3, -(1 - 3)", "null"),
+        ["7", "3.5", "1", "2"]
+    );
+    assert_eq!(
+        jq(
+            r#""a" + "b", [1] + [2], {"a": 1} + {"b": 2}, null + 1"#,
+            "null"
+        ),
+        [r#""ab""#, "[1,2]", r#"{"a":1,"b":2}"#, "1"]
+    );
+    assert_eq!(
+        jq(r#"[1, 2, 1, 3] - [1], "a,b" / ",""#, "null"),
+        ["[2,3]", r#"["a","b"]"#]
+    );
+    assert_eq!(
+        jq(r#"{"a": {"b": 1, "c": 2}} * {"a": {"b": 3}}"#, "null"),
+        [r#"{"a":{"b":3,"c":2}}"#]
+    );
+    assert_eq!(
+        jq("[null, true, false, 1, \"a\", [], {}] | sort", "null"),
+        [r#"[null,false,true,1,"a",[],{}]"#]
+    );
+    assert_eq!(
+        jq("1 == 1.0, [1, 2] < [1, 3], \"b\" > \"a\"", "null"),
+        ["true"; 3]
+    );
+    assert_eq!(
+        jq(
+            ".[0] == 0, .[0] != 0, .[0] < 0, ([.[0], 0] | sort), . - [0]",
+            "[-0.0]"
+        ),
+        ["true", "false", "false", "[-0.0,0]", "[]"]
+    );
+    assert_eq!(jq("(1, 2) + (10, 20)", "null"), ["11", "12", "21", "22"]);
+    assert_eq!(
+        jq("null and error, 1 or error, (false, 1) and true", "null"),
+        ["false", "true", "false", "true"]
+    );
+    assert_eq!(
+        jq(".a // \"default\", (false, 2, null, 3) // 4", "{}"),
+        [r#""default""#, "2", "3"]
+    );
+    assert_eq!(
+        jq("if . > 2 then \"big\" elif . > 1 then \"mid\" end", "2"),
+        [r#""mid""#]
+    );
+    assert_eq!(
+        jq("if . > 2 then \"big\" elif . > 1 then \"mid\" end", "1"),
+        ["1"]
+    );
+}
+
+#[test]
+fn reduce_and_definitions() {
+    assert_eq!(jq("reduce .[] as $x (0; . + $x)", "[1, 2, 3, 4]"), ["10"]);
+    assert_eq!(
+        jq(
+            "def fact: if . <= 1 then 1 else . * (. - 1 | fact) end; map(fact)",
+            "[1, 5, 10]"
+        ),
+        ["[1,120,3628800]"]
+    );
+    assert_eq!(
+        jq(
+            "def twice(f): f | f; def addn($n): . + $n; twice(addn(3))",
+            "1"
+        ),
+        ["7"]
+    );
+    assert_eq!(jq("def f(g): [g, g]; 2 as $x | f(. + $x)", "1"), ["[3,3]"]);
+    // Definitions shadow the built-in ones and are scoped to what follows.
+    assert_eq!(jq("(def length: 42; length), length", "[1]"), ["42", "1"]);
+    assert_eq!(eval_error("def f: f; f", "null"), EvalError::TooDeep);
+    // Running out of depth is not an error for `try` or `//` to hide.
+    assert_eq!(
+        eval_error("def f: try [f] catch 0; f", "null"),
+        EvalError::TooDeep
+    );
+    assert_eq!(eval_error("def f: f // 1; f", "null"), EvalError::TooDeep);
+}
+
+#[test]
+fn long_chains_and_deep_inputs() {
+    // `..` walks values of any depth without nesting the run.
+    let deep = (0..1000).fold(JsonValue::Null, |inner, _| JsonValue::Array(vec![inner]));
+    let filter = Filter::parse("[..] | length").unwrap();
+    assert_eq!(to_string(&filter.run(&deep).unwrap()[0]).unwrap(), "1001");
+    let filter = Filter::parse("[recurse] | length").unwrap();
+    assert_eq!(to_string(&filter.run(&deep).unwrap()[0]).unwrap(), "1001");
+    assert_eq!(
+        jq("[recurse(if . < 3 then . + 1 else error end)?]", "0"),
+        ["[0,1,2,3]"]
+    );
+
+    // Long paths, pipes and chains of operators run flat.
+    assert_eq!(jq(&".a".repeat(2000), "null"), ["null"]);
+    assert_eq!(jq(&format!(".{}", " | .a".repeat(2000)), "null"), ["null"]);
+    assert_eq!(
+        jq(&format!("[1{}] | length", ", 1".repeat(100_000)), "null"),
+        ["100001"]
+    );
+    assert_eq!(
+        jq(&format!("0{}", " + 1".repeat(100_000)), "null"),
+        ["100000"]
+    );
+    assert_eq!(
+        jq(&format!(".{}", " | .".repeat(100_000)), "null"),
+        ["null"]
+    );
+    assert_eq!(
+        jq(&format!("true{}", " and true".repeat(100_000)), "null"),
+        ["true"]
+    );
+    assert_eq!(
+        jq(&format!("false{}", " or false".repeat(100_000)), "null"),
+        ["false"]
+    );
+    assert_eq!(
+        jq(&format!("[(0, 1){}]", " + 1".repeat(100)), "null"),
+        ["[100,101]"]
+    );
+    assert_eq!(
+        jq(
+            &format!("[(true, false){}]", " and true".repeat(100)),
+            "null"
+        ),
+        ["[true,false]"]
+    );
+    let object = (0..1000)
+        .map(|i| format!("a{i}: {i}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    assert_eq!(jq(&format!("{{{object}}} | length"), "null"), ["1000"]);
+
+    // Recursion in a `def` is limited by `max_depth`.
+    let count = "def f: if . < 200 then . + 1 | f else . end; f";
+    assert_eq!(jq(count, "0"), ["200"]);
+    let filter = Filter::parse(count).unwrap().max_depth(100);
+    assert_eq!(
+        filter.run(&JsonValue::Null).unwrap_err(),
+        EvalError::TooDeep
+    );
+    assert_eq!(
+        jq(&format!("null{}", " // null".repeat(100_000)), "null"),
+        ["null"]
+    );
+}
+
+#[test]
+fn errors_and_try() {
+    assert_eq!(
+        eval_error(".[]", "3"),
+        EvalError::Invalid("Cannot iterate over number (3)".into())
+    );
+    assert_eq!(
+        eval_error(".a", "[]"),
+        EvalError::Invalid("Cannot index array with \"a\"".into())
+    );
+    assert_eq!(
+        eval_error("{} - 1", "null").to_string(),
+        "object ({}) and number (1) cannot be subtracted"
+    );
+    assert_eq!(
+        eval_error("\"ab\" * 1e18", "null").to_string(),
+        "string (\"ab\") and number (1e18) cannot be multiplied because the result is too long"
+    );
+    assert_eq!(jq("\"ab\" * 3, \"x\" * 0", "null"), [r#""ababab""#, "null"]);
+    assert_eq!(
+        eval_error("1 / 0", "null").to_string(),
+        "number (1) and number (0) cannot be divided because the divisor is zero"
+    );
+    assert_eq!(
+        jq("[.[] | .a?], [.[]?]", "[1, {\"a\": 2}]"),
+        ["[2]", "[1,{\"a\":2}]"]
+    );
+    assert_eq!(
+        jq("try error(\"x\") catch ., try error({}) catch .", "null"),
+        [r#""x""#, "{}"]
+    );
+    assert_eq!(
+        jq("[.[] | try (if . > 1 then error else . end)]", "[1, 2, 0]"),
+        ["[1,0]"]
+    );
+    // An error after `try` is not its to catch.
+    assert_eq!(
+        eval_error("try 1 | error(\"after\")", "null"),
+        EvalError::Raised(jimson::parser::JsonValue::String("after".into()))
+    );
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(parse_error(".a |"), (FilterErrorKind::UnexpectedEnd, 4));
+    assert_eq!(
+        parse_error(".a ]"),
+        (FilterErrorKind::Unexpected("]".into()), 3)
+    );
+    assert_eq!(parse_error("\"\\q\""), (FilterErrorKind::InvalidString, 1));
+    assert_eq!(
+        parse_error(". & 1"),
+        (FilterErrorKind::Unexpected("&".into()), 2)
+    );
+    assert_eq!(
+        parse_error("map(foo)"),
+        (FilterErrorKind::Undefined("foo/0".into()), 4)
+    );
+    assert_eq!(
+        parse_error("map(.; .)"),
+        (FilterErrorKind::Undefined("map/2".into()), 0)
+    );
+    assert_eq!(
+        parse_error(". as $x | $y"),
+        (FilterErrorKind::Undefined("$y".into()), 10)
+    );
+    assert_eq!(
+        parse_error("(. as $x | 1), $x"),
+        (FilterErrorKind::Undefined("$x".into()), 15)
+    );
+    // Destructuring is not supported.
+    assert_eq!(
+        parse_error("reduce .[] as [$k] ({}; .)"),
+        (FilterErrorKind::Unexpected("[".into()), 14)
+    );
+    assert_eq!(
+        parse_error("if . then 1"),
+        (FilterErrorKind::UnexpectedEnd, 11)
+    );
+    assert_eq!(
+        parse_error(&format!("{}.{}", "[".repeat(100), "]".repeat(100))),
+        (FilterErrorKind::TooDeep, 64)
+    );
+    // Suffixes are kept flat, but each `?` counts as a level.
+    assert_eq!(
+        parse_error(&".a?".repeat(100)),
+        (FilterErrorKind::TooDeep, 194)
+    );
+    assert_eq!(jq("", "[1]"), ["[1]"]);
+}
@@ -0,0 +1,170 @@
+use jimson::{
+    errors::ErrorKind,
+    lexer::Lexer,
+    options::{Dialect, ParserOptions},
+    parser::{JsonValue, Parser},
+    token::TokenType,
+};
+
+fn parse(input: &str, dialect: Dialect) -> Result<JsonValue, ErrorKind> {
+    Parser::with_options(input, ParserOptions::new().dialect(dialect))
+        .and_then(|mut parser| parser.parse_root())
+        .map_err(|e| e.kind)
+}
+
+fn json5(input: &str) -> JsonValue {
+    parse(input, Dialect::Json5).unwrap()
+}
+
+fn json(input: &str) -> JsonValue {
+    parse(input, Dialect::Json).unwrap()
+}
+
+#[test]
+fn parse_json5_document() {
+    let input = r#"// An example from the JSON5 site.
+{
+  /* comments
+     over several lines */
+  unquoted: 'and you can quote me on that',
+  singleQuotes: 'I can use "double quotes" here',
+  lineBreaks: "Look, Mom! \
+No \\n's!",
+  hexadecimal: 0xdecaf,
+  leadingDecimalPoint: .8675309, andTrailing: 8675309.,
+  positiveSign: +1,
+  trailingComma: 'in objects', andIn: ['arrays',],
+  "backwardsCompatible": "with JSON",
+}
+"#;
+    let expected = json(
+        r#"{
+  "unquoted": "and you can quote me on that",
+  "singleQuotes": "I can use \"double quotes\" here",
+  "lineBreaks": "Look, Mom! No \\n's!",
+  "hexadecimal": 912559,
+  "leadingDecimalPoint": 0.8675309, "andTrailing": 8675309.0,
+  "positiveSign": 1,
+  "trailingComma": "in objects", "andIn": ["arrays"],
+  "backwardsCompatible": "with JSON"
+}"#,
+    );
+    assert_eq!(json5(input), expected);
+}
+
+#[test]
+fn json5_numbers() {
+    assert_eq!(
+        json5("[0x7F, -0XfF, +.5e1, -5.]"),
+        json("[127, -255, 5.0, -5.0]")
+    );
+    assert_eq!(json5("0xFFFFFFFFFFFFFFFF").as_u64(), Some(u64::MAX));
+    assert_eq!(json5("-0x8000000000000000").as_i64(), Some(i64::MIN));
+    assert_eq!(json5("Infinity").as_f64(), Some(f64::INFINITY));
+    assert_eq!(json5("-Infinity").as_f64(), Some(f64::NEG_INFINITY));
+    assert!(json5("+NaN").as_f64().unwrap().is_nan());
+
+    assert_eq!(parse("[.]", Dialect::Json5), Err(ErrorKind::InvalidNumber));
+    assert_eq!(parse("[0x]", Dialect::Json5), Err(ErrorKind::InvalidNumber));
+    assert_eq!(
+        parse("[-Inf]", Dialect::Json5),
+        Err(ErrorKind::InvalidNumber)
+    );
+    assert_eq!(
+        parse("[012]", Dialect::Json5),
+        Err(ErrorKind::NumberWithLeadingZero)
+    );
+    assert_eq!(
+        parse("[0x10000000000000000]", Dialect::Json5),
+        Err(ErrorKind::NumberOutOfRange)
+    );
+}
+
+#[test]
+fn json5_strings_and_keys() {
+    assert_eq!(
+        json5(r#"{$id: '\x41\v\0\'\a', null: 1, Infinity: 2, _ok9: "it's"}"#),
+        json(r#"{"$id": "A\u000b\u0000'a", "null": 1, "Infinity": 2, "_ok9": "it's"}"#)
+    );
+    // Line continuations work with every kind of line break.
+    assert_eq!(json5("'a\\\r\nb\\\rc\\\u{2028}d'"), json(r#""abcd""#));
+    assert_eq!(json5("['tab\there']"), json(r#"["tab\there"]"#));
+
+    assert_eq!(
+        parse("['a\nb']", Dialect::Json5),
+        Err(ErrorKind::ControlCharacterInString)
+    );
+    assert_eq!(
+        parse(r"['\1']", Dialect::Json5),
+        Err(ErrorKind::InvalidBackslashEscape)
+    );
+    assert_eq!(
+        parse(r"['\xZZ']", Dialect::Json5),
+        Err(ErrorKind::InvalidBackslashEscape)
+    );
+}
+
+#[test]
+fn json5_trailing_commas_and_comments() {
+    assert_eq!(json5("[1, 2, /* three */ ] // done"), json("[1, 2]"));
+    assert_eq!(json5("{a: {},}"), json(r#"{"a": {}}"#));
+    assert_eq!(json5("\u{FEFF}\u{00A0}[\u{2028}1\u{3000}]"), json("[1]"));
+    assert_eq!(
+        parse("[1,,]", Dialect::Json5),
+        Err(ErrorKind::TrailingComma)
+    );
+    assert_eq!(parse("[,]", Dialect::Json5), Err(ErrorKind::MissingValue));
+    assert_eq!(
+        parse("[1] /* open", Dialect::Json5),
+        Err(ErrorKind::UnclosedComment)
+    );
+    assert_eq!(
+        parse("[1] / 2", Dialect::Json5),
+        Err(ErrorKind::InvalidSyntax)
+    );
+}
+
+#[test]
+fn strict_json_is_the_default() {
+    assert_eq!(
+        Parser::new("['a']")
+            .and_then(|mut p| p.parse_root())
+            .map_err(|e| e.kind),
+        Err(ErrorKind::SingleQuote)
+    );
+    assert_eq!(parse("['a']", Dialect::Json), Err(ErrorKind::SingleQuote));
+    assert_eq!(parse("[1,]", Dialect::Json), Err(ErrorKind::TrailingComma));
+    assert_eq!(
+        parse("{a: 1}", Dialect::Json),
+        Err(ErrorKind::ObjectKeyNotString)
+    );
+    assert_eq!(parse("[0x1]", Dialect::Json), Err(ErrorKind::InvalidNumber));
+    assert_eq!(parse("[+1]", Dialect::Json), Err(ErrorKind::InvalidSyntax));
+    assert_eq!(
+        parse("[Infinity]", Dialect::Json),
+        Err(ErrorKind::InvalidSyntax)
+    );
+    assert_eq!(
+        parse("[1] // no", Dialect::Json),
+        Err(ErrorKind::InvalidSyntax)
+    );
+}
+
+#[test]
+fn lex_json5_comments_as_trivia() {
+    let input = "[1, // one\n/* two */ 2]";
+    let tokens: Vec<_> = Lexer::new(input)
+        .unwrap()
+        .with_dialect(Dialect::Json5)
+        .with_trivia(true)
+        .map(Result::unwrap)
+        .collect();
+    let comments: Vec<_> = tokens
+        .iter()
+        .filter(|tok| tok.token_type == TokenType::Comment)
+        .map(|tok| tok.lexeme)
+        .collect();
+    assert_eq!(comments, ["// one", "/* two */"]);
+    let lexemes: Vec<_> = tokens.iter().map(|tok| tok.lexeme).collect();
+    assert_eq!(lexemes.concat(), input);
+}
@@ -0,0 +1,142 @@
+use jimson::{
+    comment::{PathSegment, Placement},
+    errors::ErrorKind,
+    options::{Dialect, ParserOptions},
+    parser::{JsonValue, Parser},
+};
+
+fn parse(input: &str, dialect: Dialect) -> Result<JsonValue, ErrorKind> {
+    Parser::with_options(input, ParserOptions::new().dialect(dialect))
+        .and_then(|mut parser| parser.parse_root())
+        .map_err(|e| e.kind)
+}
+
+const SETTINGS: &str = r#"// Place your settings in this file to overwrite the defaults.
+{
+    "editor.fontSize": 14, // points
+    /* Files */
+    "files.exclude": {
+        "**/.git": true,
+        "**/node_modules": true, // slow to index
+    },
+    "search.exclude": [
+        // nothing yet
+    ],
+    "editor.rulers": [80, 100,],
+}
+// end
+"#;
+
+#[test]
+fn parse_jsonc() {
+    let expected = parse(
+        r#"{
+            "editor.fontSize": 14,
+            "files.exclude": {"**/.git": true, "**/node_modules": true},
+            "search.exclude": [],
+            "editor.rulers": [80, 100]
+        }"#,
+        Dialect::Json,
+    );
+    assert_eq!(parse(SETTINGS, Dialect::Jsonc), expected);
+    assert_eq!(
+        parse(SETTINGS, Dialect::Json),
+        Err(ErrorKind::InvalidSyntax)
+    );
+}
+
+#[test]
+fn jsonc_is_json_otherwise() {
+    assert_eq!(parse("['a']", Dialect::Jsonc), Err(ErrorKind::SingleQuote));
+    assert_eq!(
+        parse("{a: 1}", Dialect::Jsonc),
+        Err(ErrorKind::ObjectKeyNotString)
+    );
+    assert_eq!(
+        parse("[0x1]", Dialect::Jsonc),
+        Err(ErrorKind::InvalidNumber)
+    );
+    assert_eq!(
+        parse("[1,,]", Dialect::Jsonc),
+        Err(ErrorKind::TrailingComma)
+    );
+    assert_eq!(
+        parse("[1 /* 2", Dialect::Jsonc),
+        Err(ErrorKind::UnclosedComment)
+    );
+}
+
+#[test]
+fn comments_are_not_attached_by_default() {
+    let mut parser =
+        Parser::with_options(SETTINGS, ParserOptions::new().dialect(Dialect::Jsonc)).unwrap();
+    parser.parse_root().unwrap();
+    assert!(parser.comments().is_empty());
+}
+
+#[test]
+fn attach_comments_to_nearest_value() {
+    let options = ParserOptions::new()
+        .dialect(Dialect::Jsonc)
+        .attach_comments(true);
+    let mut parser = Parser::with_options(SETTINGS, options).unwrap();
+    let with_comments = parser.parse_root().unwrap();
+    assert_eq!(Ok(with_comments), parse(SETTINGS, Dialect::Jsonc));
+
+    let key = |k: &str| PathSegment::Key(k.into());
+    let attached: Vec<_> = parser
+        .comments()
+        .iter()
+        .map(|c| (c.text.as_str(), c.path.clone(), c.placement))
+        .collect();
+    assert_eq!(
+        attached,
+        [
+            (
+                "// Place your settings in this file to overwrite the defaults.",
+                vec![],
+                Placement::Before
+            ),
+            ("// points", vec![key("editor.fontSize")], Placement::After),
+            ("/* Files */", vec![key("files.exclude")], Placement::Before),
+            (
+                "// slow to index",
+                vec![key("files.exclude"), key("**/node_modules")],
+                Placement::After
+            ),
+            (
+                "// nothing yet",
+                vec![key("search.exclude")],
+                Placement::Inside
+            ),
+            ("// end", vec![], Placement::After),
+        ]
+    );
+    let end = &parser.comments()[5];
+    assert_eq!(&SETTINGS[end.span.range()], "// end");
+    assert_eq!(end.span.start.line, 14);
+}
+
+#[test]
+fn attach_comments_in_arrays() {
+    let input = "[\n  1, /* one */\n  /* two */ 2,\n  3\n  // dangling\n] // root";
+    let options = ParserOptions::new()
+        .dialect(Dialect::Json5)
+        .attach_comments(true);
+    let mut parser = Parser::with_options(input, options).unwrap();
+    parser.parse_root().unwrap();
+    let attached: Vec<_> = parser
+        .comments()
+        .iter()
+        .map(|c| (c.text.as_str(), c.path.clone(), c.placement))
+        .collect();
+    assert_eq!(
+        attached,
+        [
+            ("/* one */", vec![PathSegment::Index(0)], Placement::After),
+            ("/* two */", vec![PathSegment::Index(1)], Placement::Before),
+            ("// dangling", vec![], Placement::Inside),
+            ("// root", vec![], Placement::After),
+        ]
+    );
+}
@@ -0,0 +1,222 @@
+use jimson::{errors::JsonPathErrorKind, jsonpath::JsonPath, parser::JsonValue};
+
+mod common;
+use common::parse;
+
+/// The example document from RFC 9535, section 1.5.
+const STORE: &str = r#"{ "store": {
+    "book": [
+      { "category": "reference",
+        "author": "Nigel Rees",
+        "title": "Sayings of the Century",
+        "price": 8.95
+      },
+      { "category": "fiction",
+        "author": "Evelyn Waugh",
+        "title": "Sword of Honour",
+        "price": 12.99
+      },
+      { "category": "fiction",
+        "author": "Herman Melville",
+        "title": "Moby Dick",
+        "isbn": "0-553-21311-3",
+        "price": 8.99
+      },
+      { "category": "fiction",
+        "author": "J. R. R. Tolkien",
+        "title": "The Lord of the Rings",
+        "isbn": "0-395-19395-8",
+        "price": 22.99
+      }
+    ],
+    "bicycle": {
+      "color": "red",
+      "price": 399
+    }
+  }
+}"#;
+
+fn paths(value: &JsonValue, path: &str) -> Vec<String> {
+    let path = JsonPath::parse(path).unwrap();
+    path.query(value)
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect()
+}
+
+fn error(path: &str) -> (JsonPathErrorKind, usize) {
+    let e = JsonPath::parse(path).unwrap_err();
+    (e.kind, e.offset)
+}
+
+#[test]
+fn rfc_examples() {
+    let store = parse(STORE);
+    let authors: Vec<_> = store
+        .query("$.store.book[*].author")
+        .unwrap()
+        .into_iter()
+        .map(|(_, author)| author.clone())
+        .collect();
+    assert_eq!(authors[3], JsonValue::String("J. R. R. Tolkien".into()));
+    assert_eq!(store.query("$..author").unwrap().len(), 4);
+    assert_eq!(
+        paths(&store, "$.store.*"),
+        ["$['store']['book']", "$['store']['bicycle']"]
+    );
+    assert_eq!(
+        paths(&store, "$.store..price"),
+        [
+            "$['store']['book'][0]['price']",
+            "$['store']['book'][1]['price']",
+            "$['store']['book'][2]['price']",
+            "$['store']['book'][3]['price']",
+            "$['store']['bicycle']['price']",
+        ]
+    );
+    assert_eq!(paths(&store, "$..book[2]"), ["$['store']['book'][2]"]);
+    assert_eq!(paths(&store, "$..book[-1]"), ["$['store']['book'][3]"]);
+    assert_eq!(paths(&store, "$..book[0,1]"), paths(&store, "$..book[:2]"));
+    assert_eq!(
+        paths(&store, "$..book[?@.isbn]"),
+        ["$['store']['book'][2]", "$['store']['book'][3]"]
+    );
+    assert_eq!(
+        paths(&store, "$..book[?@.price<10]"),
+        ["$['store']['book'][0]", "$['store']['book'][2]"]
+    );
+    assert_eq!(store.query("$..*").unwrap().len(), 27);
+}
+
+#[test]
+fn slices_and_unions() {
+    let value = parse(r#"["a", "b", "c", "d", "e", "f", "g"]"#);
+    let letters = |path: &str| -> String {
+        value
+            .query(path)
+            .unwrap()
+            .into_iter()
+            .map(|(_, v)| match v {
+                JsonValue::String(s) => s.clone(),
+                _ => unreachable!(),
+            })
+            .collect()
+    };
+    assert_eq!(letters("$[1:3]"), "bc");
+    assert_eq!(letters("$[5:]"), "fg");
+    assert_eq!(letters("$[1:5:2]"), "bd");
+    assert_eq!(letters("$[5:1:-2]"), "fd");
+    assert_eq!(letters("$[::-1]"), "gfedcba");
+    assert_eq!(letters("$[::0]"), "");
+    assert_eq!(letters("$[-100:100]"), "abcdefg");
+    // Unions keep duplicates, in the order of the selectors.
+    assert_eq!(letters("$[0, 3, 0, -1]"), "adag");
+    assert_eq!(letters("$[ 0 : 2 , 5 ]"), "abf");
+}
+
+#[test]
+fn filters_and_functions() {
+    let value = parse(
+        r#"[
+            {"name": "Bob", "date": "1974-05-01", "tags": ["a"], "color": {"x": "red"}},
+            {"name": "robert", "date": "1974-05-10T00", "tags": ["a", "b", "c"]},
+            {"name": "Ann", "n": 1.0, "o": {"b": 1, "a": [2]}},
+            "short"
+        ]"#,
+    );
+    assert_eq!(paths(&value, "$[?length(@) > 4]"), ["$[3]"]);
+    assert_eq!(paths(&value, "$[?length(@.tags) == 3]"), ["$[1]"]);
+    assert_eq!(paths(&value, "$[?count(@.*) == 3]"), ["$[1]", "$[2]"]);
+    assert_eq!(paths(&value, "$[?match(@.date, '1974-05-..')]"), ["$[0]"]);
+    assert_eq!(paths(&value, "$[?search(@.name, '[BR]ob')]"), ["$[0]"]);
+    assert_eq!(
+        paths(
+            &value,
+            "$[?search(@.name, '(?i)x') || match(@.name, '\\\\p{Lu}\\\\p{Ll}+')]"
+        ),
+        ["$[0]", "$[2]"]
+    );
+    assert_eq!(paths(&value, "$[?value(@..x) == 'red']"), ["$[0]"]);
+    assert_eq!(paths(&value, "$[?@.n == 1]"), ["$[2]"]);
+    assert_eq!(paths(&value, "$[?@.o == $[2].o]"), ["$[2]"]);
+    assert_eq!(paths(&value, "$[?!@.tags && @ != 'short']"), ["$[2]"]);
+    assert_eq!(paths(&value, "$[?@.missing == $.absent]").len(), 4);
+    assert_eq!(paths(&value, "$[?@.missing <= $.absent]").len(), 4);
+    assert_eq!(paths(&value, "$[?@.name > 'B' && @.name < 'a']"), ["$[0]"]);
+    assert_eq!(
+        paths(&value, "$[?(@.n || @.date) && !(@.tags[2])]"),
+        ["$[0]", "$[2]"]
+    );
+}
+
+#[test]
+fn normalized_paths_are_escaped() {
+    let value = parse(r#"{"it's": {"a\\b\u000b\n": 1}}"#);
+    assert_eq!(
+        paths(&value, "$..*"),
+        [r"$['it\'s']", r"$['it\'s']['a\\b\u000b\n']"]
+    );
+    assert_eq!(paths(&value, "$[\"it's\"]['a\\\\b\\u000b\\n']").len(), 1);
+}
+
+#[test]
+fn malformed_and_ill_typed_paths() {
+    assert_eq!(error(""), (JsonPathErrorKind::UnexpectedEnd, 0));
+    assert_eq!(error("$.a "), (JsonPathErrorKind::UnexpectedChar(' '), 3));
+    assert_eq!(error("$[01]"), (JsonPathErrorKind::InvalidNumber, 2));
+    assert_eq!(
+        error("$[9007199254740992]"),
+        (JsonPathErrorKind::InvalidNumber, 2)
+    );
+    assert_eq!(error("$['\\x']"), (JsonPathErrorKind::InvalidString, 3));
+    assert_eq!(
+        error("$[?foo(@)]"),
+        (JsonPathErrorKind::UnknownFunction("foo".into()), 3)
+    );
+    assert_eq!(
+        error("$[?length(@.*) < 3]"),
+        (JsonPathErrorKind::NotWellTyped, 10)
+    );
+    assert_eq!(
+        error("$[?count(1) == 1]"),
+        (JsonPathErrorKind::NotWellTyped, 9)
+    );
+    assert_eq!(
+        error("$[?match(@.a, 'x') == true]"),
+        (JsonPathErrorKind::NotWellTyped, 3)
+    );
+    assert_eq!(
+        error("$[?value(@..a)]"),
+        (JsonPathErrorKind::NotWellTyped, 3)
+    );
+    assert_eq!(error("$[?@.* == 1]"), (JsonPathErrorKind::NotWellTyped, 3));
+    assert_eq!(error("$[?1]"), (JsonPathErrorKind::NotWellTyped, 3));
+    let nested = format!("$[?{}@{}]", "(".repeat(100), ")".repeat(100));
+    assert_eq!(error(&nested), (JsonPathErrorKind::TooDeep, 67));
+    let nested = format!("$[?{}@{}]", "(".repeat(63), ")".repeat(63));
+    assert_eq!(paths(&parse("[1]"), &nested), ["$[0]"]);
+    // Valid patterns that cannot be compiled are refused, while invalid
+    // ones never match.
+    let group = |n: usize| format!("$[?match(@, '{}a{}')]", "(".repeat(n), ")".repeat(n));
+    assert_eq!(paths(&parse(r#"["a"]"#), &group(64)), ["$[0]"]);
+    assert_eq!(
+        error(&group(10_000)),
+        (JsonPathErrorKind::UnsupportedPattern, 12)
+    );
+    assert_eq!(
+        error("$[?search(@, '\\\\p{Sm}')]"),
+        (JsonPathErrorKind::UnsupportedPattern, 13)
+    );
+    assert_eq!(
+        error("$[?match(@, 'a{100000}')]"),
+        (JsonPathErrorKind::UnsupportedPattern, 12)
+    );
+    let value = parse(r#"["a", "+"]"#);
+    assert!(paths(&value, "$[?search(@, '\\\\p{Sm}[')]").is_empty());
+    assert!(paths(&value, "$[?search(@, '\\\\p{Xx}')]").is_empty());
+    assert!(paths(
+        &parse(r#"[{"a": "+", "p": "\\p{Sm}"}]"#),
+        "$[?match(@.a, @.p)]"
+    )
+    .is_empty());
+}
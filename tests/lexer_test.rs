@@ -0,0 +1,91 @@
+use jimson::{errors::ErrorKind, lexer::Lexer, token::TokenType};
+
+#[test]
+fn lex_tokens_with_lexemes_and_spans() {
+    let input = "{\"k\\n\": [-1.5e3, true, false, null]}";
+    let tokens: Vec<_> = Lexer::new(input).unwrap().map(Result::unwrap).collect();
+    let types: Vec<_> = tokens.iter().map(|tok| tok.token_type.clone()).collect();
+    assert_eq!(
+        types,
+        [
+            TokenType::Lbrace,
+            TokenType::Str,
+            TokenType::Colon,
+            TokenType::LSqBracket,
+            TokenType::Number,
+            TokenType::Comma,
+            TokenType::True,
+            TokenType::Comma,
+            TokenType::False,
+            TokenType::Comma,
+            TokenType::Null,
+            TokenType::RSqbracket,
+            TokenType::Rbrace,
+        ]
+    );
+    assert_eq!(tokens[1].lexeme, "\"k\\n\"");
+    assert_eq!(tokens[1].value.as_deref(), Some("k\n"));
+    assert_eq!(tokens[4].lexeme, "-1.5e3");
+    assert_eq!(tokens[4].value, None);
+    for tok in &tokens {
+        assert_eq!(&input[tok.span.range()], tok.lexeme);
+    }
+}
+
+#[test]
+fn lex_whitespace_on_request() {
+    let input = "[ 1,\n\t2 ]";
+    let tokens: Vec<_> = Lexer::new(input)
+        .unwrap()
+        .with_trivia(true)
+        .map(Result::unwrap)
+        .collect();
+    let lexemes: Vec<_> = tokens.iter().map(|tok| tok.lexeme).collect();
+    assert_eq!(lexemes, ["[", " ", "1", ",", "\n\t", "2", " ", "]"]);
+    assert_eq!(tokens[4].token_type, TokenType::Whitespace);
+    assert_eq!(tokens[5].span.start.line, 2);
+    assert_eq!(tokens[5].span.start.column, 2);
+    // With trivia the tokens cover the whole input.
+    assert_eq!(lexemes.concat(), input);
+}
+
+#[test]
+fn lex_identifiers_and_invalid_characters() {
+    let tokens: Vec<_> = Lexer::new("key True # nullish")
+        .unwrap()
+        .map(|tok| tok.unwrap().token_type)
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            TokenType::Identifier,
+            TokenType::Identifier,
+            TokenType::InvalidChar('#'),
+            TokenType::Identifier,
+        ]
+    );
+}
+
+#[test]
+fn lex_past_errors() {
+    let results: Vec<_> = Lexer::new(r#"["a\x, b", 01, 2]"#).unwrap().collect();
+    let summary: Vec<_> = results
+        .iter()
+        .map(|r| match r {
+            Ok(tok) => Ok(tok.token_type.clone()),
+            Err(e) => Err(e.kind.clone()),
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            Ok(TokenType::LSqBracket),
+            Err(ErrorKind::InvalidBackslashEscape),
+            Ok(TokenType::Comma),
+            Err(ErrorKind::NumberWithLeadingZero),
+            Ok(TokenType::Comma),
+            Ok(TokenType::Number),
+            Ok(TokenType::RSqbracket),
+        ]
+    );
+}
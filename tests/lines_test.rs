@@ -0,0 +1,110 @@
+use jimson::{
+    errors::ErrorKind,
+    lines::{JsonLinesReader, JsonLinesWriter},
+    number::Number,
+    options::ParserOptions,
+    parser::{JsonValue, Parser},
+};
+
+#[test]
+fn read_one_value_per_line() {
+    let input = "{\"a\": 1}\r\n[true]\n\"x\"";
+    let values: Vec<_> = JsonLinesReader::new(input.as_bytes())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(values.len(), 3);
+    assert_eq!(values[1], JsonValue::Array(vec![JsonValue::Boolean(true)]));
+    assert_eq!(values[2], JsonValue::String("x".into()));
+}
+
+#[test]
+fn errors_report_absolute_positions() {
+    let input = "1\n2\n{\"a\" 1}\n4";
+    let mut reader = JsonLinesReader::new(input.as_bytes());
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().unwrap().is_ok());
+    let e = reader.next().unwrap().unwrap_err();
+    assert_eq!(e.kind, ErrorKind::MissingColon);
+    assert_eq!((e.span.start.line, e.span.start.column), (3, 6));
+    assert_eq!(e.span.start.offset, 9);
+    // The first error ends the iteration by default.
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn blank_lines_and_bad_records() {
+    let input: &[u8] = b"1\n\n  \nx\n\xff\n5\n";
+
+    let mut reader = JsonLinesReader::new(input);
+    assert!(reader.next().unwrap().is_ok());
+    assert_eq!(
+        reader.next().unwrap().unwrap_err().kind,
+        ErrorKind::EmptyInput
+    );
+    assert!(reader.next().is_none());
+
+    let results: Vec<_> = JsonLinesReader::new(input)
+        .skip_blank_lines(true)
+        .continue_on_error(true)
+        .map(|result| result.map_err(|e| (e.kind, e.span.start.line)))
+        .collect();
+    assert_eq!(
+        results,
+        [
+            Ok(JsonValue::Number(Number::from(1u64))),
+            Err((ErrorKind::InvalidSyntax, 4)),
+            Err((ErrorKind::Io(std::io::ErrorKind::InvalidData.into()), 5)),
+            Ok(JsonValue::Number(Number::from(5u64))),
+        ]
+    );
+}
+
+#[test]
+fn long_lines_are_too_large() {
+    let input = "1234\r\n12345\n[1,2,3,4,5,6,7,8,9]\n7";
+    let options = ParserOptions::new().max_input_size(Some(4));
+    let results: Vec<_> = JsonLinesReader::with_options(input.as_bytes(), options)
+        .continue_on_error(true)
+        .map(|result| result.map_err(|e| (e.kind, e.span.start.line, e.span.start.offset)))
+        .collect();
+    assert_eq!(
+        results,
+        [
+            Ok(JsonValue::Number(Number::from(1234u64))),
+            Err((ErrorKind::InputTooLarge, 2, 6)),
+            Err((ErrorKind::InputTooLarge, 3, 12)),
+            Ok(JsonValue::Number(Number::from(7u64))),
+        ]
+    );
+}
+
+#[test]
+fn write_round_trips() {
+    let values = [
+        Parser::new(r#"{"msg": "two\nlines", "n": [1, 2.5]}"#)
+            .unwrap()
+            .parse_root()
+            .unwrap(),
+        JsonValue::Null,
+    ];
+    let mut writer = JsonLinesWriter::new(Vec::new());
+    for value in &values {
+        writer.write(value).unwrap();
+    }
+    let out = writer.into_inner();
+    assert_eq!(
+        String::from_utf8(out.clone()).unwrap(),
+        "{\"msg\":\"two\\nlines\",\"n\":[1,2.5]}\nnull\n"
+    );
+
+    let read: Vec<_> = JsonLinesReader::new(&out[..])
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(read, values);
+
+    let mut writer = JsonLinesWriter::new(Vec::new());
+    assert!(writer
+        .write(&JsonValue::Number(Number::from(f64::NAN)))
+        .is_err());
+    assert!(writer.into_inner().is_empty());
+}
@@ -0,0 +1,89 @@
+use jimson::{
+    map::{Entry, Map},
+    parser::{JsonValue, Parser},
+};
+
+fn sample() -> Map<i32> {
+    ["c", "a", "d", "b"]
+        .into_iter()
+        .enumerate()
+        .map(|(i, k)| (k.to_string(), i as i32))
+        .collect()
+}
+
+fn keys(map: &Map<i32>) -> Vec<&str> {
+    map.keys().map(String::as_str).collect()
+}
+
+#[test]
+fn insertion_order_is_kept() {
+    let mut map = sample();
+    assert_eq!(keys(&map), ["c", "a", "d", "b"]);
+    assert_eq!(map.insert("a".into(), 10), Some(1));
+    assert_eq!(keys(&map), ["c", "a", "d", "b"]);
+    assert_eq!(map.insert("e".into(), 4), None);
+    assert_eq!(keys(&map), ["c", "a", "d", "b", "e"]);
+    assert_eq!(map.get("a"), Some(&10));
+    assert_eq!(map["e"], 4);
+    assert_eq!(map[0], 0);
+    assert_eq!(map.get_index(3), Some((&"b".to_string(), &3)));
+    assert_eq!(map.get_index_of("d"), Some(2));
+}
+
+#[test]
+fn shift_remove_keeps_order() {
+    let mut map = sample();
+    assert_eq!(map.shift_remove("a"), Some(1));
+    assert_eq!(map.shift_remove("a"), None);
+    assert_eq!(keys(&map), ["c", "d", "b"]);
+    assert_eq!(map.get_index_of("b"), Some(2));
+    assert_eq!(map["b"], 3);
+}
+
+#[test]
+fn swap_remove_moves_last_entry() {
+    let mut map = sample();
+    assert_eq!(map.swap_remove("c"), Some(0));
+    assert_eq!(keys(&map), ["b", "a", "d"]);
+    assert_eq!(map.get_index_of("b"), Some(0));
+    assert_eq!(map.swap_remove("d"), Some(2));
+    assert_eq!(keys(&map), ["b", "a"]);
+}
+
+#[test]
+fn entry_api() {
+    let mut map = sample();
+    *map.entry("z".into()).or_insert(0) += 5;
+    *map.entry("a".into()).or_insert(0) += 5;
+    assert_eq!(map["z"], 5);
+    assert_eq!(map["a"], 6);
+    assert_eq!(keys(&map), ["c", "a", "d", "b", "z"]);
+
+    let Entry::Occupied(entry) = map.entry("d".into()) else {
+        unreachable!()
+    };
+    assert_eq!(entry.index(), 2);
+    assert_eq!(entry.shift_remove(), 2);
+    assert_eq!(keys(&map), ["c", "a", "b", "z"]);
+}
+
+#[test]
+fn equality_ignores_order() {
+    let mut reordered = Map::new();
+    for (k, v) in sample().into_iter().rev() {
+        reordered.insert(k, v);
+    }
+    assert_eq!(reordered, sample());
+    reordered.insert("x".into(), 0);
+    assert_ne!(reordered, sample());
+}
+
+#[test]
+fn parsed_objects_keep_document_order() {
+    let mut json_parser = Parser::new(r#"{"zeta": 1, "alpha": 2, "mid": 3, "beta": 4}"#).unwrap();
+    let JsonValue::Object(store) = json_parser.parse_root().unwrap() else {
+        unreachable!()
+    };
+    let keys: Vec<_> = store.keys().collect();
+    assert_eq!(keys, ["zeta", "alpha", "mid", "beta"]);
+}
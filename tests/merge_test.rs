@@ -0,0 +1,107 @@
+use jimson::merge;
+
+mod common;
+use common::parse;
+
+/// The test cases of RFC 7396, appendix A, as target, patch and result.
+const RFC_CASES: &[(&str, &str, &str)] = &[
+    (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+    (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+    (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+    (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+    (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+    (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+    (
+        r#"{"a":{"b":"c"}}"#,
+        r#"{"a":{"b":"d","c":null}}"#,
+        r#"{"a":{"b":"d"}}"#,
+    ),
+    (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+    (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+    (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+    (r#"{"a":"foo"}"#, "null", "null"),
+    (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+    (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+    (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+    (
+        r#"{}"#,
+        r#"{"a":{"bb":{"ccc":null}}}"#,
+        r#"{"a":{"bb":{}}}"#,
+    ),
+];
+
+#[test]
+fn rfc_examples() {
+    for &(target, patch, result) in RFC_CASES {
+        let mut value = parse(target);
+        value.merge_patch(&parse(patch));
+        assert_eq!(value, parse(result), "{target} patched with {patch}");
+    }
+}
+
+#[test]
+fn rfc_example_document() {
+    let mut document = parse(
+        r#"{
+            "title": "Goodbye!",
+            "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        }"#,
+    );
+    let patch = parse(
+        r#"{
+            "title": "Hello!",
+            "phoneNumber": "+01-123-456-7890",
+            "author": {"familyName": null},
+            "tags": ["example"]
+        }"#,
+    );
+    let expected = parse(
+        r#"{
+            "title": "Hello!",
+            "author": {"givenName": "John"},
+            "tags": ["example"],
+            "content": "This will be unchanged",
+            "phoneNumber": "+01-123-456-7890"
+        }"#,
+    );
+    let before = document.clone();
+    document.merge_patch(&patch);
+    assert_eq!(document, expected);
+    assert_eq!(merge::diff(&before, &expected), patch);
+}
+
+#[test]
+fn generated_patches_round_trip() {
+    for &(target, _, result) in RFC_CASES {
+        let (target, result) = (parse(target), parse(result));
+        let patch = merge::diff(&target, &result);
+        let mut value = target.clone();
+        value.merge_patch(&patch);
+        assert_eq!(value, result, "{target:?} to {result:?}");
+    }
+    assert_eq!(
+        merge::diff(
+            &parse(r#"{"a": [1], "b": {"c": 1}}"#),
+            &parse(r#"{"a": [1], "b": {"c": 1}}"#)
+        ),
+        parse("{}")
+    );
+    assert_eq!(
+        merge::diff(
+            &parse(r#"{"a": {"b": 1}}"#),
+            &parse(r#"{"a": {"b": 1, "c": [2]}}"#)
+        ),
+        parse(r#"{"a": {"c": [2]}}"#)
+    );
+    assert_eq!(
+        merge::diff(&parse("[1]"), &parse("[1, 2]")),
+        parse("[1, 2]")
+    );
+    // Null members cannot be set by a merge patch, so they are left out.
+    assert_eq!(
+        merge::diff(&parse(r#"{"a": 1}"#), &parse(r#"{"a": null, "b": null}"#)),
+        parse("{}")
+    );
+}
@@ -0,0 +1,263 @@
+use jimson::{
+    errors::ErrorKind,
+    options::{DuplicateKeys, ParserOptions},
+    parser::{JsonValue, Parser},
+};
+
+fn parse(input: &str, options: ParserOptions) -> Result<JsonValue, ErrorKind> {
+    Parser::with_options(input, options)
+        .and_then(|mut parser| parser.parse_root())
+        .map_err(|e| e.kind)
+}
+
+#[test]
+fn max_depth_can_be_raised_and_lowered() {
+    let deep = format!("{}{}", "[".repeat(40), "]".repeat(40));
+    assert_eq!(
+        parse(&deep, ParserOptions::default()),
+        Err(ErrorKind::NestingTooDeep)
+    );
+    assert!(parse(&deep, ParserOptions::new().max_depth(40)).is_ok());
+    assert_eq!(
+        parse(&deep, ParserOptions::new().max_depth(39)),
+        Err(ErrorKind::NestingTooDeep)
+    );
+    assert_eq!(
+        parse(r#"{"a": {}}"#, ParserOptions::new().max_depth(1)),
+        Err(ErrorKind::NestingTooDeep)
+    );
+}
+
+#[test]
+fn depth_counts_nesting_not_siblings() {
+    let siblings = format!("[{}[]]", "[1],".repeat(50));
+    assert!(parse(&siblings, ParserOptions::new().max_depth(2)).is_ok());
+}
+
+#[test]
+fn max_string_length() {
+    let options = ParserOptions::new().max_string_length(Some(3));
+    assert!(parse(r#"{"abc": "é"}"#, options.clone()).is_ok());
+    assert_eq!(
+        parse(r#"["abcd"]"#, options.clone()),
+        Err(ErrorKind::StringTooLong)
+    );
+    assert_eq!(
+        parse(r#"{"abcd": 1}"#, options.clone()),
+        Err(ErrorKind::StringTooLong)
+    );
+    // The limit applies to the decoded string, in bytes.
+    assert_eq!(parse(r#"["éé"]"#, options), Err(ErrorKind::StringTooLong));
+}
+
+#[test]
+fn max_object_members_and_array_elements() {
+    let options = ParserOptions::new()
+        .max_object_members(Some(2))
+        .max_array_elements(Some(2));
+    assert!(parse(r#"{"a": [1, 2], "b": []}"#, options.clone()).is_ok());
+    assert_eq!(
+        parse(r#"{"a": 1, "b": 2, "c": 3}"#, options.clone()),
+        Err(ErrorKind::TooManyObjectMembers)
+    );
+    assert_eq!(
+        parse("[1, 2, 3]", options.clone()),
+        Err(ErrorKind::TooManyArrayElements)
+    );
+    // Repeated keys count as members too.
+    assert_eq!(
+        parse(r#"{"a": 1, "a": 2, "a": 3}"#, options.clone()),
+        Err(ErrorKind::TooManyObjectMembers)
+    );
+
+    let mut parser = Parser::with_options("[[1, 2, 3]]", options).unwrap();
+    let (_, errors) = parser.parse_root_with_recovery();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, ErrorKind::TooManyArrayElements);
+    assert_eq!(errors[0].span.start.column, 9);
+}
+
+#[test]
+fn max_input_size() {
+    let options = ParserOptions::new().max_input_size(Some(8));
+    assert!(parse("[1, 2]", options.clone()).is_ok());
+    assert_eq!(
+        parse("[1, 2, 3, 4]", options),
+        Err(ErrorKind::InputTooLarge)
+    );
+}
+
+#[test]
+fn trailing_content() {
+    assert_eq!(
+        parse("[1] [2]", ParserOptions::default()),
+        Err(ErrorKind::InvalidSyntax)
+    );
+    let options = ParserOptions::new().allow_trailing_content(true);
+    assert_eq!(
+        parse("[1] [2]", options),
+        parse("[1]", ParserOptions::default())
+    );
+}
+
+fn keys_and_values(value: JsonValue) -> Vec<(String, JsonValue)> {
+    match value {
+        JsonValue::Object(map) => map.into_iter().collect(),
+        other => panic!("expected an object, got {:?}", other),
+    }
+}
+
+#[test]
+fn duplicate_keys() {
+    let input = r#"{"a": 1, "b": 2, "a": 3, "a": [4]}"#;
+    let with = |policy| {
+        keys_and_values(parse(input, ParserOptions::new().duplicate_keys(policy)).unwrap())
+    };
+    let num = |n: u64| JsonValue::Number(n.into());
+
+    assert_eq!(
+        with(DuplicateKeys::LastWins),
+        [
+            ("a".into(), JsonValue::Array(vec![num(4)])),
+            ("b".into(), num(2))
+        ]
+    );
+    assert_eq!(
+        keys_and_values(parse(input, ParserOptions::default()).unwrap()),
+        with(DuplicateKeys::LastWins)
+    );
+    assert_eq!(
+        with(DuplicateKeys::FirstWins),
+        [("a".into(), num(1)), ("b".into(), num(2))]
+    );
+    assert_eq!(
+        with(DuplicateKeys::CollectAll),
+        [
+            (
+                "a".into(),
+                JsonValue::Array(vec![num(1), num(3), JsonValue::Array(vec![num(4)])])
+            ),
+            ("b".into(), JsonValue::Array(vec![num(2)])),
+        ]
+    );
+    // A key seen twice does not look like one holding an array.
+    let collected = |input| {
+        let options = ParserOptions::new().duplicate_keys(DuplicateKeys::CollectAll);
+        parse(input, options).unwrap()
+    };
+    assert_ne!(
+        collected(r#"{"a": 1, "a": 2}"#),
+        collected(r#"{"a": [1, 2]}"#)
+    );
+    assert_eq!(
+        keys_and_values(collected(r#"{"a": {"b": 1}}"#)),
+        [(
+            "a".into(),
+            JsonValue::Array(vec![parse(r#"{"b": [1]}"#, ParserOptions::new()).unwrap()])
+        )]
+    );
+}
+
+#[test]
+fn duplicate_key_error_points_at_both_occurrences() {
+    let input = "{\"a\": 1,\n \"a\": 2}";
+    let options = ParserOptions::new().duplicate_keys(DuplicateKeys::Error);
+    let error = Parser::with_options(input, options.clone())
+        .and_then(|mut parser| parser.parse_root())
+        .unwrap_err();
+    assert_eq!((error.span.start.line, error.span.start.column), (2, 2));
+    assert_eq!(error.span.range(), 10..13);
+    let ErrorKind::DuplicateKey(first) = &error.kind else {
+        panic!("expected DuplicateKey, got {:?}", error.kind);
+    };
+    assert_eq!(first.range(), 1..4);
+    assert_eq!(
+        error.kind.to_string(),
+        "duplicate key, first defined at line 1, column 2"
+    );
+
+    // Recovery reports every duplicate and keeps the first value.
+    let mut parser = Parser::with_options(r#"{"a": 1, "a": 2, "b": 3, "a": 4}"#, options).unwrap();
+    let (value, errors) = parser.parse_root_with_recovery();
+    let columns: Vec<_> = errors.iter().map(|e| e.span.start.column).collect();
+    assert_eq!(columns, [10, 26]);
+    assert!(errors
+        .iter()
+        .all(|e| matches!(&e.kind, ErrorKind::DuplicateKey(first) if first.start.column == 2)));
+    assert_eq!(
+        keys_and_values(value),
+        [
+            ("a".into(), JsonValue::Number(1u64.into())),
+            ("b".into(), JsonValue::Number(3u64.into()))
+        ]
+    );
+}
+
+/// Parses `input` as RFC 8259 allows and as RFC 4627 did.
+fn parse_root_both_ways(
+    input: &str,
+) -> (Result<JsonValue, ErrorKind>, Result<JsonValue, ErrorKind>) {
+    let legacy = ParserOptions::new().require_object_or_array(true);
+    (parse(input, ParserOptions::default()), parse(input, legacy))
+}
+
+#[test]
+fn root_string() {
+    let (modern, legacy) = parse_root_both_ways(r#""str""#);
+    assert_eq!(modern, Ok(JsonValue::String("str".into())));
+    assert_eq!(legacy, Err(ErrorKind::NotObjectOrArray));
+}
+
+#[test]
+fn root_number() {
+    let (modern, legacy) = parse_root_both_ways(" 42 ");
+    assert_eq!(modern.unwrap().as_i64(), Some(42));
+    assert_eq!(legacy, Err(ErrorKind::NotObjectOrArray));
+}
+
+#[test]
+fn root_boolean() {
+    let (modern, legacy) = parse_root_both_ways("false");
+    assert_eq!(modern, Ok(JsonValue::Boolean(false)));
+    assert_eq!(legacy, Err(ErrorKind::NotObjectOrArray));
+}
+
+#[test]
+fn root_null() {
+    let (modern, legacy) = parse_root_both_ways("null");
+    assert_eq!(modern, Ok(JsonValue::Null));
+    assert_eq!(legacy, Err(ErrorKind::NotObjectOrArray));
+}
+
+#[test]
+fn root_object() {
+    let (modern, legacy) = parse_root_both_ways(r#"{"a": 1}"#);
+    assert!(matches!(modern, Ok(JsonValue::Object(_))));
+    assert_eq!(legacy, modern);
+}
+
+#[test]
+fn root_array() {
+    let (modern, legacy) = parse_root_both_ways("[1]");
+    assert!(matches!(modern, Ok(JsonValue::Array(_))));
+    assert_eq!(legacy, modern);
+}
+
+#[test]
+fn legacy_root_error_points_at_the_value() {
+    let options = ParserOptions::new().require_object_or_array(true);
+    let e = Parser::with_options("\n  \"x\"", options.clone())
+        .unwrap()
+        .parse_root()
+        .unwrap_err();
+    assert_eq!(e.kind, ErrorKind::NotObjectOrArray);
+    assert_eq!((e.span.start.line, e.span.start.column), (2, 3));
+    assert_eq!(e.span.end.column, 6);
+
+    // Bad input is still reported for what it is.
+    assert_eq!(
+        parse("[1,]", options.clone()),
+        Err(ErrorKind::TrailingComma)
+    );
+    assert_eq!(parse("  ", options), Err(ErrorKind::Eof));
+}
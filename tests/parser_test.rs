@@ -146,3 +146,217 @@ fn parse_invalid_json_with_array_values() {
     assert!(result.is_err());
     assert_eq!(result.unwrap_err().kind, ErrorKind::SingleQuote);
 }
+
+#[test]
+fn parse_string_with_escape_sequences() {
+    let mut json_parser =
+        Parser::new(r#"["\"quoted\"", "a\\b", "\/", "\b\f\n\r\t", "C:\\Users\\jim"]"#).unwrap();
+    let JsonValue::Array(values) = json_parser.parse_root().unwrap() else {
+        unreachable!()
+    };
+    let expected = [
+        "\"quoted\"",
+        "a\\b",
+        "/",
+        "\u{8}\u{c}\n\r\t",
+        "C:\\Users\\jim",
+    ];
+    for (value, expected) in values.iter().zip(expected) {
+        assert_eq!(value, &JsonValue::String(expected.into()));
+    }
+}
+
+#[test]
+fn parse_string_with_unicode_escapes() {
+    let mut json_parser = Parser::new(r#"["\u00e9\u4567", "\uD83E\uDD80", "🦀"]"#).unwrap();
+    let JsonValue::Array(values) = json_parser.parse_root().unwrap() else {
+        unreachable!()
+    };
+    assert_eq!(values[0], JsonValue::String("é\u{4567}".into()));
+    assert_eq!(values[1], JsonValue::String("🦀".into()));
+    assert_eq!(values[2], JsonValue::String("🦀".into()));
+}
+
+#[test]
+fn parse_string_with_lone_surrogates() {
+    for input in [r#"["\uD83E"]"#, r#"["\uD83E\n"]"#, r#"["\uD83E\u0041"]"#] {
+        let mut json_parser = Parser::new(input).unwrap();
+        let result = json_parser.parse_root().unwrap_err();
+        assert_eq!(result.kind, ErrorKind::LoneSurrogate(0xD83E));
+    }
+    let mut json_parser = Parser::new(r#"["\uDD80"]"#).unwrap();
+    let result = json_parser.parse_root().unwrap_err();
+    assert_eq!(result.kind, ErrorKind::LoneSurrogate(0xDD80));
+}
+
+#[test]
+fn parse_string_with_invalid_unicode_escape() {
+    let mut json_parser = Parser::new(r#"["\u12G4"]"#).unwrap();
+    let result = json_parser.parse_root().unwrap_err();
+    assert_eq!(result.kind, ErrorKind::InvalidUnicodeEscape);
+}
+
+#[test]
+fn parse_string_with_raw_control_character() {
+    let mut json_parser = Parser::new("[\"tab\there\"]").unwrap();
+    let result = json_parser.parse_root().unwrap_err();
+    assert_eq!(result.kind, ErrorKind::ControlCharacterInString);
+}
+
+#[test]
+fn parse_unterminated_string() {
+    let mut json_parser = Parser::new(r#"["unterminated]"#).unwrap();
+    let result = json_parser.parse_root().unwrap_err();
+    assert_eq!(result.kind, ErrorKind::UnclosedDelimiter(TokenType::Str));
+}
+
+#[test]
+fn parse_numbers_with_fractions_and_exponents() {
+    let mut json_parser =
+        Parser::new("[0, -0, 0.5, -25.50, 1e10, 2.5E-3, 1E+2, 0.1e1, 1e400]").unwrap();
+    let result = json_parser.parse_root().unwrap_err();
+    assert_eq!(result.kind, ErrorKind::NumberOutOfRange);
+
+    let mut json_parser = Parser::new("[0, -0, 0.5, -25.50, 1e10, 2.5E-3, 1E+2, 0.1e1]").unwrap();
+    let JsonValue::Array(values) = json_parser.parse_root().unwrap() else {
+        unreachable!()
+    };
+    let expected = [0.0, 0.0, 0.5, -25.5, 1e10, 2.5e-3, 100.0, 1.0];
+    for (value, expected) in values.iter().zip(expected) {
+        assert_eq!(value.as_f64(), Some(expected));
+    }
+    assert_eq!(values[0].as_u64(), Some(0));
+    assert!(values[1].as_f64().unwrap().is_sign_negative());
+    assert!(values[4].as_number().unwrap().is_f64());
+}
+
+#[test]
+fn parse_integers_beyond_f64_precision() {
+    let mut json_parser =
+        Parser::new("[9007199254740993, 18446744073709551615, -9223372036854775808]").unwrap();
+    let JsonValue::Array(values) = json_parser.parse_root().unwrap() else {
+        unreachable!()
+    };
+    assert_eq!(values[0].as_u64(), Some(9007199254740993));
+    assert_eq!(values[0].as_i64(), Some(9007199254740993));
+    assert_eq!(values[0].as_f64(), None);
+    assert_eq!(values[1].as_u64(), Some(u64::MAX));
+    assert_eq!(values[1].as_i64(), None);
+    assert_eq!(values[2].as_i64(), Some(i64::MIN));
+    assert_eq!(values[2].as_u64(), None);
+}
+
+#[test]
+fn parse_malformed_numbers() {
+    for input in ["[-]", "[1.]", "[1e]", "[1e+]", "[-a]", "[1.2.3]", "[0x14]"] {
+        let mut json_parser = Parser::new(input).unwrap();
+        let result = json_parser.parse_root().unwrap_err();
+        assert_eq!(result.kind, ErrorKind::InvalidNumber, "{input}");
+    }
+    for input in ["[01]", "[-01]"] {
+        let mut json_parser = Parser::new(input).unwrap();
+        let result = json_parser.parse_root().unwrap_err();
+        assert_eq!(result.kind, ErrorKind::NumberWithLeadingZero, "{input}");
+    }
+}
+
+#[test]
+fn parse_empty_object_with_inner_whitespace() {
+    let mut json_parser = Parser::new("{ \n }").unwrap();
+    let JsonValue::Object(store) = json_parser.parse_root().unwrap() else {
+        unreachable!()
+    };
+    assert!(store.is_empty());
+}
+
+#[test]
+fn errors_carry_positions_and_spans() {
+    let mut json_parser = Parser::new("{\n  \"key\": \"v\\x\"\n}").unwrap();
+    let result = json_parser.parse_root().unwrap_err();
+    assert_eq!(result.kind, ErrorKind::InvalidBackslashEscape);
+    assert_eq!(result.line, Some(2));
+    assert_eq!(result.position.line, 2);
+    assert_eq!(result.position.column, 12);
+    assert_eq!(result.position.offset, 13);
+    assert_eq!(result.span.range(), 13..15);
+    assert_eq!(result.span.end.column, 14);
+
+    // Columns count characters, offsets count bytes.
+    let mut json_parser = Parser::new("[\"é🦀\", nul]").unwrap();
+    let result = json_parser.parse_root().unwrap_err();
+    assert_eq!(result.kind, ErrorKind::InvalidSyntax);
+    assert_eq!(result.position.column, 8);
+    assert_eq!(result.position.offset, 11);
+
+    let mut json_parser = Parser::new("[1] x").unwrap();
+    let result = json_parser.parse_root().unwrap_err();
+    assert_eq!(result.span.range(), 4..4);
+}
+
+#[test]
+fn empty_input_error_has_a_position() {
+    let result = Parser::new("").unwrap_err();
+    assert_eq!(result.line, Some(1));
+    assert_eq!(result.position.column, 1);
+    assert_eq!(result.position.offset, 0);
+}
+
+#[test]
+fn recover_and_report_every_error() {
+    let input = r#"{"a": [1 2, tru, {"b" 3}, "x\q"], "c": 01, "d": [1, 2"#;
+    let (value, errors) = Parser::new(input).unwrap().parse_root_with_recovery();
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        [
+            ErrorKind::InvalidSyntax,
+            ErrorKind::InvalidSyntax,
+            ErrorKind::MissingColon,
+            ErrorKind::InvalidBackslashEscape,
+            ErrorKind::NumberWithLeadingZero,
+            ErrorKind::UnclosedDelimiter(TokenType::RSqbracket),
+            ErrorKind::UnclosedDelimiter(TokenType::Rbrace),
+        ]
+    );
+    let expected = Parser::new(r#"{"a": [1, null, {"b": null}, null], "c": null, "d": [1, 2]}"#)
+        .unwrap()
+        .parse_root()
+        .unwrap();
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn recover_with_virtual_closers() {
+    let (value, errors) = Parser::new(r#"[{"a": [1, 2}, 3"#)
+        .unwrap()
+        .parse_root_with_recovery();
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        [
+            ErrorKind::UnclosedDelimiter(TokenType::RSqbracket),
+            ErrorKind::UnclosedDelimiter(TokenType::RSqbracket),
+        ]
+    );
+    let expected = Parser::new(r#"[{"a": [1, 2]}, 3]"#)
+        .unwrap()
+        .parse_root()
+        .unwrap();
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn recover_from_valid_input() {
+    let (value, errors) = Parser::new(include_str!("inputs/step3/valid3.json"))
+        .unwrap()
+        .parse_root_with_recovery();
+    assert!(errors.is_empty());
+    assert!(matches!(value, JsonValue::Object(_)));
+}
+
+#[test]
+fn reject_array_values_without_comma() {
+    let mut json_parser = Parser::new("[1 2]").unwrap();
+    let result = json_parser.parse_root().unwrap_err();
+    assert_eq!(result.kind, ErrorKind::InvalidSyntax);
+}
@@ -0,0 +1,320 @@
+use jimson::{
+    errors::{PatchErrorKind, PointerError},
+    parser::JsonValue,
+    patch::{Operation, Patch},
+    pointer::JsonPointer,
+};
+
+mod common;
+use common::parse;
+
+fn patched(document: &str, patch: &str) -> JsonValue {
+    let mut document = parse(document);
+    document.apply_patch(&parse(patch)).unwrap();
+    document
+}
+
+fn error(document: &str, patch: &str) -> (usize, PatchErrorKind) {
+    let mut document = parse(document);
+    let before = document.clone();
+    let e = document.apply_patch(&parse(patch)).unwrap_err();
+    assert_eq!(document, before, "a failed patch changes nothing");
+    (e.index, e.kind)
+}
+
+/// Checks that the patch computed between two documents turns one into
+/// the other, and survives its JSON form.
+fn round_trip(from: &str, to: &str) -> Patch {
+    let (from, to) = (parse(from), parse(to));
+    let patch = Patch::diff(&from, &to);
+    let reread = Patch::from_value(&patch.to_value()).unwrap();
+    assert_eq!(reread, patch);
+    let mut document = from.clone();
+    patch.apply(&mut document).unwrap();
+    assert_eq!(document, to);
+    patch
+}
+
+#[test]
+fn rfc_examples() {
+    assert_eq!(
+        patched(
+            r#"{"foo": "bar"}"#,
+            r#"[{"op": "add", "path": "/baz", "value": "qux"}]"#
+        ),
+        parse(r#"{"baz": "qux", "foo": "bar"}"#)
+    );
+    assert_eq!(
+        patched(
+            r#"{"foo": ["bar", "baz"]}"#,
+            r#"[{"op": "add", "path": "/foo/1", "value": "qux"}]"#
+        ),
+        parse(r#"{"foo": ["bar", "qux", "baz"]}"#)
+    );
+    assert_eq!(
+        patched(
+            r#"{"baz": "qux", "foo": "bar"}"#,
+            r#"[{"op": "remove", "path": "/baz"}]"#
+        ),
+        parse(r#"{"foo": "bar"}"#)
+    );
+    assert_eq!(
+        patched(
+            r#"{"foo": ["bar", "qux", "baz"]}"#,
+            r#"[{"op": "remove", "path": "/foo/1"}]"#
+        ),
+        parse(r#"{"foo": ["bar", "baz"]}"#)
+    );
+    assert_eq!(
+        patched(
+            r#"{"baz": "qux", "foo": "bar"}"#,
+            r#"[{"op": "replace", "path": "/baz", "value": "boo"}]"#
+        ),
+        parse(r#"{"baz": "boo", "foo": "bar"}"#)
+    );
+    assert_eq!(
+        patched(
+            r#"{"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}"#,
+            r#"[{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]"#
+        ),
+        parse(r#"{"foo": {"bar": "baz"}, "qux": {"corge": "grault", "thud": "fred"}}"#)
+    );
+    assert_eq!(
+        patched(
+            r#"{"foo": ["all", "grass", "cows", "eat"]}"#,
+            r#"[{"op": "move", "from": "/foo/1", "path": "/foo/3"}]"#
+        ),
+        parse(r#"{"foo": ["all", "cows", "eat", "grass"]}"#)
+    );
+    assert_eq!(
+        patched(
+            r#"{"baz": "qux", "foo": ["a", 2, "c"]}"#,
+            r#"[
+                {"op": "test", "path": "/baz", "value": "qux"},
+                {"op": "test", "path": "/foo/1", "value": 2}
+            ]"#
+        ),
+        parse(r#"{"baz": "qux", "foo": ["a", 2, "c"]}"#)
+    );
+    assert_eq!(
+        patched(
+            r#"{"foo": "bar"}"#,
+            r#"[{"op": "add", "path": "/child", "value": {"grandchild": {}}}]"#
+        ),
+        parse(r#"{"foo": "bar", "child": {"grandchild": {}}}"#)
+    );
+    assert_eq!(
+        patched(
+            r#"{"foo": "bar"}"#,
+            r#"[{"op": "add", "path": "/baz", "value": "qux", "xyz": 123}]"#
+        ),
+        parse(r#"{"foo": "bar", "baz": "qux"}"#)
+    );
+    assert_eq!(
+        patched(
+            r#"{"foo": ["bar"]}"#,
+            r#"[{"op": "add", "path": "/foo/-", "value": ["abc", "def"]}]"#
+        ),
+        parse(r#"{"foo": ["bar", ["abc", "def"]]}"#)
+    );
+    // `~1` and `~0` in paths, and numbers compared by value.
+    assert_eq!(
+        patched(
+            r#"{"/": 9, "~1": 10}"#,
+            r#"[
+                {"op": "test", "path": "/~01", "value": 10},
+                {"op": "test", "path": "/~1", "value": 9.0}
+            ]"#
+        ),
+        parse(r#"{"/": 9, "~1": 10}"#)
+    );
+}
+
+#[test]
+fn copy_and_whole_document() {
+    assert_eq!(
+        patched(
+            r#"{"a": {"b": [1]}}"#,
+            r#"[
+                {"op": "copy", "from": "/a", "path": "/c"},
+                {"op": "add", "path": "/c/b/0", "value": 0},
+                {"op": "test", "path": "/a", "value": {"b": [1]}}
+            ]"#
+        ),
+        parse(r#"{"a": {"b": [1]}, "c": {"b": [0, 1]}}"#)
+    );
+    assert_eq!(
+        patched(
+            r#"{"a": 1}"#,
+            r#"[{"op": "replace", "path": "", "value": [1]}]"#
+        ),
+        parse("[1]")
+    );
+    assert_eq!(
+        patched(
+            r#"{"a": {"b": 1}}"#,
+            r#"[{"op": "move", "from": "/a/b", "path": ""}]"#
+        ),
+        parse("1")
+    );
+    assert_eq!(patched("[1, 2]", "[]"), parse("[1, 2]"));
+    // Moving a value onto itself changes nothing.
+    assert_eq!(
+        patched(
+            r#"{"a": [1]}"#,
+            r#"[{"op": "move", "from": "/a", "path": "/a"}]"#
+        ),
+        parse(r#"{"a": [1]}"#)
+    );
+}
+
+#[test]
+fn failures_leave_the_document_alone() {
+    let document = r#"{"baz": "qux", "foo": ["a", 2, "c"]}"#;
+    assert_eq!(
+        error(
+            document,
+            r#"[
+                {"op": "remove", "path": "/baz"},
+                {"op": "test", "path": "/foo/1", "value": "2"}
+            ]"#
+        ),
+        (1, PatchErrorKind::TestFailed)
+    );
+    assert_eq!(
+        error(
+            document,
+            r#"[
+                {"op": "add", "path": "/x", "value": 1},
+                {"op": "add", "path": "/missing/x", "value": 1}
+            ]"#
+        ),
+        (1, PatchErrorKind::Pointer(PointerError::NotFound))
+    );
+    assert_eq!(
+        error(document, r#"[{"op": "add", "path": "/foo/4", "value": 1}]"#),
+        (0, PatchErrorKind::Pointer(PointerError::NotFound))
+    );
+    assert_eq!(
+        error(
+            document,
+            r#"[{"op": "replace", "path": "/nope", "value": 1}]"#
+        ),
+        (0, PatchErrorKind::Pointer(PointerError::NotFound))
+    );
+    assert_eq!(
+        error(document, r#"[{"op": "remove", "path": "/foo/01"}]"#),
+        (0, PatchErrorKind::Pointer(PointerError::InvalidIndex))
+    );
+    assert_eq!(
+        error(
+            document,
+            r#"[{"op": "move", "from": "/foo", "path": "/foo/0"}]"#
+        ),
+        (0, PatchErrorKind::MoveIntoChild)
+    );
+}
+
+#[test]
+fn malformed_patches() {
+    let read = |patch: &str| {
+        let e = Patch::from_value(&parse(patch)).unwrap_err();
+        (e.index, e.kind)
+    };
+    assert_eq!(read("{}"), (0, PatchErrorKind::NotAnArray));
+    assert_eq!(
+        read(r#"[{"op": "remove", "path": "/a"}, 1]"#),
+        (1, PatchErrorKind::NotAnObject)
+    );
+    assert_eq!(
+        read(r#"[{"path": "/a"}]"#),
+        (0, PatchErrorKind::MissingMember("op"))
+    );
+    assert_eq!(
+        read(r#"[{"op": "add", "path": "/a"}]"#),
+        (0, PatchErrorKind::MissingMember("value"))
+    );
+    assert_eq!(
+        read(r#"[{"op": "copy", "path": "/a"}]"#),
+        (0, PatchErrorKind::MissingMember("from"))
+    );
+    assert_eq!(
+        read(r#"[{"op": "remove", "path": 1}]"#),
+        (0, PatchErrorKind::InvalidMember("path"))
+    );
+    assert_eq!(
+        read(r#"[{"op": "delete", "path": "/a"}]"#),
+        (0, PatchErrorKind::UnknownOp("delete".into()))
+    );
+    assert_eq!(
+        read(r#"[{"op": "remove", "path": "a"}]"#),
+        (0, PatchErrorKind::Pointer(PointerError::MissingSlash))
+    );
+    let e = Patch::from_value(&parse(r#"[{"op": "x", "path": ""}]"#)).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "unknown op `x` in operation 0 of the JSON patch"
+    );
+}
+
+#[test]
+fn diff_touches_only_what_changed() {
+    assert!(round_trip(r#"{"a": [1, {"b": 2}]}"#, r#"{"a": [1, {"b": 2}]}"#).is_empty());
+    // Member order is not a change.
+    assert!(round_trip(r#"{"a": 1, "b": 2}"#, r#"{"b": 2, "a": 1}"#).is_empty());
+
+    let at = |path: &str| JsonPointer::parse(path).unwrap();
+    assert_eq!(
+        round_trip(
+            r#"{"keep": 1, "gone": 2, "deep": {"x": [1, 2], "y": true}}"#,
+            r#"{"keep": 1, "deep": {"x": [1, 2, 3], "y": false}, "new": null}"#,
+        )
+        .operations(),
+        [
+            Operation::Remove { path: at("/gone") },
+            Operation::Add {
+                path: at("/deep/x/2"),
+                value: parse("3")
+            },
+            Operation::Replace {
+                path: at("/deep/y"),
+                value: parse("false")
+            },
+            Operation::Add {
+                path: at("/new"),
+                value: JsonValue::Null
+            },
+        ]
+    );
+    // Elements that stay are not touched, even where others move around
+    // them.
+    assert_eq!(
+        round_trip(r#"["a", "b", "c", "d"]"#, r#"["x", "a", "c", "d", "e"]"#).operations(),
+        [
+            Operation::Add {
+                path: at("/0"),
+                value: parse(r#""x""#)
+            },
+            Operation::Remove { path: at("/2") },
+            Operation::Add {
+                path: at("/4"),
+                value: parse(r#""e""#)
+            },
+        ]
+    );
+    assert_eq!(
+        round_trip(r#"[{"id": 1, "n": 0}, 5]"#, r#"[{"id": 1, "n": 1}, 5]"#).operations(),
+        [Operation::Replace {
+            path: at("/0/n"),
+            value: parse("1")
+        }]
+    );
+
+    round_trip("[1, 2, 3, 4, 5, 6]", "[6, 5, 4, 3, 2, 1]");
+    round_trip(r#"[[1, 2], [3]]"#, r#"[[3], [1, 2], []]"#);
+    round_trip(r#"{"a": [1, 2]}"#, "[1, 2]");
+    round_trip(r#"{"a/b": {"~": 1}}"#, r#"{"a/b": {"~": 2, "~1": []}}"#);
+    round_trip("1", "1.0");
+    round_trip("[]", r#"[null, {"a": []}]"#);
+    round_trip(r#"[null, {"a": []}]"#, "[]");
+}
@@ -0,0 +1,126 @@
+use jimson::{errors::PointerError, number::Number, parser::JsonValue, pointer::JsonPointer};
+
+mod common;
+use common::parse;
+
+/// The example document of RFC 6901, section 5.
+const RFC_DOCUMENT: &str = r#"{
+    "foo": ["bar", "baz"],
+    "": 0,
+    "a/b": 1,
+    "c%d": 2,
+    "e^f": 3,
+    "g|h": 4,
+    "i\\j": 5,
+    "k\"l": 6,
+    " ": 7,
+    "m~n": 8
+}"#;
+
+#[test]
+fn rfc_examples() {
+    let document = parse(RFC_DOCUMENT);
+    assert_eq!(document.pointer("").unwrap(), &document);
+    assert_eq!(
+        document.pointer("/foo").unwrap(),
+        &parse(r#"["bar", "baz"]"#)
+    );
+    assert_eq!(
+        document.pointer("/foo/0").unwrap(),
+        &JsonValue::String("bar".into())
+    );
+    let examples = [
+        ("/", 0),
+        ("/a~1b", 1),
+        ("/c%d", 2),
+        ("/e^f", 3),
+        ("/g|h", 4),
+        ("/i\\j", 5),
+        ("/k\"l", 6),
+        ("/ ", 7),
+        ("/m~0n", 8),
+    ];
+    for (pointer, expected) in examples {
+        let found = document.pointer(pointer).unwrap();
+        assert_eq!(found.as_i64(), Some(expected), "{pointer}");
+    }
+}
+
+#[test]
+fn malformed_pointers_and_missing_paths() {
+    let document = parse(RFC_DOCUMENT);
+    assert_eq!(document.pointer("foo"), Err(PointerError::MissingSlash));
+    assert_eq!(
+        document.pointer("/foo/~2"),
+        Err(PointerError::InvalidEscape(5))
+    );
+    assert_eq!(document.pointer("/m~"), Err(PointerError::InvalidEscape(2)));
+    assert_eq!(document.pointer("/foo/01"), Err(PointerError::InvalidIndex));
+    assert_eq!(document.pointer("/foo/x"), Err(PointerError::InvalidIndex));
+    assert_eq!(document.pointer("/foo/2"), Err(PointerError::NotFound));
+    assert_eq!(document.pointer("/foo/-"), Err(PointerError::NotFound));
+    assert_eq!(
+        document.pointer("/foo/99999999999999999999999"),
+        Err(PointerError::NotFound)
+    );
+    assert_eq!(document.pointer("/missing"), Err(PointerError::NotFound));
+    assert_eq!(document.pointer("/a~1b/c"), Err(PointerError::NotFound));
+}
+
+#[test]
+fn set_and_remove() {
+    let mut value = parse(r#"{"list": [1, 2], "obj": {"a": 1}}"#);
+    let one = JsonValue::Number(Number::from(1u64));
+
+    assert_eq!(value.set_pointer("/list/-", one.clone()), Ok(None));
+    assert_eq!(value.set_pointer("/list/3", one.clone()), Ok(None));
+    assert_eq!(
+        value.set_pointer("/list/5", one.clone()),
+        Err(PointerError::NotFound)
+    );
+    assert_eq!(
+        value.set_pointer("/list/0", JsonValue::Null),
+        Ok(Some(one.clone()))
+    );
+    assert_eq!(value.set_pointer("/obj/b", JsonValue::Null), Ok(None));
+    assert_eq!(
+        value.set_pointer("/nope/b", JsonValue::Null),
+        Err(PointerError::NotFound)
+    );
+    assert_eq!(
+        value,
+        parse(r#"{"list": [null, 2, 1, 1], "obj": {"a": 1, "b": null}}"#)
+    );
+
+    assert_eq!(value.remove_pointer("/obj/a"), Ok(one.clone()));
+    assert_eq!(
+        value.remove_pointer("/list/1"),
+        Ok(JsonValue::Number(2u64.into()))
+    );
+    assert_eq!(value.remove_pointer("/list/-"), Err(PointerError::NotFound));
+    assert_eq!(value.remove_pointer(""), Err(PointerError::RemoveRoot));
+    assert_eq!(
+        value,
+        parse(r#"{"list": [null, 1, 1], "obj": {"b": null}}"#)
+    );
+
+    *value.pointer_mut("/obj/b").unwrap() = one.clone();
+    assert_eq!(value.pointer("/obj/b"), Ok(&one));
+    assert!(value.set_pointer("", JsonValue::Null).unwrap().is_some());
+    assert_eq!(value, JsonValue::Null);
+}
+
+#[test]
+fn pointers_round_trip_through_text() {
+    for text in ["", "/", "/a~1b/0", "/m~0n/-", "//x"] {
+        let pointer = JsonPointer::parse(text).unwrap();
+        assert_eq!(pointer.to_string(), text);
+    }
+    let mut pointer = JsonPointer::root();
+    pointer.push("a/b");
+    pointer.push("~");
+    assert_eq!(pointer.to_string(), "/a~1b/~0");
+    let (parent, last) = pointer.split_last().unwrap();
+    assert_eq!((parent.to_string().as_str(), last), ("/a~1b", "~"));
+    assert!(JsonPointer::root().split_last().is_none());
+}
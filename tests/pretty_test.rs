@@ -0,0 +1,81 @@
+use jimson::pretty::{to_string_pretty, to_writer_pretty, Indent, PrettyConfig};
+
+mod common;
+use common::parse;
+
+const DOC: &str = r#"{"name": "jimson", "tags": ["json", "parser"], "deps": {}, "meta": {"b": 1, "a": [true, null]}}"#;
+
+#[test]
+fn pretty_print_with_defaults() {
+    let out = to_string_pretty(&parse(DOC), &PrettyConfig::default()).unwrap();
+    let expected = r#"{
+  "name": "jimson",
+  "tags": [
+    "json",
+    "parser"
+  ],
+  "deps": {},
+  "meta": {
+    "b": 1,
+    "a": [
+      true,
+      null
+    ]
+  }
+}
+"#;
+    assert_eq!(out, expected);
+    assert_eq!(parse(&out), parse(DOC));
+}
+
+#[test]
+fn pretty_print_with_tabs_sorted_keys_and_no_colon_space() {
+    let config = PrettyConfig::new()
+        .indent(Indent::Tab)
+        .sort_keys(true)
+        .space_after_colon(false)
+        .trailing_newline(false);
+    let out = to_string_pretty(&parse(r#"{"b": {"d": 1, "c": 2}, "a": []}"#), &config).unwrap();
+    assert_eq!(
+        out,
+        "{\n\t\"a\":[],\n\t\"b\":{\n\t\t\"c\":2,\n\t\t\"d\":1\n\t}\n}"
+    );
+}
+
+#[test]
+fn pretty_print_keeps_short_containers_inline() {
+    let config = PrettyConfig::new()
+        .indent(Indent::Spaces(4))
+        .max_width(Some(40));
+    let expected = r#"{
+    "name": "jimson",
+    "tags": ["json", "parser"],
+    "deps": {},
+    "meta": {"b": 1, "a": [true, null]}
+}
+"#;
+    assert_eq!(to_string_pretty(&parse(DOC), &config).unwrap(), expected);
+
+    let config = PrettyConfig::new().max_width(Some(80));
+    assert_eq!(
+        to_string_pretty(&parse("[1, [2, 3]]"), &config).unwrap(),
+        "[1, [2, 3]]\n"
+    );
+}
+
+#[test]
+fn pretty_print_ensure_ascii() {
+    let value = parse(r#"{"café": "🦀 ok"}"#);
+    let config = PrettyConfig::new().ensure_ascii(true);
+    let out = to_string_pretty(&value, &config).unwrap();
+    assert_eq!(out, "{\n  \"caf\\u00e9\": \"\\ud83e\\udd80 ok\"\n}\n");
+    assert!(out.is_ascii());
+    assert_eq!(parse(&out), value);
+}
+
+#[test]
+fn pretty_print_to_io_writer() {
+    let mut out = Vec::new();
+    to_writer_pretty(&mut out, &parse("[1]"), &PrettyConfig::default()).unwrap();
+    assert_eq!(out, b"[\n  1\n]\n");
+}
@@ -0,0 +1,164 @@
+use jimson::{
+    errors::{ErrorKind, JsonError},
+    event::{Event, EventKind},
+    options::{Dialect, ParserOptions},
+    parser::{JsonValue, Parser},
+    push::{PushParser, Pushed},
+};
+
+/// Pushes `input` in pieces of `step` bytes and returns the value.
+fn push(input: &[u8], step: usize, options: ParserOptions) -> Result<JsonValue, JsonError> {
+    let mut parser = PushParser::with_options(options);
+    for piece in input.chunks(step) {
+        parser.feed(piece)?;
+        if let Some(value) = parser.value()? {
+            return Ok(value);
+        }
+    }
+    parser.finish()?;
+    Ok(parser.value()?.unwrap())
+}
+
+/// The next event, which must be ready.
+fn next(parser: &mut PushParser) -> Event<'_> {
+    match parser.next_event().unwrap() {
+        Pushed::Event(event) => event,
+        other => panic!("expected an event, got {other:?}"),
+    }
+}
+
+#[test]
+fn tokens_split_across_pieces() {
+    let input = r#"{"café": ["é😀", -12.5e3, true, null, "\u00e9\ud83d\ude00"]}"#;
+    let expected = Parser::new(input).unwrap().parse_root().unwrap();
+    for step in 1..8 {
+        let value = push(input.as_bytes(), step, ParserOptions::default()).unwrap();
+        assert_eq!(value, expected, "step {step}");
+    }
+
+    let input = "// head\n{a: 'x', b: [0x1F,],}";
+    let options = ParserOptions::new().dialect(Dialect::Json5);
+    let expected = Parser::with_options(input, options.clone())
+        .unwrap()
+        .parse_root()
+        .unwrap();
+    assert_eq!(push(input.as_bytes(), 1, options).unwrap(), expected);
+}
+
+#[test]
+fn events_come_out_as_input_arrives() {
+    let mut parser = PushParser::new();
+    parser.feed(br#"{"a": [tr"#).unwrap();
+    let mut kinds = Vec::new();
+    while let Pushed::Event(event) = parser.next_event().unwrap() {
+        kinds.push(format!("{:?}", event.kind));
+    }
+    assert_eq!(kinds, ["StartObject", "Key(\"a\")", "StartArray"]);
+
+    parser.feed(b"ue]").unwrap();
+    assert_eq!(next(&mut parser).kind, EventKind::Bool(true));
+    assert_eq!(parser.next_event().unwrap(), Pushed::NeedMore);
+
+    parser.feed(b"}").unwrap();
+    parser.finish().unwrap();
+    let event = next(&mut parser);
+    assert_eq!(event.kind, EventKind::EndArray);
+    assert_eq!(event.span.start.offset, 11);
+    assert_eq!(next(&mut parser).kind, EventKind::EndObject);
+    assert_eq!(parser.next_event().unwrap(), Pushed::Done);
+    assert_eq!(parser.next_event().unwrap(), Pushed::Done);
+
+    // A whole document still needs the end of the input, which might
+    // bring trailing content.
+    let mut parser = PushParser::new();
+    parser.feed(b"[]").unwrap();
+    assert_eq!(next(&mut parser).kind, EventKind::StartArray);
+    assert_eq!(parser.next_event().unwrap(), Pushed::NeedMore);
+    parser.finish().unwrap();
+    assert_eq!(next(&mut parser).kind, EventKind::EndArray);
+    assert_eq!(parser.next_event().unwrap(), Pushed::Done);
+}
+
+#[test]
+fn errors_match_the_one_shot_parser() {
+    for input in ["[1, 2", "{\"a\" 1}", "[1] x", "  ", "\"abc"] {
+        let expected = Parser::new(input).unwrap().parse_root().unwrap_err();
+        let e = push(input.as_bytes(), 1, ParserOptions::default()).unwrap_err();
+        assert_eq!((e.kind, e.span), (expected.kind, expected.span), "{input}");
+    }
+}
+
+#[test]
+fn bad_input_is_rejected() {
+    let e = PushParser::new().finish().unwrap_err();
+    assert_eq!(e.kind, ErrorKind::EmptyInput);
+
+    let mut parser = PushParser::new();
+    parser.feed(b"\"\xc3").unwrap();
+    let e = parser.feed(b"(\"").unwrap_err();
+    assert_eq!(
+        e.kind,
+        ErrorKind::Io(std::io::ErrorKind::InvalidData.into())
+    );
+
+    let mut parser = PushParser::new();
+    parser.feed(b"\"\xc3").unwrap();
+    let e = parser.finish().unwrap_err();
+    assert_eq!(
+        e.kind,
+        ErrorKind::Io(std::io::ErrorKind::InvalidData.into())
+    );
+
+    let options = ParserOptions::new().max_input_size(Some(4));
+    let e = push(b"[1, 2]", 2, options).unwrap_err();
+    assert_eq!(e.kind, ErrorKind::InputTooLarge);
+}
+
+#[test]
+fn long_tokens_in_small_pieces() {
+    let long = "x".repeat(200_000);
+    let input = format!(
+        "// {long}\n[\"{long}\\\"\\\\\", /* {long} */ {}1.5, 1.{}]",
+        " ".repeat(200_000),
+        "0".repeat(200_000)
+    );
+    let options = ParserOptions::new().dialect(Dialect::Jsonc);
+    let expected = Parser::with_options(&input, options.clone())
+        .unwrap()
+        .parse_root()
+        .unwrap();
+    assert_eq!(push(input.as_bytes(), 16, options).unwrap(), expected);
+
+    // The string comes out once the token after it has arrived.
+    let mut parser = PushParser::new();
+    parser.feed(b"[\"").unwrap();
+    for _ in 0..1000 {
+        parser.feed(b"\\\"abc").unwrap();
+    }
+    parser.feed(b"\"").unwrap();
+    assert_eq!(next(&mut parser).kind, EventKind::StartArray);
+    assert_eq!(parser.next_event().unwrap(), Pushed::NeedMore);
+    parser.feed(b",").unwrap();
+    let event = next(&mut parser);
+    assert!(matches!(event.kind, EventKind::String(s) if s.len() == 4000));
+
+    for input in [
+        "[\"a\u{1}bc\", \"d\\qe\", 1]",
+        "[\"abc\ndef\"]",
+        "[1 /* x *",
+    ] {
+        let options = ParserOptions::new().dialect(Dialect::Jsonc);
+        let expected = Parser::with_options(input, options.clone())
+            .unwrap()
+            .parse_root()
+            .unwrap_err();
+        for step in 1..4 {
+            let e = push(input.as_bytes(), step, options.clone()).unwrap_err();
+            assert_eq!(
+                (&e.kind, e.span),
+                (&expected.kind, expected.span),
+                "{input}"
+            );
+        }
+    }
+}
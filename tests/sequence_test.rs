@@ -0,0 +1,60 @@
+use jimson::{
+    errors::ErrorKind,
+    number::Number,
+    parser::{JsonValue, Parser},
+    sequence::StreamDeserializer,
+};
+
+fn number(n: u64) -> JsonValue {
+    JsonValue::Number(Number::from(n))
+}
+
+#[test]
+fn concatenated_values() {
+    let input = "{\"a\":1}[2]\n\"three\" 4 null";
+    let items: Vec<_> = StreamDeserializer::new(Parser::new(input).unwrap())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let ends: Vec<_> = items.iter().map(|(_, end)| *end).collect();
+    assert_eq!(ends, [7, 10, 18, 20, 25]);
+    assert_eq!(items[1].0, JsonValue::Array(vec![number(2)]));
+    assert_eq!(items[3].0, number(4));
+
+    // The same from a reader.
+    let parser = Parser::from_reader(input.as_bytes()).unwrap();
+    let from_reader: Vec<_> = StreamDeserializer::new(parser)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(from_reader, items);
+}
+
+#[test]
+fn an_error_ends_concatenated_values() {
+    let mut values = StreamDeserializer::new(Parser::new("[1] [2,] [3]").unwrap());
+    assert_eq!(values.next().unwrap().unwrap().1, 3);
+    assert_eq!(values.byte_offset(), 3);
+    let e = values.next().unwrap().unwrap_err();
+    assert_eq!(e.kind, ErrorKind::TrailingComma);
+    assert_eq!(e.span.start.offset, 6);
+    assert!(values.next().is_none());
+    assert_eq!(values.byte_offset(), 3);
+}
+
+#[test]
+fn json_text_sequences() {
+    let input = "\x1e{\"a\": 1}\n\x1e\x1e[2, \n\x1e\"cut\x1e3\n\x1e4";
+    let items: Vec<_> = StreamDeserializer::json_seq(input)
+        .map(|item| item.map_err(|e| (e.kind, e.span.start.offset, e.span.start.line)))
+        .collect();
+    assert_eq!(
+        items,
+        [
+            Ok((Parser::new("{\"a\": 1}").unwrap().parse_root().unwrap(), 9)),
+            // Ends with a line feed, but the array is not done.
+            Err((ErrorKind::Eof, 17, 3)),
+            Err((ErrorKind::TruncatedRecord, 18, 3)),
+            Ok((number(3), 24)),
+            Err((ErrorKind::TruncatedRecord, 26, 4)),
+        ]
+    );
+}
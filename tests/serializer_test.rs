@@ -0,0 +1,68 @@
+use jimson::{
+    errors::SerializeError,
+    map::Map,
+    number::Number,
+    parser::JsonValue,
+    serializer::{to_string, to_writer},
+};
+
+mod common;
+use common::parse;
+
+#[test]
+fn serialize_compact() {
+    let value = parse(r#"{ "b": [1, -2, 3.5, 1e3], "a": { "x": null, "y": true, "z": false } }"#);
+    assert_eq!(
+        to_string(&value).unwrap(),
+        r#"{"b":[1,-2,3.5,1000.0],"a":{"x":null,"y":true,"z":false}}"#
+    );
+    assert_eq!(value.to_string(), to_string(&value).unwrap());
+}
+
+#[test]
+fn serialize_integers_exactly() {
+    let value = parse("[0, 18446744073709551615, -9223372036854775808, 2.0]");
+    assert_eq!(
+        to_string(&value).unwrap(),
+        "[0,18446744073709551615,-9223372036854775808,2.0]"
+    );
+}
+
+#[test]
+fn serialize_negative_zero() {
+    let value = parse("[-0, -0.0, 0]");
+    assert_eq!(to_string(&value).unwrap(), "[-0.0,-0.0,0]");
+    assert_eq!(parse(&to_string(&value).unwrap()), value);
+}
+
+#[test]
+fn serialize_string_escapes() {
+    let value = JsonValue::String("\"q\" \\ / \u{8}\u{c}\n\r\t \u{1} é 🦀".into());
+    assert_eq!(
+        to_string(&value).unwrap(),
+        r#""\"q\" \\ / \b\f\n\r\t \u0001 é 🦀""#
+    );
+    assert_eq!(parse(&to_string(&value).unwrap()), value);
+}
+
+#[test]
+fn serialize_non_finite_float() {
+    for f in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        let mut map = Map::new();
+        map.insert("f".into(), JsonValue::Number(Number::from(f)));
+        let value = JsonValue::Array(vec![JsonValue::Object(map)]);
+        assert!(matches!(
+            to_string(&value),
+            Err(SerializeError::NonFiniteFloat(_))
+        ));
+    }
+    assert_eq!(Number::from_f64(f64::NAN), None);
+}
+
+#[test]
+fn serialize_to_io_writer() {
+    let value = parse(r#"{"key": ["value", 1]}"#);
+    let mut out = Vec::new();
+    to_writer(&mut out, &value).unwrap();
+    assert_eq!(out, br#"{"key":["value",1]}"#);
+}
@@ -0,0 +1,141 @@
+use std::io::{BufReader, Cursor, Read};
+
+use jimson::{
+    errors::ErrorKind,
+    options::{Dialect, ParserOptions},
+    parser::{JsonValue, Parser},
+};
+
+/// Hands out one byte per read, then fails once the input runs out if
+/// `fail` is set.
+struct Bytewise<'a> {
+    input: &'a [u8],
+    fail: bool,
+}
+
+impl Read for Bytewise<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.input.split_first() {
+            Some((byte, rest)) => {
+                buf[0] = *byte;
+                self.input = rest;
+                Ok(1)
+            }
+            None if self.fail => Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "peer went away",
+            )),
+            None => Ok(0),
+        }
+    }
+}
+
+fn from_str(input: &str) -> Result<JsonValue, (ErrorKind, usize, usize)> {
+    Parser::new(input)
+        .and_then(|mut p| p.parse_root())
+        .map_err(|e| (e.kind, e.span.start.line, e.span.start.column))
+}
+
+fn from_bytes(input: &[u8]) -> Result<JsonValue, (ErrorKind, usize, usize)> {
+    let reader = Bytewise { input, fail: false };
+    Parser::from_reader(reader)
+        .and_then(|mut p| p.parse_root())
+        .map_err(|e| (e.kind, e.span.start.line, e.span.start.column))
+}
+
+#[test]
+fn read_split_characters_and_tokens() {
+    let input =
+        "{\"ünïcödé 🦀\": [true, null, -12.5e3, \"\\ud83e\\udd80\"],\n \"k\": 1234567890123}";
+    assert_eq!(from_bytes(input.as_bytes()), from_str(input));
+    assert!(from_bytes(input.as_bytes()).is_ok());
+
+    let broken = "[1,\n  \"é\" \"🦀\"]";
+    assert_eq!(from_bytes(broken.as_bytes()), from_str(broken));
+    assert_eq!(
+        from_bytes(broken.as_bytes()),
+        Err((ErrorKind::InvalidSyntax, 2, 7))
+    );
+}
+
+#[test]
+fn read_long_strings_through_a_bufreader() {
+    let long = "x".repeat(100_000);
+    let input = format!("[\"{}\", {}]", long, "1".repeat(30));
+    let reader = BufReader::with_capacity(7, Cursor::new(input.as_bytes()));
+    let value = Parser::from_reader(reader).unwrap().parse_root().unwrap();
+    assert_eq!(Ok(value), from_str(&input));
+}
+
+#[test]
+fn read_failures() {
+    assert_eq!(
+        Parser::from_reader(Cursor::new(b"")).unwrap_err().kind,
+        ErrorKind::EmptyInput
+    );
+
+    let reader = Bytewise {
+        input: b"[1,\n 2",
+        fail: true,
+    };
+    let error = Parser::from_reader(reader)
+        .unwrap()
+        .parse_root()
+        .unwrap_err();
+    assert_eq!(
+        error.kind,
+        ErrorKind::Io(std::io::ErrorKind::ConnectionReset.into())
+    );
+    // The error points at where the input stopped.
+    assert_eq!((error.span.start.line, error.span.start.column), (2, 3));
+    assert_eq!(
+        error.to_string(),
+        "error at line 2, column 3: failed to read input, peer went away"
+    );
+
+    // In recovery mode the parser gives up at the failure.
+    let reader = Bytewise {
+        input: b"[1, 2",
+        fail: true,
+    };
+    let (_, errors) = Parser::from_reader(reader)
+        .unwrap()
+        .parse_root_with_recovery();
+    assert_eq!(
+        errors[0].kind,
+        ErrorKind::Io(std::io::ErrorKind::ConnectionReset.into())
+    );
+
+    for invalid in [&b"[\"\xff\"]"[..], b"[\"\xc3"] {
+        let error = from_bytes(invalid).unwrap_err();
+        assert_eq!(
+            error.0,
+            ErrorKind::Io(std::io::ErrorKind::InvalidData.into())
+        );
+    }
+}
+
+#[test]
+fn read_with_options() {
+    let input = "// comment\n{unquoted: 'single', trailing: [0x10,],}";
+    let options = ParserOptions::new().dialect(Dialect::Json5);
+    let reader = Bytewise {
+        input: input.as_bytes(),
+        fail: false,
+    };
+    let from_reader = Parser::from_reader_with_options(reader, options.clone())
+        .unwrap()
+        .parse_root()
+        .unwrap();
+    let from_str = Parser::with_options(input, options)
+        .unwrap()
+        .parse_root()
+        .unwrap();
+    assert_eq!(from_reader, from_str);
+
+    let options = ParserOptions::new().max_input_size(Some(4));
+    let error = Parser::from_reader_with_options(Cursor::new(b"[1, 2]"), options)
+        .and_then(|mut p| p.parse_root())
+        .unwrap_err();
+    assert_eq!(error.kind, ErrorKind::InputTooLarge);
+}